@@ -0,0 +1,27 @@
+-- 细粒度权限：角色默认权限 + 单个管理员额外授予的权限
+-- super_admin 始终拥有全部权限，不需要写入 role_permissions
+CREATE TABLE IF NOT EXISTS role_permissions (
+    role VARCHAR(32) NOT NULL,
+    permission VARCHAR(64) NOT NULL,
+    PRIMARY KEY (role, permission)
+);
+
+CREATE TABLE IF NOT EXISTS admin_permissions (
+    admin_id BIGINT NOT NULL,
+    permission VARCHAR(64) NOT NULL,
+    PRIMARY KEY (admin_id, permission),
+    FOREIGN KEY (admin_id) REFERENCES admins(id) ON DELETE CASCADE
+);
+
+-- 普通管理员的默认权限，与原先硬编码的行为保持一致
+INSERT IGNORE INTO role_permissions (role, permission) VALUES
+    ('admin', 'bans.view'),
+    ('admin', 'bans.create'),
+    ('admin', 'bans.edit'),
+    ('admin', 'bans.check'),
+    ('admin', 'servers.view'),
+    ('admin', 'servers.rcon'),
+    ('admin', 'whitelist.view'),
+    ('admin', 'whitelist.manage'),
+    ('admin', 'whitelist.review'),
+    ('admin', 'logs.create');
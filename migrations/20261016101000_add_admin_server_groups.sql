@@ -0,0 +1,13 @@
+-- 管理员与服务器组的绑定关系
+-- 没有任何绑定的管理员保持全局权限（兼容旧数据），super_admin 始终为全局
+CREATE TABLE IF NOT EXISTS admin_server_groups (
+    admin_id BIGINT NOT NULL,
+    group_id BIGINT NOT NULL,
+    PRIMARY KEY (admin_id, group_id),
+    FOREIGN KEY (admin_id) REFERENCES admins(id) ON DELETE CASCADE,
+    FOREIGN KEY (group_id) REFERENCES server_groups(id) ON DELETE CASCADE
+);
+
+-- 记录封禁所属的服务器组，受限管理员只能编辑本组的封禁
+ALTER TABLE bans ADD COLUMN group_id BIGINT NULL;
+ALTER TABLE bans ADD CONSTRAINT fk_bans_group_id FOREIGN KEY (group_id) REFERENCES server_groups(id) ON DELETE SET NULL;
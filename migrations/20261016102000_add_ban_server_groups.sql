@@ -0,0 +1,9 @@
+-- 封禁的生效范围：没有记录的封禁为全局封禁，否则仅在列出的服务器组内生效
+CREATE TABLE IF NOT EXISTS ban_server_groups (
+    ban_id BIGINT NOT NULL,
+    group_id BIGINT NOT NULL,
+    PRIMARY KEY (ban_id, group_id),
+    INDEX idx_group_id (group_id),
+    FOREIGN KEY (ban_id) REFERENCES bans(id) ON DELETE CASCADE,
+    FOREIGN KEY (group_id) REFERENCES server_groups(id) ON DELETE CASCADE
+);
@@ -0,0 +1,20 @@
+-- TOTP 两步验证：密钥在确认绑定前 totp_enabled 保持为 FALSE
+ALTER TABLE admins
+    ADD COLUMN totp_secret VARCHAR(64) NULL,
+    ADD COLUMN totp_enabled BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- 恢复码只保存 SHA-256 哈希，每个只能使用一次
+CREATE TABLE IF NOT EXISTS admin_recovery_codes (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    admin_id BIGINT NOT NULL,
+    code_hash CHAR(64) NOT NULL,
+    used_at TIMESTAMP NULL,
+    INDEX idx_recovery_admin (admin_id),
+    FOREIGN KEY (admin_id) REFERENCES admins(id) ON DELETE CASCADE
+);
+
+-- 按角色强制启用两步验证
+CREATE TABLE IF NOT EXISTS role_settings (
+    role VARCHAR(32) NOT NULL PRIMARY KEY,
+    require_2fa BOOLEAN NOT NULL DEFAULT FALSE
+);
@@ -0,0 +1,13 @@
+-- 首次登录强制改密：置位后只能访问改密接口
+ALTER TABLE admins
+    ADD COLUMN must_change_password BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- 历史密码哈希，用于禁止重复使用最近的密码
+CREATE TABLE IF NOT EXISTS admin_password_history (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    admin_id BIGINT NOT NULL,
+    password_hash VARCHAR(256) NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    INDEX idx_password_history_admin (admin_id, id),
+    FOREIGN KEY (admin_id) REFERENCES admins(id) ON DELETE CASCADE
+);
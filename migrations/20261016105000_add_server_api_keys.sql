@@ -0,0 +1,15 @@
+-- 服务器插件 API Key：只保存 SHA-256 哈希，key_prefix 用于在面板中辨认
+CREATE TABLE IF NOT EXISTS server_api_keys (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    server_id BIGINT NOT NULL,
+    key_prefix VARCHAR(16) NOT NULL,
+    key_hash CHAR(64) NOT NULL UNIQUE,
+    scopes VARCHAR(255) NOT NULL,
+    created_by VARCHAR(64) NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    last_used_at TIMESTAMP NULL,
+    last_used_ip VARCHAR(45) NULL,
+    revoked_at TIMESTAMP NULL,
+    INDEX idx_api_keys_server (server_id),
+    FOREIGN KEY (server_id) REFERENCES servers(id) ON DELETE CASCADE
+);
@@ -0,0 +1,30 @@
+-- 封禁申诉：玩家通过封禁 ID + SteamID 提交，管理员评论并裁决
+CREATE TABLE IF NOT EXISTS appeals (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    ban_id BIGINT NOT NULL,
+    steam_id VARCHAR(64) NOT NULL,
+    contact VARCHAR(255) NULL,
+    reason TEXT NOT NULL,
+    status ENUM('open', 'under_review', 'accepted', 'denied') NOT NULL DEFAULT 'open',
+    decided_by VARCHAR(64) NULL,
+    decision_note TEXT NULL,
+    decided_at TIMESTAMP NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    INDEX idx_appeals_ban (ban_id),
+    INDEX idx_appeals_status (status),
+    FOREIGN KEY (ban_id) REFERENCES bans(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS appeal_comments (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    appeal_id BIGINT NOT NULL,
+    author VARCHAR(64) NOT NULL,
+    content TEXT NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (appeal_id) REFERENCES appeals(id) ON DELETE CASCADE
+);
+
+-- 普通管理员默认可以查看并处理申诉
+INSERT IGNORE INTO role_permissions (role, permission) VALUES
+    ('admin', 'appeals.view'),
+    ('admin', 'appeals.review');
@@ -0,0 +1,24 @@
+-- 禁言处罚：mute（语音）、gag（文字）、silence（两者）
+CREATE TABLE IF NOT EXISTS comm_blocks (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    steam_id VARCHAR(64) NOT NULL,
+    steam_id_3 VARCHAR(64) NULL,
+    steam_id_64 VARCHAR(64) NULL,
+    block_type ENUM('mute', 'gag', 'silence') NOT NULL,
+    reason TEXT NULL,
+    duration VARCHAR(64) NOT NULL,
+    status ENUM('active', 'expired', 'removed') NOT NULL DEFAULT 'active',
+    admin_name VARCHAR(255) NULL,
+    server_id BIGINT NULL,
+    group_id BIGINT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMP NULL,
+    INDEX idx_comm_blocks_steam (steam_id_64, status),
+    FOREIGN KEY (server_id) REFERENCES servers(id) ON DELETE SET NULL,
+    FOREIGN KEY (group_id) REFERENCES server_groups(id) ON DELETE SET NULL
+);
+
+INSERT IGNORE INTO role_permissions (role, permission) VALUES
+    ('admin', 'comms.view'),
+    ('admin', 'comms.manage');
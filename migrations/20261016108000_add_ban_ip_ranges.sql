@@ -0,0 +1,13 @@
+-- IP 封禁支持 CIDR / 区间：以 16 字节地址存储范围（IPv4 映射为 ::ffff:a.b.c.d），便于范围查询
+ALTER TABLE bans ADD COLUMN ip_start VARBINARY(16) NULL;
+ALTER TABLE bans ADD COLUMN ip_end VARBINARY(16) NULL;
+CREATE INDEX idx_bans_ip_range ON bans (ip_start, ip_end);
+
+-- 回填已有的单 IP 封禁
+UPDATE bans SET
+    ip_start = CASE
+        WHEN IS_IPV4(ip) THEN INET6_ATON(CONCAT('::ffff:', ip))
+        WHEN IS_IPV6(ip) THEN INET6_ATON(ip)
+    END,
+    ip_end = ip_start
+WHERE ban_type = 'ip';
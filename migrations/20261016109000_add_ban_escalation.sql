@@ -0,0 +1,22 @@
+-- 封禁原因分类与升级阶梯：同一分类下的第 N 次违规使用第 N 级时长（超出后停留在最后一级）
+CREATE TABLE IF NOT EXISTS ban_categories (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    name VARCHAR(64) NOT NULL UNIQUE,
+    description TEXT NULL,
+    -- 只统计最近 N 天内的前科，NULL 表示不限
+    lookback_days INT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS ban_category_steps (
+    category_id BIGINT NOT NULL,
+    step INT NOT NULL,
+    duration VARCHAR(64) NOT NULL,
+    PRIMARY KEY (category_id, step),
+    FOREIGN KEY (category_id) REFERENCES ban_categories(id) ON DELETE CASCADE
+);
+
+ALTER TABLE bans ADD COLUMN category_id BIGINT NULL;
+ALTER TABLE bans ADD COLUMN ladder_step INT NULL;
+ALTER TABLE bans ADD CONSTRAINT fk_bans_category_id FOREIGN KEY (category_id) REFERENCES ban_categories(id) ON DELETE SET NULL;
+CREATE INDEX idx_bans_category_steam ON bans (category_id, steam_id_64);
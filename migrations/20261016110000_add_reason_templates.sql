@@ -0,0 +1,13 @@
+-- 封禁/踢出原因模板：统一措辞，便于统计
+CREATE TABLE IF NOT EXISTS reason_templates (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    code VARCHAR(32) NOT NULL UNIQUE,
+    text_zh VARCHAR(255) NOT NULL,
+    text_en VARCHAR(255) NOT NULL,
+    default_duration VARCHAR(64) NULL,
+    default_ban_type VARCHAR(16) NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+ALTER TABLE bans ADD COLUMN reason_template_id BIGINT NULL;
+ALTER TABLE bans ADD CONSTRAINT fk_bans_reason_template_id FOREIGN KEY (reason_template_id) REFERENCES reason_templates(id) ON DELETE SET NULL;
@@ -0,0 +1,22 @@
+-- 封禁证据：demo、截图、聊天记录等，文件按 SHA-256 存放在本地磁盘
+CREATE TABLE IF NOT EXISTS ban_evidence (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    ban_id BIGINT NOT NULL,
+    file_name VARCHAR(255) NOT NULL,
+    content_type VARCHAR(64) NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    sha256 CHAR(64) NOT NULL,
+    description TEXT NULL,
+    -- 是否在公开封禁列表中展示下载链接
+    is_public BOOLEAN NOT NULL DEFAULT FALSE,
+    uploaded_by VARCHAR(64) NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    INDEX idx_ban_evidence_ban (ban_id),
+    INDEX idx_ban_evidence_sha (sha256),
+    FOREIGN KEY (ban_id) REFERENCES bans(id) ON DELETE CASCADE
+);
+
+-- 普通管理员默认可以查看与上传证据
+INSERT IGNORE INTO role_permissions (role, permission) VALUES
+    ('admin', 'evidence.view'),
+    ('admin', 'evidence.manage');
@@ -0,0 +1,37 @@
+-- 封禁修改历史：每次修改/到期/解封都记录修改前后的字段，支持回滚到任一版本
+CREATE TABLE IF NOT EXISTS ban_revisions (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    ban_id BIGINT NOT NULL,
+    revision INT NOT NULL,
+    change_type VARCHAR(32) NOT NULL,
+    changed_by VARCHAR(64) NOT NULL,
+    note VARCHAR(255) NULL,
+    -- [{"field": ..., "before": ..., "after": ...}]
+    changes LONGTEXT NOT NULL,
+    -- 本次修改后的完整状态
+    snapshot LONGTEXT NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE KEY uq_ban_revisions (ban_id, revision),
+    FOREIGN KEY (ban_id) REFERENCES bans(id) ON DELETE CASCADE
+);
+
+-- 已有封禁以当前状态作为第 1 版，之后的修改才有可回滚的基线
+INSERT INTO ban_revisions (ban_id, revision, change_type, changed_by, note, changes, snapshot, created_at)
+SELECT
+    b.id, 1, 'create', COALESCE(b.admin_name, 'System'), 'Recorded when revision history was introduced', '[]',
+    JSON_OBJECT(
+        'name', b.name,
+        'steam_id', b.steam_id,
+        'ip', b.ip,
+        'ban_type', b.ban_type,
+        'reason', b.reason,
+        'duration', b.duration,
+        'expires_at', DATE_FORMAT(b.expires_at, '%Y-%m-%dT%H:%i:%sZ'),
+        'status', b.status,
+        'server_group_ids', COALESCE(
+            (SELECT JSON_ARRAYAGG(s.group_id) FROM ban_server_groups s WHERE s.ban_id = b.id),
+            JSON_ARRAY()
+        )
+    ),
+    COALESCE(b.created_at, NOW())
+FROM bans b;
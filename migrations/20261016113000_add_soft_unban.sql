@@ -0,0 +1,4 @@
+-- 解封不再删除记录：保留最近一次解封的操作人、时间与原因
+ALTER TABLE bans ADD COLUMN unbanned_by VARCHAR(64) NULL;
+ALTER TABLE bans ADD COLUMN unbanned_at TIMESTAMP NULL;
+ALTER TABLE bans ADD COLUMN unban_reason TEXT NULL;
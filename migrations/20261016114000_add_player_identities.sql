@@ -0,0 +1,21 @@
+-- 玩家身份图：记录每次进服检查与 RCON status 巡检中看到的 (SteamID64, IP) 组合
+CREATE TABLE IF NOT EXISTS player_identities (
+    steam_id_64 VARCHAR(32) NOT NULL,
+    ip VARCHAR(45) NOT NULL,
+    last_name VARCHAR(128) NULL,
+    -- 同一组合一小时内重复出现只算一次
+    seen_count INT NOT NULL DEFAULT 1,
+    source VARCHAR(16) NOT NULL,
+    first_seen TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    last_seen TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (steam_id_64, ip),
+    INDEX idx_player_identities_ip (ip)
+);
+
+-- 是否将封禁延伸到关联账号（同 IP 新账号、身份图中的关联账号），以及自动封禁的来源封禁
+ALTER TABLE bans ADD COLUMN extend_to_linked BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE bans ADD COLUMN linked_from BIGINT NULL;
+ALTER TABLE bans ADD CONSTRAINT fk_bans_linked_from FOREIGN KEY (linked_from) REFERENCES bans(id) ON DELETE SET NULL;
+
+-- 原先 IP 封禁会无条件封禁同 IP 的新账号，保持已有 IP 封禁的行为不变
+UPDATE bans SET extend_to_linked = TRUE WHERE ban_type = 'ip';
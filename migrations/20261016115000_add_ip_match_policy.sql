@@ -0,0 +1,53 @@
+-- 同 IP 匹配策略：off 不处理 / flag 进入审核队列 / auto 自动封禁（保持原有行为）
+CREATE TABLE IF NOT EXISTS ip_match_policy (
+    id TINYINT PRIMARY KEY,
+    mode ENUM('off', 'flag', 'auto') NOT NULL DEFAULT 'auto',
+    -- 白名单中已通过的玩家视为豁免
+    exempt_whitelisted BOOLEAN NOT NULL DEFAULT TRUE,
+    updated_by VARCHAR(64) NULL,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP
+);
+
+INSERT IGNORE INTO ip_match_policy (id, mode) VALUES (1, 'auto');
+
+-- 豁免名单：指定 SteamID 或可信 IP 段（网吧、校园 NAT）
+CREATE TABLE IF NOT EXISTS ip_match_exemptions (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    kind ENUM('steam_id', 'ip_range') NOT NULL,
+    value VARCHAR(64) NOT NULL,
+    ip_start VARBINARY(16) NULL,
+    ip_end VARBINARY(16) NULL,
+    note VARCHAR(255) NULL,
+    created_by VARCHAR(64) NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE KEY uq_ip_match_exemptions (kind, value),
+    INDEX idx_ip_match_exemptions_range (ip_start, ip_end)
+);
+
+-- 审核队列：同一来源封禁 + 同一账号只保留一条记录
+CREATE TABLE IF NOT EXISTS ip_match_reviews (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    parent_ban_id BIGINT NOT NULL,
+    steam_id VARCHAR(64) NOT NULL,
+    steam_id_64 VARCHAR(32) NULL,
+    name VARCHAR(128) NULL,
+    ip VARCHAR(45) NOT NULL,
+    server_id BIGINT NULL,
+    source VARCHAR(16) NOT NULL,
+    status ENUM('pending', 'confirmed', 'dismissed') NOT NULL DEFAULT 'pending',
+    match_count INT NOT NULL DEFAULT 1,
+    decided_by VARCHAR(64) NULL,
+    decision_note TEXT NULL,
+    decided_at TIMESTAMP NULL,
+    created_ban_id BIGINT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    last_seen TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE KEY uq_ip_match_reviews (parent_ban_id, steam_id),
+    INDEX idx_ip_match_reviews_status (status),
+    FOREIGN KEY (parent_ban_id) REFERENCES bans(id) ON DELETE CASCADE,
+    FOREIGN KEY (created_ban_id) REFERENCES bans(id) ON DELETE SET NULL
+);
+
+-- 普通管理员默认可以审核匹配，策略与豁免名单仅超级管理员或单独授权
+INSERT IGNORE INTO role_permissions (role, permission) VALUES
+    ('admin', 'ipmatch.review');
@@ -0,0 +1,7 @@
+-- 封禁列表分页、筛选与排序所需的索引
+CREATE INDEX idx_bans_created_at ON bans (created_at);
+CREATE INDEX idx_bans_status_created ON bans (status, created_at);
+CREATE INDEX idx_bans_expires_at ON bans (expires_at);
+CREATE INDEX idx_bans_admin_name ON bans (admin_name);
+CREATE INDEX idx_bans_steam_id ON bans (steam_id);
+CREATE INDEX idx_bans_steam_id_64 ON bans (steam_id_64);
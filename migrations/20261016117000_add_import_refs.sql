@@ -0,0 +1,6 @@
+-- 导入来源标识（如 SourceBans++ 的 bid），重复导入同一数据库时据此去重
+ALTER TABLE bans ADD COLUMN import_ref VARCHAR(191) NULL;
+CREATE UNIQUE INDEX uq_bans_import_ref ON bans (import_ref);
+
+ALTER TABLE comm_blocks ADD COLUMN import_ref VARCHAR(191) NULL;
+CREATE UNIQUE INDEX uq_comm_blocks_import_ref ON comm_blocks (import_ref);
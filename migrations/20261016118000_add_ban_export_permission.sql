@@ -0,0 +1,3 @@
+-- 普通管理员默认可以导出封禁列表（与查看封禁列表可见的数据一致）
+INSERT IGNORE INTO role_permissions (role, permission) VALUES
+    ('admin', 'bans.export');
@@ -0,0 +1,3 @@
+-- 显式记录管理员是否受服务器组限制，避免组被删除后受限管理员因绑定行被级联删除而变成全局
+ALTER TABLE admins ADD COLUMN server_scoped BOOLEAN NOT NULL DEFAULT FALSE;
+UPDATE admins SET server_scoped = TRUE WHERE id IN (SELECT admin_id FROM admin_server_groups);
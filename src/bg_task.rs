@@ -3,6 +3,8 @@ use crate::AppState;
 use tokio::time::{interval, Duration};
 use crate::models::server::Server;
 use crate::models::ban::Ban;
+use crate::services::enforcement::LinkedTarget;
+use crate::services::ip_match::MatchOutcome;
 use crate::utils::rcon::send_command;
 
 
@@ -20,34 +22,40 @@ pub async fn start_background_task(state: Arc<AppState>) {
 
 async fn check_all_servers(state: &Arc<AppState>) -> Result<(), Box<dyn std::error::Error>> {
     // 1. Get all Active IP Bans
-    let ip_bans = sqlx::query_as::<_, Ban>(
-        "SELECT * FROM bans WHERE status = 'active' AND ban_type = 'ip'"
+    let mut ip_bans = sqlx::query_as::<_, Ban>(
+        "SELECT * FROM bans WHERE status = 'active' AND ban_type = 'ip' AND (expires_at IS NULL OR expires_at > NOW())"
     )
     .fetch_all(&state.db)
     .await?;
 
-    if ip_bans.is_empty() {
-        return Ok(());
-    }
-
-    // Convert to HashMap for fast lookup: IP -> Ban Details
-    // Note: Multiple bans might exist for same IP (though unlikely if managed well), we just need one valid one.
-    use std::collections::{HashMap, HashSet};
-    let ip_ban_map: HashMap<String, Ban> = ip_bans.into_iter()
-        .map(|b| (b.ip.clone(), b))
+    crate::services::ban_scope::attach_scopes(&state.db, &mut ip_bans).await?;
+
+    // Parse each ban's address range once. Several bans may cover the same IP with
+    // different server group scopes; each server picks the ones in scope.
+    use std::collections::HashSet;
+    use crate::utils::ip_range::{self, IpRange};
+    let ip_ban_ranges: Vec<(IpRange, Ban)> = ip_bans.into_iter()
+        .filter_map(|b| match IpRange::parse(&b.ip) {
+            Ok(range) => Some((range, b)),
+            Err(e) => {
+                tracing::warn!("BG Task: Skipping IP ban {} with invalid address '{}': {}", b.id, b.ip, e);
+                None
+            }
+        })
         .collect();
 
     // 2. Get all Active Account Bans (SteamIDs) to avoid N+1 DB check
     // We only need the steam_ids to know if they are already banned.
-    let account_bans_result = sqlx::query!(
-        "SELECT steam_id FROM bans WHERE status = 'active' AND steam_id IS NOT NULL"
+    let account_bans_result = sqlx::query_as::<_, (String, Option<String>)>(
+        "SELECT steam_id, steam_id_64 FROM bans WHERE status = 'active' AND steam_id IS NOT NULL AND (expires_at IS NULL OR expires_at > NOW())"
     )
     .fetch_all(&state.db)
     .await?;
 
     let mut active_steamids: HashSet<String> = account_bans_result.into_iter()
-        .map(|r| r.steam_id.clone())
+        .flat_map(|(steam_id, steam_id_64)| std::iter::once(steam_id).chain(steam_id_64))
         .collect();
+    let steam_service = crate::services::steam_api::SteamService::new();
 
     // 3. Get Servers
     let servers = sqlx::query_as::<_, Server>("SELECT * FROM servers")
@@ -95,43 +103,55 @@ async fn check_all_servers(state: &Arc<AppState>) -> Result<(), Box<dyn std::err
                     
                     if ip_only.is_empty() || steam_id == "BOT" { continue; }
 
-                    // CHECK: Is this IP in our ban list?
-                    if let Some(ban) = ip_ban_map.get(ip_only) {
+                    // 巡检看到的每个在线玩家都记入身份图
+                    let steam_id_64 = steam_service.resolve_steam_id(steam_id).await;
+                    if let Some(id64) = &steam_id_64 {
+                        crate::services::identity::observe(&state.db, id64, ip_only, Some(player_name), "status").await;
+                    }
+
+                    // CHECK: Is this IP inside any banned range?
+                    let Some(player_ip) = ip_range::parse_player_ip(ip_only) else { continue; };
+                    let ban = ip_ban_ranges.iter()
+                        .find(|(range, b)| range.contains(player_ip) && b.applies_to_group(server.group_id))
+                        .map(|(_, b)| b);
+                    if let Some(ban) = ban {
                         // IP is BANNED. Check if Account is already banned.
-                        if active_steamids.contains(steam_id) {
-                            // Already banned - Just Kick
+                        let already_banned = active_steamids.contains(steam_id)
+                            || steam_id_64.as_ref().is_some_and(|id| active_steamids.contains(id));
+                        if already_banned {
                             let _ = send_command(&address, &pwd, &format!("kickid {} \"Banned IP Detected\"", userid)).await;
-                        } else {
-                            // NEW CATCH!
-                            tracing::info!("BG Task: Caught user bypassing IP Ban! IP: {}, SteamID: {}, Name: {}", ip_only, steam_id, player_name);
-                            
-                            let reason = "同IP关联封禁 (Detected online with Banned IP)";
-                            let expires_at = ban.expires_at;
-
-                            // Insert into DB
-                            let insert_result = sqlx::query(
-                                "INSERT INTO bans (name, steam_id, ip, ban_type, reason, duration, admin_name, expires_at, created_at, status, server_id) VALUES (?, ?, ?, ?, ?, ?, ?, ?, NOW(), 'active', ?)"
-                            )
-                            .bind(player_name)
-                            .bind(steam_id)
-                            .bind(ip_only)
-                            .bind("account")
-                            .bind(reason)
-                            .bind(&ban.duration)
-                            .bind("System (BG Monitor)")
-                            .bind(expires_at)
-                            .bind(server.id) // Use current server ID logic
-                            .execute(&state.db)
-                            .await;
-
-                            if let Ok(_) = insert_result {
+                            continue;
+                        }
+
+                        let reason = "同IP关联封禁 (Detected online with Banned IP)";
+                        let target = LinkedTarget {
+                            name: player_name,
+                            steam_id,
+                            steam_id_64: steam_id_64.as_deref(),
+                            ip: ip_only,
+                            server_id: Some(server.id),
+                        };
+                        match crate::services::ip_match::handle(state, ban, &target, "status", reason, "System (BG Monitor)").await {
+                            Ok(MatchOutcome::Banned(linked)) => {
+                                // NEW CATCH!
+                                tracing::info!("BG Task: Caught user bypassing IP Ban! IP: {}, SteamID: {}, Name: {}", ip_only, steam_id, player_name);
                                 // Add to local cache so we don't try to ban again in this loop
                                 active_steamids.insert(steam_id.to_string());
-                            }
 
-                            // Ban & Kick on Server
-                            let duration_str = &ban.duration;
-                            let _ = send_command(&address, &pwd, &format!("sm_ban #{} {} \"{}\"", userid, duration_str, reason)).await;
+                                // Ban & Kick on Server. sm_ban 只接受分钟数，0 表示永久
+                                let minutes = linked.expires_at.map_or(0, |t| (t - chrono::Utc::now()).num_minutes().max(1));
+                                let _ = send_command(&address, &pwd, &format!("sm_ban #{} {} \"{}\"", userid, minutes, reason)).await;
+                            }
+                            // 豁免名单中的玩家或已被驳回的匹配不受该 IP 封禁影响
+                            Ok(MatchOutcome::Exempt) => {}
+                            // 仅标记 / 不处理：IP 封禁本身仍然生效 - Just Kick
+                            Ok(MatchOutcome::Flagged) | Ok(MatchOutcome::Ignored) => {
+                                let _ = send_command(&address, &pwd, &format!("kickid {} \"Banned IP Detected\"", userid)).await;
+                            }
+                            Err(e) => {
+                                tracing::error!("BG Task: Failed to apply IP match policy for {}: {}", steam_id, e);
+                                let _ = send_command(&address, &pwd, &format!("kickid {} \"Banned IP Detected\"", userid)).await;
+                            }
                         }
                     }
                 }
@@ -0,0 +1,61 @@
+//! Minimal stand-in for Steam's OpenID provider, for testing Steam login offline.
+//!
+//! Run it and point the backend at it:
+//!   STEAM_OPENID_ENDPOINT=http://127.0.0.1:8089/openid/login
+//! Every sign-in succeeds as `STUB_STEAM_ID` and every assertion is reported valid.
+
+use axum::{
+    extract::{Form, Query},
+    response::{IntoResponse, Redirect},
+    routing::get,
+    Router,
+};
+use std::collections::HashMap;
+
+fn endpoint() -> String {
+    let port = std::env::var("STUB_PORT").unwrap_or_else(|_| "8089".to_string());
+    format!("http://127.0.0.1:{}/openid/login", port)
+}
+
+/// checkid_setup: "log in" immediately and send the browser back with a positive assertion
+async fn checkid_setup(Query(params): Query<HashMap<String, String>>) -> impl IntoResponse {
+    let Some(return_to) = params.get("openid.return_to") else {
+        return (axum::http::StatusCode::BAD_REQUEST, "missing openid.return_to").into_response();
+    };
+    let steam_id = std::env::var("STUB_STEAM_ID").unwrap_or_else(|_| "76561197960287930".to_string());
+    let claimed_id = format!("https://steamcommunity.com/openid/id/{}", steam_id);
+    let endpoint = endpoint();
+    let nonce = format!("{}stub", chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ"));
+
+    let mut url = reqwest::Url::parse(return_to).expect("invalid return_to");
+    url.query_pairs_mut()
+        .append_pair("openid.ns", "http://specs.openid.net/auth/2.0")
+        .append_pair("openid.mode", "id_res")
+        .append_pair("openid.op_endpoint", &endpoint)
+        .append_pair("openid.claimed_id", &claimed_id)
+        .append_pair("openid.identity", &claimed_id)
+        .append_pair("openid.return_to", return_to)
+        .append_pair("openid.response_nonce", &nonce)
+        .append_pair("openid.assoc_handle", "stub")
+        .append_pair("openid.signed", "signed,op_endpoint,claimed_id,identity,return_to,response_nonce,assoc_handle")
+        .append_pair("openid.sig", "stub");
+
+    println!("checkid_setup -> {}", steam_id);
+    Redirect::to(url.as_str()).into_response()
+}
+
+/// check_authentication: accept every assertion
+async fn check_authentication(Form(params): Form<HashMap<String, String>>) -> impl IntoResponse {
+    println!("check_authentication for {:?}", params.get("openid.claimed_id"));
+    "ns:http://specs.openid.net/auth/2.0\nis_valid:true\n"
+}
+
+#[tokio::main]
+async fn main() {
+    let app = Router::new().route("/openid/login", get(checkid_setup).post(check_authentication));
+    let endpoint = endpoint();
+    let addr = endpoint.trim_start_matches("http://").trim_end_matches("/openid/login").to_string();
+    println!("Steam OpenID stub listening, set STEAM_OPENID_ENDPOINT={}", endpoint);
+    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+    axum::serve(listener, app).await.unwrap();
+}
@@ -1,16 +1,21 @@
 use axum::{
     extract::{Extension, Path, State},
     http::StatusCode,
-    response::IntoResponse,
+    response::{IntoResponse, Response},
     Json,
 };
 use std::sync::Arc;
 use crate::AppState;
 use crate::models::user::{Admin, CreateAdminRequest, UpdateAdminRequest};
+use crate::models::server::AdminServerGroupsRequest;
 use crate::handlers::auth::Claims;
+use crate::models::session::Session;
+use crate::services::{login_guard, session};
 use crate::utils::log_admin_action;
+use crate::utils::password::{set_password, PasswordPolicy};
 use crate::services::steam_api::SteamService;
 use bcrypt::{hash, DEFAULT_COST};
+use sqlx::{MySql, QueryBuilder};
 
 #[utoipa::path(
     get,
@@ -24,10 +29,24 @@ use bcrypt::{hash, DEFAULT_COST};
 )]
 pub async fn list_admins(
     State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
 ) -> impl IntoResponse {
-    let admins = sqlx::query_as::<_, Admin>("SELECT * FROM admins")
-        .fetch_all(&state.db)
-        .await;
+    let mut select = QueryBuilder::<MySql>::new("SELECT * FROM admins a");
+    // 受限管理员只看到所属组全部在自己范围内的受限管理员
+    if let Some(groups) = &user.server_groups {
+        if groups.is_empty() {
+            return (StatusCode::OK, Json(Vec::<Admin>::new())).into_response();
+        }
+        select.push(" WHERE a.server_scoped = TRUE \
+            AND EXISTS (SELECT 1 FROM admin_server_groups g WHERE g.admin_id = a.id) \
+            AND NOT EXISTS (SELECT 1 FROM admin_server_groups g WHERE g.admin_id = a.id AND g.group_id NOT IN (");
+        let mut ids = select.separated(", ");
+        for group_id in groups {
+            ids.push_bind(*group_id);
+        }
+        select.push("))");
+    }
+    let admins = select.build_query_as::<Admin>().fetch_all(&state.db).await;
 
     match admins {
         Ok(data) => (StatusCode::OK, Json(data)).into_response(),
@@ -41,7 +60,7 @@ pub async fn list_admins(
     request_body = CreateAdminRequest,
     responses(
         (status = 201, description = "Admin created"),
-        (status = 400, description = "Bad request")
+        (status = 400, description = "Bad request or password policy violated")
     ),
     security(
         ("jwt" = [])
@@ -52,6 +71,13 @@ pub async fn create_admin(
     Extension(user): Extension<Claims>,
     Json(payload): Json<CreateAdminRequest>,
 ) -> impl IntoResponse {
+    if payload.role == "super_admin" && user.role != "super_admin" {
+        return (StatusCode::FORBIDDEN, Json("Only super admins can create super admins")).into_response();
+    }
+    if let Err(e) = PasswordPolicy::from_env().validate(&payload.password) {
+        return (StatusCode::BAD_REQUEST, Json(e)).into_response();
+    }
+
     let hashed = hash(payload.password, DEFAULT_COST).unwrap();
 
     // 解析 SteamID 为各种格式
@@ -68,18 +94,31 @@ pub async fn create_admin(
         (None, None, None)
     };
 
-    let result = sqlx::query(
-        "INSERT INTO admins (username, password, role, steam_id, steam_id_3, steam_id_64, remark) VALUES (?, ?, ?, ?, ?, ?, ?)"
-    )
-    .bind(&payload.username)
-    .bind(hashed)
-    .bind(&payload.role)
-    .bind(&steam_id_2)
-    .bind(&steam_id_3)
-    .bind(&steam_id_64)
-    .bind(&payload.remark)
-    .execute(&state.db)
-    .await;
+    // 受限管理员创建的管理员继承其服务器组，不能借此创建全局管理员
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let inserted = sqlx::query(
+            "INSERT INTO admins (username, password, role, steam_id, steam_id_3, steam_id_64, remark, server_scoped) VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&payload.username)
+        .bind(hashed)
+        .bind(&payload.role)
+        .bind(&steam_id_2)
+        .bind(&steam_id_3)
+        .bind(&steam_id_64)
+        .bind(&payload.remark)
+        .bind(!user.is_global())
+        .execute(&mut *tx)
+        .await?;
+        for group_id in user.server_groups.iter().flatten() {
+            sqlx::query("INSERT IGNORE INTO admin_server_groups (admin_id, group_id) VALUES (?, ?)")
+                .bind(inserted.last_insert_id() as i64)
+                .bind(group_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await
+    }.await;
 
     match result {
         Ok(_) => {
@@ -96,6 +135,39 @@ pub async fn create_admin(
     }
 }
 
+/// 受限管理员只能管理同样受限、且所属服务器组全部在自己范围内的管理员
+fn target_in_scope(user: &Claims, target_scoped: bool, target_groups: &[i64]) -> bool {
+    user.is_global()
+        || (target_scoped && !target_groups.is_empty() && target_groups.iter().all(|g| user.can_access_group(*g)))
+}
+
+/// 只有 super_admin 可以修改或删除 super_admin 账号；受限管理员只能操作自己范围内的管理员
+pub(crate) async fn guard_target(state: &AppState, user: &Claims, id: i64) -> Result<(), Response> {
+    let target: Option<(String, bool)> = sqlx::query_as("SELECT role, server_scoped FROM admins WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    let Some((role, scoped)) = target else {
+        return Err((StatusCode::NOT_FOUND, Json("Admin not found")).into_response());
+    };
+    if role == "super_admin" && user.role != "super_admin" {
+        return Err((StatusCode::FORBIDDEN, Json("Only super admins can modify a super admin")).into_response());
+    }
+    if !user.is_global() {
+        let groups: Vec<i64> = sqlx::query_scalar("SELECT group_id FROM admin_server_groups WHERE admin_id = ?")
+            .bind(id)
+            .fetch_all(&state.db)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+        if !target_in_scope(user, scoped, &groups) {
+            return Err((StatusCode::FORBIDDEN, Json("Admin is outside your server groups")).into_response());
+        }
+    }
+    Ok(())
+}
+
 #[utoipa::path(
     put,
     path = "/api/admins/{id}",
@@ -105,6 +177,8 @@ pub async fn create_admin(
     request_body = UpdateAdminRequest,
     responses(
         (status = 200, description = "Admin updated"),
+        (status = 400, description = "Password policy violated"),
+        (status = 403, description = "Target is a super admin and the caller is not"),
         (status = 404, description = "Admin not found")
     ),
     security(
@@ -117,6 +191,25 @@ pub async fn update_admin(
     Path(id): Path<i64>,
     Json(payload): Json<UpdateAdminRequest>,
 ) -> impl IntoResponse {
+    if payload.role.as_deref() == Some("super_admin") && user.role != "super_admin" {
+        return (StatusCode::FORBIDDEN, Json("Only super admins can promote to super admin")).into_response();
+    }
+    if let Err(resp) = guard_target(&state, &user, id).await {
+        return resp;
+    }
+    if let Some(ref password) = payload.password {
+        let policy = PasswordPolicy::from_env();
+        if let Err(e) = policy.validate(password) {
+            return (StatusCode::BAD_REQUEST, Json(e)).into_response();
+        }
+        if let Err(e) = policy.check_reuse(&state.db, id, password).await {
+            return (StatusCode::BAD_REQUEST, Json(e)).into_response();
+        }
+    }
+
+    // 用户名、密码或角色变更后，旧令牌中的身份信息已失效
+    let invalidates_sessions = payload.username.is_some() || payload.password.is_some() || payload.role.is_some();
+
     if let Some(username) = payload.username {
         let _ = sqlx::query("UPDATE admins SET username = ? WHERE id = ?")
             .bind(username).bind(id)
@@ -124,9 +217,7 @@ pub async fn update_admin(
     }
     if let Some(password) = payload.password {
          let hashed = hash(password, DEFAULT_COST).unwrap();
-         let _ = sqlx::query("UPDATE admins SET password = ? WHERE id = ?")
-            .bind(hashed).bind(id)
-            .execute(&state.db).await;
+         let _ = set_password(&state.db, id, &hashed, false).await;
     }
     if let Some(role) = payload.role {
         let _ = sqlx::query("UPDATE admins SET role = ? WHERE id = ?")
@@ -164,6 +255,10 @@ pub async fn update_admin(
         "Updated admin details"
     ).await;
 
+    if invalidates_sessions {
+        session::invalidate_admin(&state.cache, id).await;
+    }
+
     (StatusCode::OK, Json("Admin updated")).into_response()
 }
 
@@ -175,6 +270,7 @@ pub async fn update_admin(
     ),
     responses(
         (status = 200, description = "Admin deleted"),
+        (status = 403, description = "Target is a super admin and the caller is not"),
         (status = 404, description = "Admin not found")
     ),
     security(
@@ -186,6 +282,10 @@ pub async fn delete_admin(
     Extension(user): Extension<Claims>,
     Path(id): Path<i64>,
 ) -> impl IntoResponse {
+    if let Err(resp) = guard_target(&state, &user, id).await {
+        return resp;
+    }
+
     let result = sqlx::query("DELETE FROM admins WHERE id = ?")
         .bind(id)
         .execute(&state.db)
@@ -200,8 +300,322 @@ pub async fn delete_admin(
                 &format!("AdminID: {}", id),
                 "Deleted admin"
             ).await;
+            session::invalidate_admin(&state.cache, id).await;
             (StatusCode::OK, Json("Admin deleted")).into_response()
         },
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
     }
 }
+
+#[utoipa::path(
+    get,
+    path = "/api/admins/{id}/server-groups",
+    params(
+        ("id" = i64, Path, description = "Admin ID")
+    ),
+    responses(
+        (status = 200, description = "Server groups the admin is scoped to (empty = global)", body = AdminServerGroupsRequest)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn get_admin_server_groups(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    if let Err(resp) = guard_target(&state, &user, id).await {
+        return resp;
+    }
+
+    let groups = sqlx::query_scalar::<_, i64>("SELECT group_id FROM admin_server_groups WHERE admin_id = ? ORDER BY group_id")
+        .bind(id)
+        .fetch_all(&state.db)
+        .await;
+
+    match groups {
+        Ok(group_ids) => (StatusCode::OK, Json(AdminServerGroupsRequest { group_ids })).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admins/{id}/server-groups",
+    params(
+        ("id" = i64, Path, description = "Admin ID")
+    ),
+    request_body = AdminServerGroupsRequest,
+    responses(
+        (status = 200, description = "Server group scope replaced"),
+        (status = 403, description = "Group out of the caller's own scope")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn set_admin_server_groups(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path(id): Path<i64>,
+    Json(payload): Json<AdminServerGroupsRequest>,
+) -> impl IntoResponse {
+    if let Err(resp) = guard_target(&state, &user, id).await {
+        return resp;
+    }
+
+    // A scoped admin can neither hand out groups they don't manage nor make someone global.
+    if !user.is_global()
+        && (payload.group_ids.is_empty() || payload.group_ids.iter().any(|g| !user.can_access_group(*g)))
+    {
+        return (StatusCode::FORBIDDEN, Json("Server group out of scope")).into_response();
+    }
+
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        sqlx::query("DELETE FROM admin_server_groups WHERE admin_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        for group_id in &payload.group_ids {
+            sqlx::query("INSERT IGNORE INTO admin_server_groups (admin_id, group_id) VALUES (?, ?)")
+                .bind(id)
+                .bind(group_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        sqlx::query("UPDATE admins SET server_scoped = ? WHERE id = ?")
+            .bind(!payload.group_ids.is_empty())
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await
+    }.await;
+
+    match result {
+        Ok(_) => {
+            let groups = payload.group_ids.iter().map(|g| g.to_string()).collect::<Vec<_>>().join(", ");
+            let _ = log_admin_action(
+                &state.db,
+                &user.sub,
+                "set_admin_server_groups",
+                &format!("AdminID: {}", id),
+                &format!("Groups: [{}]", groups)
+            ).await;
+            session::invalidate_admin(&state.cache, id).await;
+            (StatusCode::OK, Json("Admin server groups updated")).into_response()
+        },
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admins/{id}/sessions",
+    params(
+        ("id" = i64, Path, description = "Admin ID")
+    ),
+    responses(
+        (status = 200, description = "Active sessions of the admin", body = Vec<Session>)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_admin_sessions(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Extension(current): Extension<Session>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    if let Err(resp) = guard_target(&state, &user, id).await {
+        return resp;
+    }
+
+    match session::list_for_admin(&state.cache, id).await {
+        Ok(mut sessions) => {
+            for s in &mut sessions {
+                s.current = s.id == current.id;
+            }
+            (StatusCode::OK, Json(sessions)).into_response()
+        },
+        Err(e) => (StatusCode::SERVICE_UNAVAILABLE, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admins/{id}/logout",
+    params(
+        ("id" = i64, Path, description = "Admin ID")
+    ),
+    responses(
+        (status = 200, description = "All sessions of the admin revoked")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn force_logout_admin(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    if let Err(resp) = guard_target(&state, &user, id).await {
+        return resp;
+    }
+
+    match session::revoke_all_for_admin(&state.cache, id).await {
+        Ok(revoked) => {
+            let _ = log_admin_action(
+                &state.db,
+                &user.sub,
+                "force_logout_admin",
+                &format!("AdminID: {}", id),
+                &format!("Revoked {} session(s)", revoked)
+            ).await;
+            (StatusCode::OK, Json(serde_json::json!({ "revoked": revoked }))).into_response()
+        },
+        Err(e) => (StatusCode::SERVICE_UNAVAILABLE, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admins/{id}/2fa/reset",
+    params(
+        ("id" = i64, Path, description = "Admin ID")
+    ),
+    responses(
+        (status = 200, description = "2FA and recovery codes removed, sessions revoked"),
+        (status = 403, description = "Only super admins can reset 2FA")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn reset_admin_2fa(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    if user.role != "super_admin" {
+        return (StatusCode::FORBIDDEN, Json("Only super admins can reset 2FA")).into_response();
+    }
+
+    let result: Result<u64, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let updated = sqlx::query("UPDATE admins SET totp_secret = NULL, totp_enabled = FALSE WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM admin_recovery_codes WHERE admin_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(updated.rows_affected())
+    }.await;
+
+    match result {
+        Ok(0) => (StatusCode::NOT_FOUND, Json("Admin not found")).into_response(),
+        Ok(_) => {
+            let _ = log_admin_action(
+                &state.db,
+                &user.sub,
+                "reset_admin_2fa",
+                &format!("AdminID: {}", id),
+                "Reset 2FA"
+            ).await;
+            session::invalidate_admin(&state.cache, id).await;
+            (StatusCode::OK, Json("Admin 2FA reset")).into_response()
+        },
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admins/{id}/unlock",
+    params(
+        ("id" = i64, Path, description = "Admin ID")
+    ),
+    responses(
+        (status = 200, description = "Failed login counters and lockout cleared"),
+        (status = 404, description = "Admin not found")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn unlock_admin(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    if let Err(resp) = guard_target(&state, &user, id).await {
+        return resp;
+    }
+
+    let username = match sqlx::query_scalar::<_, String>("SELECT username FROM admins WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&state.db)
+        .await
+    {
+        Ok(Some(name)) => name,
+        Ok(None) => return (StatusCode::NOT_FOUND, Json("Admin not found")).into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    match login_guard::unlock(&state.cache, &username).await {
+        Ok(_) => {
+            let _ = log_admin_action(
+                &state.db,
+                &user.sub,
+                "unlock_admin",
+                &format!("AdminID: {}", id),
+                &format!("Cleared login lockout for {}", username)
+            ).await;
+            (StatusCode::OK, Json("Admin unlocked")).into_response()
+        },
+        Err(e) => (StatusCode::SERVICE_UNAVAILABLE, e.to_string()).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn caller(server_groups: Option<Vec<i64>>) -> Claims {
+        Claims {
+            sub: "caller".to_string(),
+            role: "admin".to_string(),
+            permissions: vec!["admins.manage".to_string()],
+            server_groups,
+            sid: String::new(),
+            exp: 0,
+        }
+    }
+
+    #[test]
+    fn scoped_callers_only_reach_admins_inside_their_groups() {
+        let global = caller(None);
+        let scoped = caller(Some(vec![1, 2]));
+        let nothing = caller(Some(vec![]));
+        let cases = [
+            (&global, false, vec![], true),
+            (&global, true, vec![9], true),
+            (&scoped, true, vec![1], true),
+            (&scoped, true, vec![1, 2], true),
+            (&scoped, true, vec![1, 3], false),
+            (&scoped, true, vec![], false),
+            (&scoped, false, vec![], false),
+            (&scoped, false, vec![1], false),
+            (&nothing, true, vec![1], false),
+        ];
+        for (user, target_scoped, groups, allowed) in cases {
+            assert_eq!(target_in_scope(user, target_scoped, &groups), allowed, "{:?} -> {} {:?}", user.server_groups, target_scoped, groups);
+        }
+    }
+}
@@ -0,0 +1,441 @@
+use axum::{
+    extract::{ConnectInfo, Extension, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use sqlx::{MySql, QueryBuilder};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use crate::AppState;
+use crate::handlers::auth::Claims;
+use crate::handlers::ban::ban_in_scope;
+use crate::models::appeal::{
+    Appeal, AppealComment, AppealCommentRequest, AppealDetail, AppealListQuery, AppealLookupQuery,
+    CreateAppealRequest, DecideAppealRequest, PublicAppeal, APPEAL_STATUSES,
+};
+use crate::models::ban::Ban;
+use crate::services::enforcement;
+use crate::services::steam_api::SteamService;
+use crate::utils::log_admin_action;
+
+/// 每个 IP 每小时最多提交的申诉数
+const SUBMIT_LIMIT: i64 = 5;
+const MAX_REASON_LEN: usize = 4000;
+
+/// 输入的 SteamID（任意格式）是否就是被封禁的账号
+fn steam_matches(ban: &Ban, steam_service: &SteamService, steam_id_64: &str) -> bool {
+    if ban.steam_id_64.as_deref() == Some(steam_id_64) || ban.steam_id == steam_id_64 {
+        return true;
+    }
+    if steam_service.id64_to_id2(steam_id_64).is_some_and(|id2| id2 == ban.steam_id) {
+        return true;
+    }
+    match (steam_service.id64_to_id3(steam_id_64), &ban.steam_id_3) {
+        (Some(id3), Some(ban_id3)) => id3 == *ban_id3,
+        _ => false,
+    }
+}
+
+/// Loads an appeal and its ban, rejecting admins whose server groups do not cover the ban
+async fn load_in_scope(state: &AppState, user: &Claims, id: i64) -> Result<(Appeal, Ban), Response> {
+    let appeal = match sqlx::query_as::<_, Appeal>("SELECT * FROM appeals WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&state.db)
+        .await
+    {
+        Ok(Some(a)) => a,
+        Ok(None) => return Err((StatusCode::NOT_FOUND, Json(json!({ "error": "Appeal not found" }))).into_response()),
+        Err(e) => return Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()),
+    };
+
+    let ban = match sqlx::query_as::<_, Ban>("SELECT * FROM bans WHERE id = ?")
+        .bind(appeal.ban_id)
+        .fetch_one(&state.db)
+        .await
+    {
+        Ok(b) => b,
+        Err(e) => return Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()),
+    };
+
+    if !ban_in_scope(user, ban.group_id) {
+        return Err((StatusCode::FORBIDDEN, Json(json!({ "error": "Ban belongs to a server group out of scope" }))).into_response());
+    }
+    Ok((appeal, ban))
+}
+
+// 玩家提交申诉（公开接口）
+#[utoipa::path(
+    post,
+    path = "/api/appeals/submit",
+    request_body = CreateAppealRequest,
+    responses(
+        (status = 201, description = "Appeal submitted"),
+        (status = 400, description = "Invalid input or ban no longer active"),
+        (status = 404, description = "No ban with this ID for this SteamID"),
+        (status = 409, description = "An appeal for this ban is already pending"),
+        (status = 429, description = "Too many submissions")
+    )
+)]
+pub async fn submit_appeal(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateAppealRequest>,
+) -> impl IntoResponse {
+    let ip = crate::utils::client_ip(&headers, &addr);
+    match state.cache.incr(&format!("appeal_submit:{}", ip), Duration::from_secs(3600)).await {
+        Ok(count) if count > SUBMIT_LIMIT => {
+            return (StatusCode::TOO_MANY_REQUESTS, Json(json!({ "error": "提交过于频繁，请稍后再试" }))).into_response();
+        }
+        Ok(_) => {}
+        Err(e) => tracing::warn!("Appeal rate limit unavailable: {}", e),
+    }
+
+    let reason = payload.reason.trim();
+    if reason.is_empty() || reason.chars().count() > MAX_REASON_LEN {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": "申诉理由不能为空且不能超过 4000 字" }))).into_response();
+    }
+
+    let steam_service = SteamService::new();
+    let Some(steam_id_64) = steam_service.resolve_steam_id(&payload.steam_id).await else {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": "SteamID 格式无效，请检查" }))).into_response();
+    };
+
+    let ban = sqlx::query_as::<_, Ban>("SELECT * FROM bans WHERE id = ?")
+        .bind(payload.ban_id)
+        .fetch_optional(&state.db)
+        .await
+        .unwrap_or(None);
+
+    // 封禁 ID 与 SteamID 不匹配时同样返回 404，避免借此枚举封禁记录
+    let ban = match ban {
+        Some(b) if steam_matches(&b, &steam_service, &steam_id_64) => b,
+        _ => return (StatusCode::NOT_FOUND, Json(json!({ "error": "未找到与该 SteamID 对应的封禁记录" }))).into_response(),
+    };
+
+    if ban.status != "active" || ban.expires_at.is_some_and(|t| t < chrono::Utc::now()) {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": "该封禁已失效，无需申诉" }))).into_response();
+    }
+
+    let pending: Option<i64> = sqlx::query_scalar(
+        "SELECT id FROM appeals WHERE ban_id = ? AND status IN ('open', 'under_review') LIMIT 1"
+    )
+    .bind(ban.id)
+    .fetch_optional(&state.db)
+    .await
+    .unwrap_or(None);
+
+    if let Some(id) = pending {
+        return (StatusCode::CONFLICT, Json(json!({ "error": "该封禁已有待处理的申诉", "id": id }))).into_response();
+    }
+
+    let result = sqlx::query(
+        "INSERT INTO appeals (ban_id, steam_id, contact, reason, status) VALUES (?, ?, ?, ?, 'open')"
+    )
+    .bind(ban.id)
+    .bind(&steam_id_64)
+    .bind(payload.contact.as_deref().map(str::trim).filter(|c| !c.is_empty()))
+    .bind(reason)
+    .execute(&state.db)
+    .await;
+
+    match result {
+        Ok(res) => (StatusCode::CREATED, Json(json!({
+            "message": "申诉已提交，请等待管理员处理",
+            "id": res.last_insert_id()
+        }))).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to submit appeal: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": "提交申诉失败" }))).into_response()
+        }
+    }
+}
+
+// 玩家查询申诉进度（公开接口，需提供提交时的 SteamID）
+#[utoipa::path(
+    get,
+    path = "/api/appeals/public/{id}",
+    params(
+        ("id" = i64, Path, description = "Appeal ID"),
+        ("steam_id" = String, Query, description = "SteamID the appeal was submitted with (any format)")
+    ),
+    responses(
+        (status = 200, description = "Appeal status", body = PublicAppeal),
+        (status = 404, description = "Appeal not found")
+    )
+)]
+pub async fn get_public_appeal(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    Query(query): Query<AppealLookupQuery>,
+) -> impl IntoResponse {
+    let steam_id_64 = SteamService::new().resolve_steam_id(&query.steam_id).await;
+
+    let appeal = sqlx::query_as::<_, PublicAppeal>(
+        "SELECT id, ban_id, status, decision_note, decided_at, created_at FROM appeals WHERE id = ? AND steam_id = ?"
+    )
+    .bind(id)
+    .bind(steam_id_64.unwrap_or_default())
+    .fetch_optional(&state.db)
+    .await;
+
+    match appeal {
+        Ok(Some(a)) => (StatusCode::OK, Json(a)).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, Json(json!({ "error": "未找到申诉记录" }))).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/appeals",
+    params(
+        ("status" = Option<String>, Query, description = "open, under_review, accepted or denied")
+    ),
+    responses(
+        (status = 200, description = "List appeals", body = Vec<Appeal>)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_appeals(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Query(query): Query<AppealListQuery>,
+) -> impl IntoResponse {
+    if let Some(status) = &query.status {
+        if !APPEAL_STATUSES.contains(&status.as_str()) {
+            return (StatusCode::BAD_REQUEST, Json(json!({ "error": format!("Unknown status '{}'", status) }))).into_response();
+        }
+    }
+
+    let mut select = QueryBuilder::<MySql>::new("SELECT a.* FROM appeals a");
+    // 受限管理员只能看到本组封禁的申诉（与 ban_in_scope 一致：全局封禁不在其范围内）
+    if let Some(groups) = &user.server_groups {
+        if groups.is_empty() {
+            return (StatusCode::OK, Json(Vec::<Appeal>::new())).into_response();
+        }
+        select.push(" JOIN bans b ON b.id = a.ban_id AND b.group_id IN (");
+        let mut ids = select.separated(", ");
+        for group_id in groups {
+            ids.push_bind(*group_id);
+        }
+        select.push(")");
+    }
+    select.push(" WHERE (");
+    select.push_bind(&query.status);
+    select.push(" IS NULL OR a.status = ");
+    select.push_bind(&query.status);
+    select.push(") ORDER BY a.created_at DESC");
+
+    let appeals = select.build_query_as::<Appeal>().fetch_all(&state.db).await;
+
+    match appeals {
+        Ok(data) => (StatusCode::OK, Json(data)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/appeals/{id}",
+    params(
+        ("id" = i64, Path, description = "Appeal ID")
+    ),
+    responses(
+        (status = 200, description = "Appeal with ban summary and comments", body = AppealDetail),
+        (status = 403, description = "Ban out of scope"),
+        (status = 404, description = "Appeal not found")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn get_appeal(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let (appeal, ban) = match load_in_scope(&state, &user, id).await {
+        Ok(loaded) => loaded,
+        Err(resp) => return resp,
+    };
+
+    let comments = sqlx::query_as::<_, AppealComment>("SELECT * FROM appeal_comments WHERE appeal_id = ? ORDER BY created_at, id")
+        .bind(id)
+        .fetch_all(&state.db)
+        .await;
+
+    match comments {
+        Ok(comments) => (StatusCode::OK, Json(AppealDetail {
+            appeal,
+            ban_name: ban.name,
+            ban_reason: ban.reason,
+            ban_status: ban.status,
+            comments,
+        })).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/appeals/{id}/comments",
+    params(
+        ("id" = i64, Path, description = "Appeal ID")
+    ),
+    request_body = AppealCommentRequest,
+    responses(
+        (status = 201, description = "Comment added; an open appeal moves to under_review"),
+        (status = 400, description = "Empty comment"),
+        (status = 403, description = "Ban out of scope"),
+        (status = 404, description = "Appeal not found")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn comment_appeal(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path(id): Path<i64>,
+    Json(payload): Json<AppealCommentRequest>,
+) -> impl IntoResponse {
+    let content = payload.content.trim();
+    if content.is_empty() {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": "Comment cannot be empty" }))).into_response();
+    }
+
+    let (appeal, _) = match load_in_scope(&state, &user, id).await {
+        Ok(v) => v,
+        Err(resp) => return resp,
+    };
+
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        sqlx::query("INSERT INTO appeal_comments (appeal_id, author, content) VALUES (?, ?, ?)")
+            .bind(id)
+            .bind(&user.sub)
+            .bind(content)
+            .execute(&mut *tx)
+            .await?;
+        // 管理员开始处理后，申诉自动进入审核中
+        sqlx::query("UPDATE appeals SET status = 'under_review' WHERE id = ? AND status = 'open'")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await
+    }.await;
+
+    match result {
+        Ok(_) => {
+            let _ = log_admin_action(
+                &state.db,
+                &user.sub,
+                "comment_appeal",
+                &format!("AppealID: {}, BanID: {}", id, appeal.ban_id),
+                content
+            ).await;
+            (StatusCode::CREATED, Json("Comment added")).into_response()
+        },
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/appeals/{id}/status",
+    params(
+        ("id" = i64, Path, description = "Appeal ID")
+    ),
+    request_body = DecideAppealRequest,
+    responses(
+        (status = 200, description = "Appeal updated; accepting it lifts the ban"),
+        (status = 400, description = "Invalid status"),
+        (status = 403, description = "Ban out of scope"),
+        (status = 404, description = "Appeal not found"),
+        (status = 409, description = "Appeal already decided")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn decide_appeal(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path(id): Path<i64>,
+    Json(payload): Json<DecideAppealRequest>,
+) -> impl IntoResponse {
+    let status = payload.status.as_str();
+    if !matches!(status, "under_review" | "accepted" | "denied") {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": "status must be under_review, accepted or denied" }))).into_response();
+    }
+
+    let (appeal, ban) = match load_in_scope(&state, &user, id).await {
+        Ok(v) => v,
+        Err(resp) => return resp,
+    };
+    if !appeal.is_pending() {
+        return (StatusCode::CONFLICT, Json(json!({ "error": format!("Appeal already {}", appeal.status) }))).into_response();
+    }
+
+    let note = payload.note.as_deref().map(str::trim).filter(|n| !n.is_empty());
+    let result = if status == "under_review" {
+        sqlx::query("UPDATE appeals SET status = 'under_review' WHERE id = ?")
+            .bind(id)
+            .execute(&state.db)
+            .await
+    } else {
+        sqlx::query("UPDATE appeals SET status = ?, decided_by = ?, decision_note = ?, decided_at = NOW() WHERE id = ? AND status IN ('open', 'under_review')")
+            .bind(status)
+            .bind(&user.sub)
+            .bind(note)
+            .bind(id)
+            .execute(&state.db)
+            .await
+    };
+
+    match result {
+        Ok(res) if res.rows_affected() == 0 && status != "under_review" => {
+            return (StatusCode::CONFLICT, Json(json!({ "error": "Appeal was decided concurrently" }))).into_response();
+        }
+        Ok(_) => {}
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+
+    // 通过申诉即解除封禁，与手动解封走同一路径（含 RCON sm_unban 下发）
+    let mut details = note.unwrap_or("").to_string();
+    if status == "accepted" {
+        match enforcement::unban(&state, &ban, &user.sub, &format!("Appeal #{} accepted", id)).await {
+            Ok(true) => {
+                details = format!("Ban lifted (Unban commands queued). {}", details);
+                let _ = log_admin_action(
+                    &state.db,
+                    &user.sub,
+                    "unban",
+                    &format!("BanID: {}, Target: {} ({})", ban.id, ban.name, ban.steam_id),
+                    &format!("Unbanned via accepted appeal #{}", id)
+                ).await;
+            }
+            Ok(false) => details = format!("Ban was no longer active. {}", details),
+            Err(e) => {
+                tracing::error!("Failed to lift ban {} for appeal {}: {}", ban.id, id, e);
+                return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+            }
+        }
+    }
+
+    let _ = log_admin_action(
+        &state.db,
+        &user.sub,
+        "decide_appeal",
+        &format!("AppealID: {}, BanID: {}", id, ban.id),
+        &format!("{} {}", status, details.trim())
+    ).await;
+
+    (StatusCode::OK, Json(format!("Appeal {}", status))).into_response()
+}
@@ -1,13 +1,21 @@
 use axum::{
-    extract::State,
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{ConnectInfo, Extension, Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect, Response},
     Json,
 };
 use serde_json::json;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use crate::AppState;
-use crate::models::user::{LoginRequest, LoginResponse, ChangePasswordRequest};
+use crate::models::session::{RequiredAction, Session};
+use crate::models::user::{
+    Admin, LoginRequest, LoginResponse, ChangePasswordRequest, RecoveryCodes, RefreshRequest,
+    SteamVerifyRequest, TotpCodeRequest, TotpEnrollment, TwoFactorLoginRequest,
+};
+use crate::services::session::{self, RefreshOutcome};
+use crate::services::{login_guard, totp};
+use crate::services::steam_openid::SteamOpenId;
 use bcrypt::verify;
 use jsonwebtoken::{encode, Header, EncodingKey};
 use serde::{Deserialize, Serialize};
@@ -16,23 +24,56 @@ use serde::{Deserialize, Serialize};
 pub struct Claims {
     pub sub: String, // username
     pub role: String,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+    /// Server groups the admin is scoped to. `None` means global.
+    #[serde(default)]
+    pub server_groups: Option<Vec<i64>>,
+    /// Server-side session id, checked on every request so the token can be revoked
+    #[serde(default)]
+    pub sid: String,
     pub exp: usize,
 }
 
+impl Claims {
+    pub fn has_permission(&self, permission: &str) -> bool {
+        self.role == "super_admin" || self.permissions.iter().any(|p| p == permission)
+    }
+
+    pub fn is_global(&self) -> bool {
+        self.server_groups.is_none()
+    }
+
+    pub fn can_access_group(&self, group_id: i64) -> bool {
+        match &self.server_groups {
+            Some(groups) => groups.contains(&group_id),
+            None => true,
+        }
+    }
+}
+
 #[utoipa::path(
     post,
     path = "/api/auth/login",
     request_body = LoginRequest,
     responses(
-        (status = 200, description = "Login successful", body = LoginResponse),
-        (status = 401, description = "Unauthorized")
+        (status = 200, description = "Login successful, or `mfa_required` with an `mfa_token` for `/api/auth/login/2fa`", body = LoginResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 429, description = "Too many failed attempts, see Retry-After")
     )
 )]
 pub async fn login(
     State(state): State<Arc<AppState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     Json(payload): Json<LoginRequest>,
 ) -> impl IntoResponse {
-    let row = sqlx::query_as::<_, crate::models::user::Admin>("SELECT * FROM admins WHERE username = ?")
+    let ip = crate::utils::client_ip(&headers, &addr);
+    if let Some(blocked) = check_login_guard(&state, &payload.username, &ip).await {
+        return blocked;
+    }
+
+    let row = sqlx::query_as::<_, Admin>("SELECT * FROM admins WHERE username = ?")
         .bind(&payload.username)
         .fetch_optional(&state.db)
         .await;
@@ -48,29 +89,15 @@ pub async fn login(
             let valid = verify(&payload.password, &user.password).unwrap_or(false);
             
             if valid {
-                tracing::info!("Login successful for user: {}", user.username);
-                // Generate JWT
-                let expiration = chrono::Utc::now()
-                    .checked_add_signed(chrono::Duration::days(1))
-                    .expect("valid timestamp")
-                    .timestamp();
-
-                let claims = Claims {
-                    sub: user.username.clone(),
-                    role: user.role.clone(),
-                    exp: expiration as usize,
-                };
-                
-                let secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "secret".to_string());
-                let token = encode(&Header::default(), &claims, &EncodingKey::from_secret(secret.as_ref())).unwrap();
-
-                return (StatusCode::OK, Json(json!({ "token": token, "user": { "username": user.username, "role": user.role } }))).into_response();
+                return complete_first_factor(&state, &user, &headers, &addr).await;
             } else {
                 tracing::warn!("Login failed for user: {} (Invalid password)", payload.username);
+                record_login_failure(&state, &payload.username, &ip).await;
             }
         }
         Ok(None) => {
             tracing::warn!("Login failed: User '{}' not found", payload.username);
+            record_login_failure(&state, &payload.username, &ip).await;
         }
         Err(e) => {
             tracing::error!("Database error during login for user '{}': {}", payload.username, e);
@@ -80,17 +107,528 @@ pub async fn login(
     (StatusCode::UNAUTHORIZED, Json(json!({ "error": "Invalid credentials" }))).into_response()
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/auth/steam/login",
+    responses(
+        (status = 303, description = "Redirect to the Steam sign-in page"),
+        (status = 503, description = "Steam login not configured")
+    )
+)]
+pub async fn steam_login(
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    let Some(openid) = SteamOpenId::from_env() else {
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(json!({ "error": "Steam login is not configured" }))).into_response();
+    };
+
+    match openid.login_url(&state.cache).await {
+        Ok(url) => Redirect::to(&url).into_response(),
+        Err(e) => (StatusCode::SERVICE_UNAVAILABLE, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/steam/verify",
+    request_body = SteamVerifyRequest,
+    responses(
+        (status = 200, description = "Login successful, or `mfa_required` with an `mfa_token`", body = LoginResponse),
+        (status = 401, description = "Assertion invalid"),
+        (status = 403, description = "No admin is linked to this Steam account")
+    )
+)]
+pub async fn steam_verify(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(payload): Json<SteamVerifyRequest>,
+) -> impl IntoResponse {
+    let Some(openid) = SteamOpenId::from_env() else {
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(json!({ "error": "Steam login is not configured" }))).into_response();
+    };
+
+    let steam_id = match openid.verify(&state.client, &state.cache, &payload.params).await {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::warn!("Steam login failed: {}", e);
+            return (StatusCode::UNAUTHORIZED, Json(json!({ "error": e }))).into_response();
+        }
+    };
+
+    let user = sqlx::query_as::<_, Admin>("SELECT * FROM admins WHERE steam_id_64 = ?")
+        .bind(&steam_id)
+        .fetch_optional(&state.db)
+        .await;
+
+    match user {
+        Ok(Some(user)) => complete_first_factor(&state, &user, &headers, &addr).await,
+        Ok(None) => {
+            tracing::warn!("Steam login failed: no admin linked to {}", steam_id);
+            (StatusCode::FORBIDDEN, Json(json!({ "error": "No admin is linked to this Steam account" }))).into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// 第一因素（密码或 Steam）通过后：已启用两步验证时只返回待验证令牌，否则直接签发令牌
+async fn complete_first_factor(state: &AppState, user: &Admin, headers: &HeaderMap, addr: &SocketAddr) -> Response {
+    if user.totp_enabled {
+        return match totp::create_challenge(&state.cache, user.id).await {
+            Ok(mfa_token) => (StatusCode::OK, Json(json!({ "mfa_required": true, "mfa_token": mfa_token }))).into_response(),
+            Err(e) => (StatusCode::SERVICE_UNAVAILABLE, e.to_string()).into_response(),
+        };
+    }
+
+    tracing::info!("Login successful for user: {}", user.username);
+    let _ = login_guard::record_success(&state.cache, &user.username).await;
+    issue_token(state, user, headers, addr).await
+}
+
+/// 账号或 IP 处于退避/锁定期时返回 429；缓存不可用时拒绝登录
+async fn check_login_guard(state: &AppState, username: &str, ip: &str) -> Option<Response> {
+    match login_guard::check(&state.cache, username, ip).await {
+        Ok(None) => None,
+        Ok(Some(blocked)) => {
+            let error = if blocked.locked {
+                "Too many failed attempts, login temporarily locked"
+            } else {
+                "Too many failed attempts, please wait before retrying"
+            };
+            Some((
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, blocked.retry_after.to_string())],
+                Json(json!({ "error": error, "retry_after": blocked.retry_after })),
+            ).into_response())
+        }
+        Err(e) => {
+            tracing::error!("Login guard unavailable: {}", e);
+            Some((StatusCode::SERVICE_UNAVAILABLE, Json(json!({ "error": "Login temporarily unavailable" }))).into_response())
+        }
+    }
+}
+
+async fn record_login_failure(state: &AppState, username: &str, ip: &str) {
+    match login_guard::record_failure(&state.cache, username, ip).await {
+        Ok(locked) => {
+            for subject in locked {
+                tracing::warn!("Login locked after repeated failures ({})", subject);
+                let _ = crate::utils::log_admin_action(
+                    &state.db,
+                    "System (Login Guard)",
+                    "login_lockout",
+                    &subject.to_string(),
+                    &format!("Locked after repeated failed logins, last attempt for '{}' from {}", username, ip)
+                ).await;
+            }
+        }
+        Err(e) => tracing::error!("Failed to record login failure: {}", e),
+    }
+}
+
+/// Lifetime of a session and therefore of its refresh tokens
+const SESSION_TTL: std::time::Duration = std::time::Duration::from_secs(7 * 24 * 60 * 60);
+/// Access tokens are short-lived; the panel renews them via `/api/auth/refresh`
+const ACCESS_TOKEN_TTL: chrono::Duration = chrono::Duration::minutes(15);
+
+/// 为已通过认证的管理员创建服务端会话并签发令牌，返回登录响应
+pub async fn issue_token(state: &AppState, user: &Admin, headers: &HeaderMap, addr: &SocketAddr) -> Response {
+    let ip = crate::utils::client_ip(headers, addr);
+    let user_agent = headers.get(header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string());
+
+    let required_action = match required_action(state, user).await {
+        Ok(a) => a,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    let created: anyhow::Result<(Session, String)> = async {
+        let session = session::create(&state.cache, user.id, &user.username, Some(ip), user_agent, required_action, SESSION_TTL).await?;
+        let refresh_token = session::issue_refresh_token(&state.cache, &session).await?;
+        Ok((session, refresh_token))
+    }.await;
+
+    match created {
+        Ok((session, refresh_token)) => token_response(state, user, &session, refresh_token).await,
+        Err(e) => {
+            tracing::error!("Failed to create session for user '{}': {}", user.username, e);
+            (StatusCode::SERVICE_UNAVAILABLE, Json(json!({ "error": "Failed to create session" }))).into_response()
+        }
+    }
+}
+
+/// 登录后必须先完成的操作（例如初始密码未修改、角色要求两步验证但尚未绑定）
+async fn required_action(state: &AppState, user: &Admin) -> Result<Option<RequiredAction>, sqlx::Error> {
+    if user.must_change_password {
+        return Ok(Some(RequiredAction::ChangePassword));
+    }
+    if !user.totp_enabled && totp::role_requires_2fa(&state.db, &user.role).await? {
+        return Ok(Some(RequiredAction::Enroll2fa));
+    }
+    Ok(None)
+}
+
+/// 根据数据库中的最新权限签发访问令牌
+async fn token_response(state: &AppState, user: &Admin, session: &Session, refresh_token: String) -> Response {
+    let permissions = match crate::utils::resolve_permissions(&state.db, user.id, &user.role).await {
+        Ok(p) => p,
+        Err(e) => {
+            tracing::error!("Failed to resolve permissions for user '{}': {}", user.username, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": "Failed to resolve permissions" }))).into_response();
+        }
+    };
+
+    let server_groups = match crate::utils::resolve_server_groups(&state.db, user.id, &user.role).await {
+        Ok(g) => g,
+        Err(e) => {
+            tracing::error!("Failed to resolve server groups for user '{}': {}", user.username, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": "Failed to resolve server groups" }))).into_response();
+        }
+    };
+
+    let expiration = std::cmp::min(chrono::Utc::now() + ACCESS_TOKEN_TTL, session.expires_at);
+    let claims = Claims {
+        sub: user.username.clone(),
+        role: user.role.clone(),
+        permissions: permissions.clone(),
+        server_groups: server_groups.clone(),
+        sid: session.id.clone(),
+        exp: expiration.timestamp() as usize,
+    };
+    
+    let secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "secret".to_string());
+    let token = encode(&Header::default(), &claims, &EncodingKey::from_secret(secret.as_ref())).unwrap();
+
+    (StatusCode::OK, Json(json!({
+        "token": token,
+        "refresh_token": refresh_token,
+        "expires_in": (expiration - chrono::Utc::now()).num_seconds(),
+        "required_action": session.required_action,
+        "user": { "username": user.username, "role": user.role, "permissions": permissions, "server_groups": server_groups }
+    }))).into_response()
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/refresh",
+    request_body = RefreshRequest,
+    responses(
+        (status = 200, description = "New access and refresh token", body = LoginResponse),
+        (status = 401, description = "Refresh token invalid, expired or already used")
+    )
+)]
+pub async fn refresh(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<RefreshRequest>,
+) -> impl IntoResponse {
+    let (session, refresh_token) = match session::rotate_refresh_token(&state.cache, &payload.refresh_token).await {
+        Ok(RefreshOutcome::Rotated(s, t)) => (s, t),
+        Ok(RefreshOutcome::Reused(s)) => {
+            // 旧的刷新令牌被再次使用，说明令牌可能已泄露，整个会话已被注销
+            tracing::warn!("Refresh token reuse detected for user {} (session {})", s.username, s.id);
+            let _ = crate::utils::log_admin_action(
+                &state.db,
+                &s.username,
+                "refresh_token_reuse",
+                &format!("Session: {}", s.id),
+                "Refresh token reused, session revoked"
+            ).await;
+            return (StatusCode::UNAUTHORIZED, Json(json!({ "error": "Refresh token reused, please log in again" }))).into_response();
+        }
+        Ok(RefreshOutcome::Invalid) => {
+            return (StatusCode::UNAUTHORIZED, Json(json!({ "error": "Invalid refresh token" }))).into_response();
+        }
+        Err(e) => return (StatusCode::SERVICE_UNAVAILABLE, e.to_string()).into_response(),
+    };
+
+    let user = match sqlx::query_as::<_, Admin>("SELECT * FROM admins WHERE id = ?")
+        .bind(session.admin_id)
+        .fetch_optional(&state.db)
+        .await
+    {
+        Ok(Some(u)) => u,
+        Ok(None) => {
+            let _ = session::revoke(&state.cache, &session).await;
+            return (StatusCode::UNAUTHORIZED, Json(json!({ "error": "Invalid refresh token" }))).into_response();
+        }
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    token_response(&state, &user, &session, refresh_token).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/login/2fa",
+    request_body = TwoFactorLoginRequest,
+    responses(
+        (status = 200, description = "Login successful", body = LoginResponse),
+        (status = 401, description = "Invalid code or expired login challenge"),
+        (status = 429, description = "Too many failed attempts, see Retry-After")
+    )
+)]
+pub async fn login_2fa(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(payload): Json<TwoFactorLoginRequest>,
+) -> impl IntoResponse {
+    let admin_id = match totp::challenge_admin(&state.cache, &payload.mfa_token).await {
+        Ok(Some(id)) => id,
+        Ok(None) => return (StatusCode::UNAUTHORIZED, Json(json!({ "error": "Login challenge expired, please log in again" }))).into_response(),
+        Err(e) => return (StatusCode::SERVICE_UNAVAILABLE, e.to_string()).into_response(),
+    };
+
+    let user = match sqlx::query_as::<_, Admin>("SELECT * FROM admins WHERE id = ?")
+        .bind(admin_id)
+        .fetch_optional(&state.db)
+        .await
+    {
+        Ok(Some(u)) if u.totp_enabled => u,
+        Ok(_) => return (StatusCode::UNAUTHORIZED, Json(json!({ "error": "Login challenge expired, please log in again" }))).into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    let ip = crate::utils::client_ip(&headers, &addr);
+    if let Some(blocked) = check_login_guard(&state, &user.username, &ip).await {
+        return blocked;
+    }
+
+    match verify_second_factor(&state, &user, &payload.code).await {
+        Ok(true) => {}
+        Ok(false) => {
+            tracing::warn!("Login failed for user: {} (Invalid 2FA code)", user.username);
+            if let Err(e) = totp::fail_challenge(&state.cache, &payload.mfa_token).await {
+                tracing::error!("Failed to record 2FA attempt: {}", e);
+            }
+            record_login_failure(&state, &user.username, &ip).await;
+            return (StatusCode::UNAUTHORIZED, Json(json!({ "error": "Invalid 2FA code" }))).into_response();
+        }
+        Err(e) => return (StatusCode::SERVICE_UNAVAILABLE, e.to_string()).into_response(),
+    }
+
+    let _ = totp::finish_challenge(&state.cache, &payload.mfa_token).await;
+    let _ = login_guard::record_success(&state.cache, &user.username).await;
+    tracing::info!("Login successful for user: {} (2FA)", user.username);
+    issue_token(&state, &user, &headers, &addr).await
+}
+
+/// 接受当前的 TOTP 验证码或一个未使用的恢复码
+async fn verify_second_factor(state: &AppState, user: &Admin, code: &str) -> anyhow::Result<bool> {
+    let code = code.trim();
+    if code.chars().all(|c| c.is_ascii_digit()) {
+        let Some(secret) = user.totp_secret.as_deref() else {
+            return Ok(false);
+        };
+        return match totp::verify(secret, code, chrono::Utc::now().timestamp()) {
+            Some(step) => totp::consume_step(&state.cache, user.id, step).await,
+            None => Ok(false),
+        };
+    }
+
+    if totp::use_recovery_code(&state.db, user.id, code).await? {
+        let _ = crate::utils::log_admin_action(
+            &state.db,
+            &user.username,
+            "use_recovery_code",
+            "Self",
+            "Logged in with a 2FA recovery code"
+        ).await;
+        return Ok(true);
+    }
+    Ok(false)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/2fa/enroll",
+    responses(
+        (status = 200, description = "New TOTP secret, confirm it with a code to enable 2FA", body = TotpEnrollment),
+        (status = 409, description = "2FA already enabled")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn enroll_2fa(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Extension(current): Extension<Session>,
+) -> impl IntoResponse {
+    let secret = totp::generate_secret();
+    let result = sqlx::query("UPDATE admins SET totp_secret = ? WHERE id = ? AND totp_enabled = FALSE")
+        .bind(&secret)
+        .bind(current.admin_id)
+        .execute(&state.db)
+        .await;
+
+    match result {
+        Ok(r) if r.rows_affected() == 0 => (StatusCode::CONFLICT, Json(json!({ "error": "2FA is already enabled" }))).into_response(),
+        Ok(_) => {
+            let issuer = std::env::var("TOTP_ISSUER").unwrap_or_else(|_| "zzzXBDJBans".to_string());
+            let otpauth_uri = totp::otpauth_uri(&secret, &user.sub, &issuer);
+            (StatusCode::OK, Json(TotpEnrollment { secret, otpauth_uri })).into_response()
+        },
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/2fa/confirm",
+    request_body = TotpCodeRequest,
+    responses(
+        (status = 200, description = "2FA enabled, recovery codes are returned once", body = RecoveryCodes),
+        (status = 400, description = "Invalid code or no pending enrollment"),
+        (status = 409, description = "2FA already enabled")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn confirm_2fa(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Extension(mut current): Extension<Session>,
+    Json(payload): Json<TotpCodeRequest>,
+) -> impl IntoResponse {
+    let pending = sqlx::query_scalar::<_, Option<String>>("SELECT totp_secret FROM admins WHERE id = ? AND totp_enabled = FALSE")
+        .bind(current.admin_id)
+        .fetch_optional(&state.db)
+        .await;
+
+    let secret = match pending {
+        Ok(Some(Some(secret))) => secret,
+        Ok(Some(None)) => return (StatusCode::BAD_REQUEST, Json(json!({ "error": "No pending 2FA enrollment" }))).into_response(),
+        Ok(None) => return (StatusCode::CONFLICT, Json(json!({ "error": "2FA is already enabled" }))).into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    let step = match totp::verify(&secret, &payload.code, chrono::Utc::now().timestamp()) {
+        Some(step) => step,
+        None => return (StatusCode::BAD_REQUEST, Json(json!({ "error": "Invalid 2FA code" }))).into_response(),
+    };
+    let _ = totp::consume_step(&state.cache, current.admin_id, step).await;
+
+    let recovery_codes = totp::generate_recovery_codes();
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        sqlx::query("UPDATE admins SET totp_enabled = TRUE WHERE id = ?")
+            .bind(current.admin_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM admin_recovery_codes WHERE admin_id = ?")
+            .bind(current.admin_id)
+            .execute(&mut *tx)
+            .await?;
+        for code in &recovery_codes {
+            sqlx::query("INSERT INTO admin_recovery_codes (admin_id, code_hash) VALUES (?, ?)")
+                .bind(current.admin_id)
+                .bind(totp::hash_recovery_code(code))
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await
+    }.await;
+
+    if let Err(e) = result {
+        return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+    }
+
+    let _ = crate::utils::log_admin_action(
+        &state.db,
+        &user.sub,
+        "enable_2fa",
+        "Self",
+        "Enabled TOTP 2FA"
+    ).await;
+
+    if current.required_action == Some(RequiredAction::Enroll2fa) {
+        current.required_action = None;
+        if let Err(e) = session::update(&state.cache, &current).await {
+            tracing::error!("Failed to update session {}: {}", current.id, e);
+        }
+    }
+
+    (StatusCode::OK, Json(RecoveryCodes { recovery_codes })).into_response()
+}
+
 #[utoipa::path(
     post,
     path = "/api/auth/logout",
     responses(
-        (status = 200, description = "Logged out")
+        (status = 200, description = "Logged out, the current session is revoked")
+    ),
+    security(
+        ("jwt" = [])
     )
 )]
-pub async fn logout() -> impl IntoResponse {
-    // Stateless JWT, client just drops token. 
-    // We can blacklist token in Redis if stricter.
-    (StatusCode::OK, Json(json!({ "msg": "Logged out" })))
+pub async fn logout(
+    State(state): State<Arc<AppState>>,
+    Extension(current): Extension<Session>,
+) -> impl IntoResponse {
+    match session::revoke(&state.cache, &current).await {
+        Ok(_) => (StatusCode::OK, Json(json!({ "msg": "Logged out" }))).into_response(),
+        Err(e) => (StatusCode::SERVICE_UNAVAILABLE, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/sessions",
+    responses(
+        (status = 200, description = "Active sessions of the current admin", body = Vec<Session>)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_my_sessions(
+    State(state): State<Arc<AppState>>,
+    Extension(current): Extension<Session>,
+) -> impl IntoResponse {
+    match session::list_for_admin(&state.cache, current.admin_id).await {
+        Ok(mut sessions) => {
+            for s in &mut sessions {
+                s.current = s.id == current.id;
+            }
+            (StatusCode::OK, Json(sessions)).into_response()
+        },
+        Err(e) => (StatusCode::SERVICE_UNAVAILABLE, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/auth/sessions/{sid}",
+    params(
+        ("sid" = String, Path, description = "Session ID")
+    ),
+    responses(
+        (status = 200, description = "Session revoked"),
+        (status = 404, description = "Session not found")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn revoke_my_session(
+    State(state): State<Arc<AppState>>,
+    Extension(current): Extension<Session>,
+    Path(sid): Path<String>,
+) -> impl IntoResponse {
+    let target = match session::get(&state.cache, &sid).await {
+        // 只能注销自己的会话
+        Ok(Some(s)) if s.admin_id == current.admin_id => s,
+        Ok(_) => return (StatusCode::NOT_FOUND, Json(json!({ "error": "Session not found" }))).into_response(),
+        Err(e) => return (StatusCode::SERVICE_UNAVAILABLE, e.to_string()).into_response(),
+    };
+
+    match session::revoke(&state.cache, &target).await {
+        Ok(_) => (StatusCode::OK, Json(json!({ "msg": "Session revoked" }))).into_response(),
+        Err(e) => (StatusCode::SERVICE_UNAVAILABLE, e.to_string()).into_response(),
+    }
 }
 
 #[utoipa::path(
@@ -103,20 +641,27 @@ pub async fn logout() -> impl IntoResponse {
         ("jwt" = [])
     )
 )]
-pub async fn me() -> impl IntoResponse {
-    // Need middleware to extract claims. For now placeholder.
-    (StatusCode::OK, Json(json!({ "msg": "Me" })))
+pub async fn me(
+    axum::extract::Extension(claims): axum::extract::Extension<Claims>,
+) -> impl IntoResponse {
+    (StatusCode::OK, Json(json!({
+        "username": claims.sub,
+        "role": claims.role,
+        "permissions": claims.permissions,
+        "server_groups": claims.server_groups,
+    })))
 }
 
 use bcrypt::{hash, DEFAULT_COST};
+use crate::utils::password::{set_password, PasswordPolicy};
 
 #[utoipa::path(
     post,
     path = "/api/auth/change-password",
     request_body = ChangePasswordRequest,
     responses(
-        (status = 200, description = "Password changed successfully"),
-        (status = 400, description = "Invalid old password"),
+        (status = 200, description = "Password changed successfully, all sessions are revoked"),
+        (status = 400, description = "Invalid old password or password policy violated"),
         (status = 401, description = "Unauthorized")
     ),
     security(
@@ -142,13 +687,18 @@ pub async fn change_password(
                 return (StatusCode::BAD_REQUEST, Json(json!({ "error": "Old password incorrect" }))).into_response();
             }
 
-            // 3. Update to New Password
+            // 3. Enforce password policy
+            let policy = PasswordPolicy::from_env();
+            if let Err(e) = policy.validate(&payload.new_password) {
+                return (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))).into_response();
+            }
+            if let Err(e) = policy.check_reuse(&state.db, admin.id, &payload.new_password).await {
+                return (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))).into_response();
+            }
+
+            // 4. Update to New Password
             let hashed = hash(payload.new_password, DEFAULT_COST).unwrap();
-            let update = sqlx::query("UPDATE admins SET password = ? WHERE id = ?")
-                .bind(hashed)
-                .bind(admin.id)
-                .execute(&state.db)
-                .await;
+            let update = set_password(&state.db, admin.id, &hashed, false).await;
 
             match update {
                 Ok(_) => {
@@ -161,6 +711,9 @@ pub async fn change_password(
                         "Changed own password"
                      ).await;
 
+                    // 密码变更后注销所有会话（含刷新令牌），需要重新登录
+                    session::invalidate_admin(&state.cache, admin.id).await;
+
                     (StatusCode::OK, Json(json!({ "message": "Password updated successfully" }))).into_response()
                 },
                 Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
@@ -6,24 +6,47 @@ use axum::{
 };
 use std::sync::Arc;
 use crate::AppState;
-use crate::models::ban::{Ban, PublicBan, CreateBanRequest, UpdateBanRequest};
+use crate::models::ban::{Ban, BanPage, BanSearchQuery, PublicBan, PublicBanPage, PublicBanQuery, CreateBanRequest, UnbanRequest, UpdateBanRequest};
+use crate::models::evidence::PublicEvidence;
 use crate::handlers::auth::Claims;
+use crate::handlers::reason;
+use crate::models::api_key::ServerIdentity;
 use crate::utils::{log_admin_action, calculate_expires_at};
-use chrono::Utc;
+use crate::utils::ip_range::{self, IpRange};
 use serde::Deserialize;
+use sqlx::{MySql, QueryBuilder};
 use serde_json::json;
 
 #[derive(Deserialize)]
-pub struct BanFilter {
+pub struct BanCheckQuery {
     steam_id: Option<String>,
     ip: Option<String>,
+    server_id: Option<i64>,
 }
 
 #[utoipa::path(
     get,
     path = "/api/bans",
+    params(
+        ("page" = Option<u32>, Query, description = "1-based page number (default 1)"),
+        ("page_size" = Option<u32>, Query, description = "Rows per page (default 50, max 200)"),
+        ("status" = Option<String>, Query, description = "active, unbanned or expired"),
+        ("ban_type" = Option<String>, Query, description = "account or ip"),
+        ("admin_name" = Option<String>, Query, description = "Banning admin"),
+        ("server_id" = Option<i64>, Query, description = "Server the ban was issued on"),
+        ("reason" = Option<String>, Query, description = "Text contained in the reason"),
+        ("steam_id" = Option<String>, Query, description = "SteamID in any format"),
+        ("player" = Option<String>, Query, description = "Player name fragment or SteamID"),
+        ("created_from" = Option<String>, Query, description = "Created at or after"),
+        ("created_to" = Option<String>, Query, description = "Created before"),
+        ("expires_from" = Option<String>, Query, description = "Expires at or after"),
+        ("expires_to" = Option<String>, Query, description = "Expires before"),
+        ("sort" = Option<String>, Query, description = "created_at (default), expires_at, id or name"),
+        ("order" = Option<String>, Query, description = "desc (default) or asc")
+    ),
     responses(
-        (status = 200, description = "List all bans", body = Vec<Ban>)
+        (status = 200, description = "One page of matching bans", body = BanPage),
+        (status = 400, description = "Invalid filter or sort")
     ),
     security(
         ("jwt" = [])
@@ -31,18 +54,34 @@ pub struct BanFilter {
 )]
 pub async fn list_bans(
     State(state): State<Arc<AppState>>,
+    Query(params): Query<BanSearchQuery>,
 ) -> impl IntoResponse {
-    // Lazy expire check: Update all active bans that have expired
-    let _ = sqlx::query("UPDATE bans SET status = 'expired' WHERE status = 'active' AND expires_at < NOW()")
-        .execute(&state.db)
-        .await;
+    let filters = match BanFilters::resolve(&params).await {
+        Ok(f) => f,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))).into_response(),
+    };
+    let (page, page_size) = ban_search::page(params.page, params.page_size);
 
-    let bans = sqlx::query_as::<_, Ban>("SELECT * FROM bans ORDER BY created_at DESC")
-        .fetch_all(&state.db)
-        .await;
+    let mut count = QueryBuilder::<MySql>::new("SELECT COUNT(*) FROM bans");
+    filters.push_where(&mut count);
+    let total: i64 = match count.build_query_scalar().fetch_one(&state.db).await {
+        Ok(n) => n,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    let mut select = QueryBuilder::<MySql>::new("SELECT * FROM bans");
+    filters.push_where(&mut select);
+    filters.push_order(&mut select);
+    ban_search::push_page(&mut select, page, page_size);
+    let bans = select.build_query_as::<Ban>().fetch_all(&state.db).await;
 
     match bans {
-        Ok(data) => (StatusCode::OK, Json(data)).into_response(),
+        Ok(mut items) => {
+            if let Err(e) = ban_scope::attach_scopes(&state.db, &mut items).await {
+                return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+            }
+            (StatusCode::OK, Json(BanPage { items, total, page, page_size })).into_response()
+        },
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
     }
 }
@@ -50,33 +89,88 @@ pub async fn list_bans(
 #[utoipa::path(
     get,
     path = "/api/bans/public",
+    params(
+        ("page" = Option<u32>, Query, description = "1-based page number (default 1)"),
+        ("page_size" = Option<u32>, Query, description = "Rows per page (default 50, max 200)"),
+        ("player" = Option<String>, Query, description = "Player name fragment or SteamID in any format"),
+        ("status" = Option<String>, Query, description = "active, unbanned or expired")
+    ),
     responses(
-        (status = 200, description = "List public bans", body = Vec<PublicBan>)
+        (status = 200, description = "One page of public bans", body = PublicBanPage)
     )
 )]
 pub async fn list_public_bans(
     State(state): State<Arc<AppState>>,
+    Query(params): Query<PublicBanQuery>,
 ) -> impl IntoResponse {
-    // Lazy expire check: Update all active bans that have expired
-    let _ = sqlx::query("UPDATE bans SET status = 'expired' WHERE status = 'active' AND expires_at < NOW()")
-        .execute(&state.db)
-        .await;
+    let search = BanSearchQuery {
+        player: params.player,
+        status: params.status,
+        ..Default::default()
+    };
+    let filters = match BanFilters::resolve(&search).await {
+        Ok(f) => f,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))).into_response(),
+    };
+    let (page, page_size) = ban_search::page(params.page, params.page_size);
+
+    let mut count = QueryBuilder::<MySql>::new("SELECT COUNT(*) FROM bans");
+    filters.push_where(&mut count);
+    let total: i64 = match count.build_query_scalar().fetch_one(&state.db).await {
+        Ok(n) => n,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
 
     // Select specific columns to avoid exposing IP
-    let bans = sqlx::query_as::<_, PublicBan>(
-        "SELECT id, name, steam_id, steam_id_3, steam_id_64, reason, duration, status, admin_name, created_at, expires_at FROM bans ORDER BY created_at DESC"
-    )
-    .fetch_all(&state.db)
-    .await;
+    let mut select = QueryBuilder::<MySql>::new(
+        "SELECT id, name, steam_id, steam_id_3, steam_id_64, reason, duration, status, admin_name, created_at, expires_at FROM bans"
+    );
+    filters.push_where(&mut select);
+    filters.push_order(&mut select);
+    ban_search::push_page(&mut select, page, page_size);
+    let bans = select.build_query_as::<PublicBan>().fetch_all(&state.db).await;
+
+    let mut bans = match bans {
+        Ok(data) => data,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    if bans.is_empty() {
+        return (StatusCode::OK, Json(PublicBanPage { items: bans, total, page, page_size })).into_response();
+    }
 
-    match bans {
-        Ok(data) => (StatusCode::OK, Json(data)).into_response(),
+    // 附上本页封禁中标记为公开的证据下载链接
+    let mut evidence = QueryBuilder::<MySql>::new(
+        "SELECT id, ban_id, file_name, content_type, size_bytes, sha256, description FROM ban_evidence WHERE is_public = TRUE AND ban_id IN ("
+    );
+    let mut ids = evidence.separated(", ");
+    for ban in &bans {
+        ids.push_bind(ban.id);
+    }
+    evidence.push(") ORDER BY created_at");
+    let evidence = evidence.build_query_as::<PublicEvidence>().fetch_all(&state.db).await;
+
+    match evidence {
+        Ok(items) => {
+            let mut by_ban: std::collections::HashMap<i64, Vec<PublicEvidence>> = std::collections::HashMap::new();
+            for mut item in items {
+                item.url = format!("/api/bans/public/evidence/{}", item.id);
+                by_ban.entry(item.ban_id).or_default().push(item);
+            }
+            for ban in &mut bans {
+                ban.evidence = by_ban.remove(&ban.id).unwrap_or_default();
+            }
+            (StatusCode::OK, Json(PublicBanPage { items: bans, total, page, page_size })).into_response()
+        },
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
     }
 }
 
 // ... imports
 use crate::services::steam_api::SteamService;
+use crate::services::ban_search::{self, BanFilters};
+use crate::services::{ban_scope, enforcement, escalation, evidence, identity, ip_match, revision};
+use crate::services::ip_match::MatchOutcome;
+use crate::services::enforcement::LinkedTarget;
 
 // ... check_ban
 #[utoipa::path(
@@ -84,19 +178,22 @@ use crate::services::steam_api::SteamService;
     path = "/api/check_ban",
     params(
         ("steam_id" = Option<String>, Query, description = "SteamID to check"),
-        ("ip" = Option<String>, Query, description = "IP to check")
+        ("ip" = Option<String>, Query, description = "IP to check"),
+        ("server_id" = Option<i64>, Query, description = "Requesting server; without it group-scoped bans are matched everywhere")
     ),
     responses(
         (status = 200, description = "Ban details if banned", body = Ban),
         (status = 404, description = "Not banned")
     ),
     security(
-        ("jwt" = [])
+        ("jwt" = []),
+        ("api_key" = [])
     )
 )]
 pub async fn check_ban(
     State(state): State<Arc<AppState>>,
-    Query(params): Query<BanFilter>,
+    identity: Option<Extension<ServerIdentity>>,
+    Query(params): Query<BanCheckQuery>,
 ) -> impl IntoResponse {
     if params.steam_id.is_none() && params.ip.is_none() {
         return (StatusCode::BAD_REQUEST, "Missing steam_id or ip").into_response();
@@ -105,6 +202,18 @@ pub async fn check_ban(
     let steam_id = params.steam_id.unwrap_or_default();
     let ip = params.ip.unwrap_or_default();
 
+    // Only bans scoped to the requesting server's group (or global ones) apply.
+    // A server API key always checks on behalf of its own server.
+    let group_id = match (identity, params.server_id) {
+        (Some(Extension(identity)), _) => Some(identity.server.group_id),
+        (None, Some(server_id)) => match ban_scope::server_group(&state.db, server_id).await {
+            Ok(Some(g)) => Some(g),
+            Ok(None) => return (StatusCode::BAD_REQUEST, "Unknown server_id").into_response(),
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        },
+        (None, None) => None,
+    };
+
     // CONVERSION: Ensure SteamID is in standard SteamID2 format (STEAM_0:...) for DB lookup
     // 将输入的 SteamID 转换为 steam_id_64 格式进行匹配
     let mut steam_id_64 = String::new();
@@ -115,129 +224,118 @@ pub async fn check_ban(
         }
     }
     
+    // 记录本次进服看到的 (SteamID64, IP)，用于关联账号识别
+    if !steam_id_64.is_empty() && !ip.is_empty() {
+        identity::observe(&state.db, &steam_id_64, &ip, None, "join").await;
+    }
+
     // 1. Check for DIRECT Account Ban (优先使用 steam_id_64 匹配)
     let account_ban = if !steam_id_64.is_empty() {
-        sqlx::query_as::<_, Ban>(
-            "SELECT * FROM bans WHERE status = 'active' AND (steam_id_64 = ? OR steam_id = ?) LIMIT 1"
-        )
+        sqlx::query_as::<_, Ban>(&format!(
+            "SELECT * FROM bans b WHERE status = 'active' AND (expires_at IS NULL OR expires_at > NOW()) AND (steam_id_64 = ? OR steam_id = ?) AND {} LIMIT 1",
+            ban_scope::SCOPE_CONDITION
+        ))
         .bind(&steam_id_64)
         .bind(&steam_id)
+        .bind(group_id)
+        .bind(group_id)
         .fetch_optional(&state.db)
         .await
     } else {
-        sqlx::query_as::<_, Ban>(
-            "SELECT * FROM bans WHERE status = 'active' AND steam_id = ? LIMIT 1"
-        )
+        sqlx::query_as::<_, Ban>(&format!(
+            "SELECT * FROM bans b WHERE status = 'active' AND (expires_at IS NULL OR expires_at > NOW()) AND steam_id = ? AND {} LIMIT 1",
+            ban_scope::SCOPE_CONDITION
+        ))
         .bind(&steam_id)
+        .bind(group_id)
+        .bind(group_id)
         .fetch_optional(&state.db)
         .await
     };
 
+    // 已过期但尚未被定时任务处理的封禁已在查询中排除
     match account_ban {
-        Ok(Some(b)) => {
-
-            // Check expiration
-            if let Some(expires_at) = b.expires_at {
-                if Utc::now() > expires_at {
-
-                    let _ = sqlx::query("UPDATE bans SET status = 'expired' WHERE id = ?")
-                        .bind(b.id).execute(&state.db).await;
-                    // Expired - Do NOT return yet. Treat as not banned, proceed to check IP.
-                } else {
-                    return (StatusCode::OK, Json(b)).into_response();
-                }
-            } else {
-                return (StatusCode::OK, Json(b)).into_response();
-            }
-        },
+        Ok(Some(b)) => return (StatusCode::OK, Json(b)).into_response(),
         Err(e) => {
              tracing::error!("CHECK_BAN: DB Error on Account Check: {}", e);
              return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
         },
-        Ok(None) => {
-
-        }
+        Ok(None) => {}
     }
 
     // 2. Check for IP Ban (Matches IP AND ban_type = 'ip')
 
-    let ip_ban = sqlx::query_as::<_, Ban>(
-        "SELECT * FROM bans WHERE status = 'active' AND ip = ? AND ban_type = 'ip' LIMIT 1"
-    )
-    .bind(&ip)
-    .fetch_optional(&state.db)
-    .await;
+    // IP bans may cover a CIDR / range; an unparseable IP cannot match any of them
+    let ip_ban = match ip_range::parse_player_ip(&ip).map(ip_range::key) {
+        Some(ip_key) => sqlx::query_as::<_, Ban>(&format!(
+            "SELECT * FROM bans b WHERE status = 'active' AND (expires_at IS NULL OR expires_at > NOW()) AND ban_type = 'ip' AND ip_start <= ? AND ip_end >= ? AND {} ORDER BY ip_start DESC LIMIT 1",
+            ban_scope::SCOPE_CONDITION
+        ))
+        .bind(&ip_key)
+        .bind(&ip_key)
+        .bind(group_id)
+        .bind(group_id)
+        .fetch_optional(&state.db)
+        .await,
+        None => Ok(None),
+    };
+
+    // 同 IP / 关联账号的处理由 IP 匹配策略决定（off / flag / auto + 豁免名单）
+    let known_name = identity::last_name(&state.db, &steam_id_64).await;
+    let target = LinkedTarget {
+        name: known_name.as_deref().unwrap_or("Auto-Banned"),
+        steam_id: &steam_id,
+        steam_id_64: Some(steam_id_64.as_str()).filter(|s| !s.is_empty()),
+        ip: &ip,
+        server_id: None,
+    };
 
     match ip_ban {
+        Ok(Some(b)) if steam_id.is_empty() => return (StatusCode::OK, Json(b)).into_response(),
         Ok(Some(b)) => {
-
-             // Check expiration for the IP ban
-            if let Some(expires_at) = b.expires_at {
-                if Utc::now() > expires_at {
-
-                    let _ = sqlx::query("UPDATE bans SET status = 'expired' WHERE id = ?")
-                        .bind(b.id).execute(&state.db).await;
-                    return (StatusCode::NOT_FOUND, Json("Not banned (Expired)")).into_response();
-                }
-            }
-
             // HIT! IP is banned, and user has no personal ban.
-            tracing::info!("CHECK_BAN: IP Ban Hit for new identity! Triggering Auto-Ban. IP: {}, New SteamID: {}", ip, steam_id);
-
-            // Create NEW Ban Record
-            let reason = "同IP关联封禁 (Different account repeated IP login)".to_string();
-            // Inherit expiration from the parent IP ban
-            let expires_at = b.expires_at; 
-            
-            let insert_result = sqlx::query(
-                "INSERT INTO bans (name, steam_id, ip, ban_type, reason, duration, admin_name, expires_at, created_at, status, server_id) VALUES (?, ?, ?, ?, ?, ?, ?, ?, NOW(), 'active', ?)"
-            )
-            .bind("Auto-Banned") 
-            .bind(&steam_id)
-            .bind(&ip)
-            .bind("account") 
-            .bind(&reason)
-            .bind(&b.duration) 
-            .bind("System (IP Match)")
-            .bind(expires_at)
-            .bind(b.server_id)
-            .execute(&state.db)
-            .await;
-
-            match insert_result {
-                Ok(res) => {
-                    let new_id = res.last_insert_id() as i64;
-                    tracing::info!("CHECK_BAN: Auto-Ban Created Successfully. New ID: {}", new_id);
-                    let new_ban = Ban {
-                        id: new_id,
-                        name: "Auto-Banned".to_string(),
-                        steam_id: steam_id,
-                        steam_id_3: None,
-                        steam_id_64: Some(steam_id_64.clone()),
-                        ip: ip,
-                        ban_type: "account".to_string(),
-                        reason: Some(reason),
-                        duration: b.duration,
-                        status: "active".to_string(),
-                        admin_name: Some("System (IP Match)".to_string()),
-                        created_at: Some(Utc::now()),
-                        expires_at: expires_at,
-                        server_id: b.server_id
-                    };
+            let target = LinkedTarget { server_id: b.server_id, ..target };
+            match ip_match::handle(&state, &b, &target, "join", "同IP关联封禁 (Different account repeated IP login)", "System (IP Match)").await {
+                Ok(MatchOutcome::Banned(new_ban)) => {
+                    tracing::info!("CHECK_BAN: IP Ban Hit for new identity {} on {}, auto-ban #{} created", steam_id, ip, new_ban.id);
                     return (StatusCode::OK, Json(new_ban)).into_response();
                 },
+                // 豁免或已驳回：不受该 IP 封禁影响，继续检查关联账号
+                Ok(MatchOutcome::Exempt) => {},
+                // 仅标记 / 未开启：不为新账号创建封禁，但 IP 封禁本身仍然生效
+                Ok(MatchOutcome::Flagged) | Ok(MatchOutcome::Ignored) => return (StatusCode::OK, Json(b)).into_response(),
                 Err(e) => {
-                    tracing::error!("CHECK_BAN: Failed to auto-create ban: {}", e);
-                    // If insert fails, still return the IP ban so they are blocked
+                    tracing::error!("CHECK_BAN: Failed to apply IP match policy: {}", e);
+                    // If the policy fails, still return the IP ban so they are blocked
                     return (StatusCode::OK, Json(b)).into_response();
                 }
             }
         },
-        Ok(None) => {
+        Ok(None) => {}
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
 
-            return (StatusCode::NOT_FOUND, Json("Not banned")).into_response();
+    // 3. 身份图中可信度足够的关联账号有开启了关联封禁的有效封禁
+    if steam_id_64.is_empty() {
+        return (StatusCode::NOT_FOUND, Json("Not banned")).into_response();
+    }
+    match identity::banned_link(&state.db, &steam_id_64, group_id).await {
+        Ok(Some((parent, link))) => {
+            tracing::info!("CHECK_BAN: {} is linked to banned account {} (confidence {})", steam_id_64, link.steam_id_64, link.confidence);
+            let reason = format!("关联账号封禁 (Linked to banned account {}, confidence {:.2})", link.steam_id_64, link.confidence);
+            let target = LinkedTarget { server_id: parent.server_id, ..target };
+            match ip_match::handle(&state, &parent, &target, "alt_link", &reason, "System (Alt Link)").await {
+                Ok(MatchOutcome::Banned(new_ban)) => (StatusCode::OK, Json(new_ban)).into_response(),
+                // 关联账号本身没有被封禁的地址，未自动封禁时直接放行
+                Ok(_) => (StatusCode::NOT_FOUND, Json("Not banned")).into_response(),
+                Err(e) => {
+                    tracing::error!("CHECK_BAN: Failed to create linked ban: {}", e);
+                    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+                }
+            }
         },
-        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, Json("Not banned")).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
     }
 }
 
@@ -253,7 +351,8 @@ pub async fn check_ban(
         (status = 400, description = "Missing steam_id")
     ),
     security(
-        ("jwt" = [])
+        ("jwt" = []),
+        ("api_key" = [])
     )
 )]
 pub async fn check_global_ban(
@@ -301,7 +400,8 @@ pub struct BulkBanCheckRequest {
         (status = 200, description = "Bulk ban details", body = std::collections::HashMap<String, Option<serde_json::Value>>)
     ),
     security(
-        ("jwt" = [])
+        ("jwt" = []),
+        ("api_key" = [])
     )
 )]
 pub async fn check_global_ban_bulk(
@@ -373,7 +473,33 @@ pub async fn create_ban(
     Extension(user): Extension<Claims>,
     Json(payload): Json<CreateBanRequest>,
 ) -> impl IntoResponse {
-    let expires_at = calculate_expires_at(&payload.duration);
+    let group_id = match resolve_ban_group(&user, payload.group_id) {
+        Ok(g) => g,
+        Err((status, msg)) => return (status, Json(msg)).into_response(),
+    };
+
+    let scope = match resolve_ban_scope(&user, payload.server_group_ids, group_id) {
+        Ok(s) => s,
+        Err((status, msg)) => return (status, Json(msg)).into_response(),
+    };
+
+    let template = match reason::requested_template(&state, payload.reason_template_id).await {
+        Ok(t) => t,
+        Err(resp) => return resp,
+    };
+    let ban_type = match payload.ban_type.trim() {
+        "" => template.as_ref().and_then(|t| t.default_ban_type.clone()).unwrap_or_else(|| "account".to_string()),
+        t => t.to_string(),
+    };
+
+    let ip_range = if ban_type == "ip" {
+        match check_ip_range(&user, &payload.ip, payload.confirm_wide_range) {
+            Ok(r) => Some(r),
+            Err((status, msg)) => return (status, Json(msg)).into_response(),
+        }
+    } else {
+        None
+    };
 
     // 解析输入的 SteamID 为各种格式
     let steam_service = SteamService::new();
@@ -386,30 +512,76 @@ pub async fn create_ban(
     let steam_id_3 = steam_service.id64_to_id3(&steam_id_64)
         .unwrap_or_default();
 
+    // 选择了原因分类时，根据该玩家在此分类下的前科取升级阶梯的下一级
+    let mut duration = payload.duration.clone();
+    let mut reason = match &template {
+        Some(t) => Some(t.reason(payload.reason.as_deref())),
+        None => payload.reason.clone(),
+    };
+    let mut ladder_step = None;
+    if let Some(category_id) = payload.category_id {
+        match escalation::choose(&state.db, category_id, &steam_id_64, &payload.duration).await {
+            Ok(Some(choice)) => {
+                duration = choice.duration;
+                ladder_step = choice.step;
+                reason = reason.filter(|r| !r.trim().is_empty()).or(Some(choice.category_name));
+            }
+            Ok(None) => return (StatusCode::BAD_REQUEST, Json(json!({ "error": "Unknown ban category" }))).into_response(),
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        }
+    }
+
+    // 未填写时长且没有升级阶梯时，使用原因模板的默认时长
+    if duration.trim().is_empty() {
+        if let Some(default) = template.as_ref().and_then(|t| t.default_duration.clone()) {
+            duration = default;
+        }
+    }
+
+    let expires_at = match calculate_expires_at(&duration) {
+        Ok(t) => t,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({ "error": e.to_string() }))).into_response(),
+    };
+
     let result = sqlx::query(
-        "INSERT INTO bans (name, steam_id, steam_id_3, steam_id_64, ip, ban_type, reason, duration, admin_name, expires_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        "INSERT INTO bans (name, steam_id, steam_id_3, steam_id_64, ip, ip_start, ip_end, ban_type, reason, duration, admin_name, expires_at, group_id, category_id, ladder_step, reason_template_id, extend_to_linked) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
     )
     .bind(&payload.name)
     .bind(&steam_id_2)
     .bind(&steam_id_3)
     .bind(&steam_id_64)
-    .bind(&payload.ip)
-    .bind(&payload.ban_type)
-    .bind(&payload.reason)
-    .bind(&payload.duration)
+    .bind(payload.ip.trim())
+    .bind(ip_range.map(|r| r.start_key()))
+    .bind(ip_range.map(|r| r.end_key()))
+    .bind(&ban_type)
+    .bind(&reason)
+    .bind(&duration)
     .bind(&payload.admin_name)
     .bind(expires_at)
+    .bind(group_id)
+    .bind(payload.category_id)
+    .bind(ladder_step)
+    .bind(payload.reason_template_id)
+    .bind(payload.extend_to_linked.unwrap_or(ban_type == "ip"))
     .execute(&state.db)
     .await;
 
     match result {
-        Ok(_) => {
+        Ok(res) => {
+            if let Err(e) = ban_scope::set_scope(&state.db, res.last_insert_id() as i64, &scope).await {
+                tracing::error!("Failed to store scope for new ban: {}", e);
+                return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+            }
+            revision::track(&state.db, res.last_insert_id() as i64, None, "create", &user.sub).await;
             let _ = log_admin_action(
                 &state.db, 
                 &user.sub, 
                 "create_ban", 
                 &format!("User: {}, SteamID64: {}", payload.name, steam_id_64), 
-                &format!("Reason: {}, Duration: {}", payload.reason.clone().unwrap_or_default(), payload.duration)
+                &match ladder_step {
+                    Some(step) => format!("Reason: {}, Duration: {} (ladder step {})", reason.unwrap_or_default(), duration, step),
+                    None => format!("Reason: {}, Duration: {}", reason.unwrap_or_default(), duration),
+                }
             ).await;
             (StatusCode::CREATED, Json("Ban created")).into_response()
         },
@@ -417,6 +589,59 @@ pub async fn create_ban(
     }
 }
 
+/// Picks the owning group for a new ban based on the admin's scope.
+pub fn resolve_ban_group(user: &Claims, requested: Option<i64>) -> Result<Option<i64>, (StatusCode, &'static str)> {
+    match (&user.server_groups, requested) {
+        (None, group_id) => Ok(group_id),
+        (Some(groups), Some(g)) if groups.contains(&g) => Ok(Some(g)),
+        (Some(_), Some(_)) => Err((StatusCode::FORBIDDEN, "Server group out of scope")),
+        (Some(groups), None) if groups.len() == 1 => Ok(Some(groups[0])),
+        (Some(_), None) => Err((StatusCode::BAD_REQUEST, "group_id is required for admins scoped to several server groups")),
+    }
+}
+
+/// Works out which server groups a ban is enforced on. Scoped admins can only ban
+/// within their own groups and default to the owning group; global admins default to global.
+fn resolve_ban_scope(
+    user: &Claims,
+    requested: Option<Vec<i64>>,
+    owner_group: Option<i64>,
+) -> Result<Vec<i64>, (StatusCode, &'static str)> {
+    let scope = match (requested, owner_group) {
+        (Some(groups), _) if !groups.is_empty() => groups,
+        (_, Some(g)) if !user.is_global() => vec![g],
+        _ => Vec::new(),
+    };
+
+    if !user.is_global() && (scope.is_empty() || scope.iter().any(|g| !user.can_access_group(*g))) {
+        return Err((StatusCode::FORBIDDEN, "Ban scope must stay within your server groups"));
+    }
+    Ok(scope)
+}
+
+/// Scoped admins may only touch bans owned by one of their groups; global bans are off limits.
+pub fn ban_in_scope(user: &Claims, group_id: Option<i64>) -> bool {
+    match group_id {
+        Some(g) => user.can_access_group(g),
+        None => user.is_global(),
+    }
+}
+
+/// Parses the address of an IP ban. Very wide ranges (e.g. a whole provider) are only
+/// accepted from a super admin who explicitly confirms them.
+pub fn check_ip_range(user: &Claims, ip: &str, confirm_wide: bool) -> Result<IpRange, (StatusCode, String)> {
+    let range = IpRange::parse(ip).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    if range.is_wide() {
+        if user.role != "super_admin" {
+            return Err((StatusCode::FORBIDDEN, format!("IP range '{}' is too wide; only a super admin can ban it", ip.trim())));
+        }
+        if !confirm_wide {
+            return Err((StatusCode::BAD_REQUEST, format!("IP range '{}' is very wide; set confirm_wide_range to ban it", ip.trim())));
+        }
+    }
+    Ok(range)
+}
+
 #[utoipa::path(
     put,
     path = "/api/bans/{id}",
@@ -426,6 +651,7 @@ pub async fn create_ban(
     request_body = UpdateBanRequest,
     responses(
         (status = 200, description = "Ban updated"),
+        (status = 400, description = "Invalid status, duration or IP range"),
         (status = 404, description = "Ban not found")
     ),
     security(
@@ -438,50 +664,133 @@ pub async fn update_ban(
     Path(id): Path<i64>,
     Json(payload): Json<UpdateBanRequest>,
 ) -> impl IntoResponse {
-    if let Some(status) = payload.status {
-        let _ = sqlx::query("UPDATE bans SET status = ? WHERE id = ?")
-            .bind(status).bind(id)
-            .execute(&state.db).await;
-    }
-    // ... (other fields name, steam_id etc same as before)
-    if let Some(name) = payload.name {
-         let _ = sqlx::query("UPDATE bans SET name = ? WHERE id = ?")
-            .bind(name).bind(id)
-            .execute(&state.db).await;
-    }
-    if let Some(steam_id) = payload.steam_id {
-         let _ = sqlx::query("UPDATE bans SET steam_id = ? WHERE id = ?")
-            .bind(steam_id).bind(id)
-            .execute(&state.db).await;
-    }
-    if let Some(ip) = payload.ip {
-         let _ = sqlx::query("UPDATE bans SET ip = ? WHERE id = ?")
-            .bind(ip).bind(id)
-            .execute(&state.db).await;
-    }
-    if let Some(ban_type) = payload.ban_type {
-         let _ = sqlx::query("UPDATE bans SET ban_type = ? WHERE id = ?")
-            .bind(ban_type).bind(id)
-            .execute(&state.db).await;
-    }
-    if let Some(reason) = payload.reason {
-         let _ = sqlx::query("UPDATE bans SET reason = ? WHERE id = ?")
-            .bind(reason).bind(id)
-            .execute(&state.db).await;
-    }
-    if let Some(duration) = payload.duration {
-         let expires_at = calculate_expires_at(&duration);
-         let _ = sqlx::query("UPDATE bans SET duration = ?, expires_at = ? WHERE id = ?")
-            .bind(duration).bind(expires_at).bind(id)
-            .execute(&state.db).await;
+    if !user.is_global() {
+        let group_id: Option<Option<i64>> = sqlx::query_scalar("SELECT group_id FROM bans WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&state.db)
+            .await
+            .unwrap_or(None);
+
+        match group_id {
+            None => return (StatusCode::NOT_FOUND, "Ban not found").into_response(),
+            Some(g) if !ban_in_scope(&user, g) => {
+                return (StatusCode::FORBIDDEN, Json("Ban belongs to a server group out of scope")).into_response();
+            }
+            Some(_) => {}
+        }
+    }
+
+    if let Some(groups) = &payload.server_group_ids {
+        if !user.is_global() && (groups.is_empty() || groups.iter().any(|g| !user.can_access_group(*g))) {
+            return (StatusCode::FORBIDDEN, Json("Ban scope must stay within your server groups")).into_response();
+        }
     }
 
+    // 解除封禁（解封或提前到期）必须附带原因并下发 sm_unban，只能通过专门的解封接口
+    match payload.status.as_deref() {
+        None | Some("active") => {}
+        Some("unbanned") | Some("expired") => {
+            return (StatusCode::BAD_REQUEST, Json(json!({ "error": "Use POST /api/bans/{id}/unban with a reason to lift a ban" }))).into_response();
+        }
+        Some(other) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({ "error": format!("Invalid status '{}', only 'active' can be set here", other) }))).into_response();
+        }
+    }
+
+    let before = match revision::snapshot(&state.db, id).await {
+        Ok(Some(s)) => s,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Ban not found").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    // 时长无效时直接拒绝，而不是当作永久封禁
+    let new_expiry = match payload.duration.as_deref().map(calculate_expires_at).transpose() {
+        Ok(t) => t,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({ "error": e.to_string() }))).into_response(),
+    };
+
+    // ban_type 与 ip 一起决定封禁的地址范围，任一变化都重新计算
+    let ip_update = if payload.ip.is_some() || payload.ban_type.is_some() {
+        let current = sqlx::query_as::<_, (String, String)>("SELECT ban_type, ip FROM bans WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&state.db)
+            .await;
+        let (ban_type, ip) = match current {
+            Ok(Some((ban_type, ip))) => (payload.ban_type.unwrap_or(ban_type), payload.ip.unwrap_or(ip)),
+            Ok(None) => return (StatusCode::NOT_FOUND, "Ban not found").into_response(),
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        };
+        let range = if ban_type == "ip" {
+            match check_ip_range(&user, &ip, payload.confirm_wide_range) {
+                Ok(r) => Some(r),
+                Err((status, msg)) => return (status, Json(msg)).into_response(),
+            }
+        } else {
+            None
+        };
+        Some((ban_type, ip.trim().to_string(), range))
+    } else {
+        None
+    };
+
+    let result: Result<(), sqlx::Error> = async {
+        // 重新生效时清除上一次解封的记录
+        if payload.status.is_some() {
+            sqlx::query("UPDATE bans SET status = 'active', unbanned_by = NULL, unbanned_at = NULL, unban_reason = NULL WHERE id = ?")
+                .bind(id)
+                .execute(&state.db).await?;
+        }
+        if let Some(name) = payload.name {
+            sqlx::query("UPDATE bans SET name = ? WHERE id = ?")
+                .bind(name).bind(id)
+                .execute(&state.db).await?;
+        }
+        if let Some(steam_id) = payload.steam_id {
+            sqlx::query("UPDATE bans SET steam_id = ? WHERE id = ?")
+                .bind(steam_id).bind(id)
+                .execute(&state.db).await?;
+        }
+        if let Some((ban_type, ip, range)) = ip_update {
+            sqlx::query("UPDATE bans SET ban_type = ?, ip = ?, ip_start = ?, ip_end = ? WHERE id = ?")
+                .bind(ban_type)
+                .bind(ip)
+                .bind(range.map(|r| r.start_key()))
+                .bind(range.map(|r| r.end_key()))
+                .bind(id)
+                .execute(&state.db).await?;
+        }
+        if let Some(reason) = payload.reason {
+            sqlx::query("UPDATE bans SET reason = ? WHERE id = ?")
+                .bind(reason).bind(id)
+                .execute(&state.db).await?;
+        }
+        if let (Some(duration), Some(expires_at)) = (payload.duration, new_expiry) {
+            sqlx::query("UPDATE bans SET duration = ?, expires_at = ? WHERE id = ?")
+                .bind(duration).bind(expires_at).bind(id)
+                .execute(&state.db).await?;
+        }
+        if let Some(extend) = payload.extend_to_linked {
+            sqlx::query("UPDATE bans SET extend_to_linked = ? WHERE id = ?")
+                .bind(extend).bind(id)
+                .execute(&state.db).await?;
+        }
+        if let Some(groups) = payload.server_group_ids {
+            ban_scope::set_scope(&state.db, id, &groups).await?;
+        }
+        Ok(())
+    }.await;
+
+    // 部分字段可能已经写入，仍然记录修订以保留痕迹
+    let changes = revision::track(&state.db, id, Some(&before), "update", &user.sub).await;
+    if let Err(e) = result {
+        return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+    }
     let _ = log_admin_action(
         &state.db,
         &user.sub,
         "update_ban",
         &format!("BanID: {}", id),
-        "Updated ban details"
+        &revision::summarize(&changes)
     ).await;
 
     (StatusCode::OK, Json("Ban updated")).into_response()
@@ -494,9 +803,9 @@ pub async fn update_ban(
         ("id" = i64, Path, description = "Ban ID")
     ),
     responses(
-        (status = 200, description = "Ban deleted"),
+        (status = 200, description = "Ban record permanently deleted"),
         (status = 404, description = "Ban not found"),
-        (status = 403, description = "Forbidden")
+        (status = 403, description = "Only super admins can purge bans")
     ),
     security(
         ("jwt" = [])
@@ -509,13 +818,12 @@ pub async fn delete_ban(
 ) -> impl IntoResponse {
     tracing::info!("DELETE /api/bans/{} requested by user: {}, role: {}", id, user.sub, user.role);
 
-    // 1. Permission Check
+    // 彻底删除会丢失玩家的封禁记录与修改历史，日常解封请使用 POST /api/bans/{id}/unban
     if user.role != "super_admin" {
-        tracing::warn!("Permission denied for user {}", user.sub);
-        return (StatusCode::FORBIDDEN, Json("Only super admins can delete bans")).into_response();
+        return (StatusCode::FORBIDDEN, Json(json!({ "error": "Only super admins can purge bans; use unban instead" }))).into_response();
     }
 
-    // 2. Fetch Ban Details (for RCON unban)
+    // 1. Fetch Ban Details (for RCON unban)
     // Removed unwrap_or(None) to see actual error if mapping fails
     let ban_query = sqlx::query_as::<_, Ban>("SELECT * FROM bans WHERE id = ?")
         .bind(id)
@@ -534,10 +842,15 @@ pub async fn delete_ban(
         }
     };
 
+    if !ban_in_scope(&user, ban.group_id) {
+        return (StatusCode::FORBIDDEN, Json("Ban belongs to a server group out of scope")).into_response();
+    }
 
 
-    // 3. Delete from DB first (for fast response)
 
+    // 2. Delete from DB first (for fast response)
+
+    let evidence_files = evidence::hashes_for_ban(&state.db, id).await;
     let result = sqlx::query("DELETE FROM bans WHERE id = ?")
         .bind(id)
         .execute(&state.db)
@@ -548,61 +861,22 @@ pub async fn delete_ban(
             if res.rows_affected() == 0 {
                 tracing::warn!("DELETE executed but 0 rows affected for ID {}", id);
             } else {
-                // 4. Spawn RCON Unban task (Fire-and-forget)
-                // Fetch servers inside the handler first to avoid lifetime issues or clone valid data
-                let servers_result = sqlx::query_as::<_, crate::models::server::Server>("SELECT * FROM servers")
-                    .fetch_all(&state.db)
-                    .await;
-
-                if let Ok(servers) = servers_result {
-                    let _ban_clone = ban.clone(); // Ban struct needs simple Clone derive or manual clone
-                    // If Ban doesn't implement Clone, we might need to construct a lightweight struct or ensure it does.
-                    // Assuming Ban implements Clone (it normally derives FromRow, Debug, Serialize, Deserialize - let's check or just clone fields)
-                    // Let's manually reconstruct or assume Clone if easy. 
-                    // Actually, let's just use the data we need: steam_id and ip.
-                    let steam_id = ban.steam_id.clone();
-                    let ip = ban.ip.clone();
-                    let ban_name = ban.name.clone();
-
-                    tokio::spawn(async move {
-                        tracing::debug!("Background task: Sending unban commands to {} servers for {}", servers.len(), ban_name);
-                        use crate::utils::rcon::send_command;
-                        use futures::future::join_all;
-                        
-                        let tasks: Vec<_> = servers.into_iter().map(|server| {
-                            let steam_id = steam_id.clone();
-                            let ip = ip.clone();
-                            async move {
-                                let address = format!("{}:{}", server.ip, server.port);
-                                let pwd = server.rcon_password.unwrap_or_default();
-                                
-                                // Unban SteamID
-                                if !steam_id.is_empty() {
-                                    let cmd = format!("sm_unban \"{}\"", steam_id);
-                                    let _ = send_command(&address, &pwd, &cmd).await;
-                                }
-                                
-                                // Unban IP
-                                if !ip.is_empty() {
-                                    let cmd = format!("sm_unban \"{}\"", ip);
-                                    let _ = send_command(&address, &pwd, &cmd).await;
-                                }
-                            }
-                        }).collect();
-
-                        join_all(tasks).await;
-                    });
+                // 3. Spawn RCON Unban task (Fire-and-forget); already lifted bans need no unban
+                if ban.status == "active" {
+                    enforcement::queue_rcon_unban(&state, &ban);
                 }
+                // 证据记录随封禁级联删除，清理不再被引用的文件
+                evidence::remove_unused(&state.db, &evidence_files).await;
             }
 
             let _ = log_admin_action(
                 &state.db,
                 &user.sub,
-                "delete_ban",
+                "purge_ban",
                 &format!("BanID: {}, Target: {} ({})", id, ban.name, ban.steam_id),
-                "Deleted ban (Unban commands queued)"
+                &format!("Purged {} ban record{}", ban.status, if ban.status == "active" { " (Unban commands queued)" } else { "" })
             ).await;
-            (StatusCode::OK, Json("Ban deleted, unban process started in background")).into_response()
+            (StatusCode::OK, Json("Ban purged")).into_response()
         },
         Err(e) => {
             tracing::error!("Failed to delete ban from DB: {}", e);
@@ -610,3 +884,56 @@ pub async fn delete_ban(
         },
     }
 }
+
+#[utoipa::path(
+    post,
+    path = "/api/bans/{id}/unban",
+    params(
+        ("id" = i64, Path, description = "Ban ID")
+    ),
+    request_body = UnbanRequest,
+    responses(
+        (status = 200, description = "Ban lifted, unban commands queued"),
+        (status = 400, description = "Missing reason"),
+        (status = 404, description = "Ban not found"),
+        (status = 409, description = "Ban is not active")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn unban_ban(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path(id): Path<i64>,
+    Json(payload): Json<UnbanRequest>,
+) -> impl IntoResponse {
+    let reason = payload.reason.trim();
+    if reason.is_empty() {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": "An unban reason is required" }))).into_response();
+    }
+
+    let ban = match sqlx::query_as::<_, Ban>("SELECT * FROM bans WHERE id = ?").bind(id).fetch_optional(&state.db).await {
+        Ok(Some(b)) => b,
+        Ok(None) => return (StatusCode::NOT_FOUND, Json(json!({ "error": "Ban not found" }))).into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    if !ban_in_scope(&user, ban.group_id) {
+        return (StatusCode::FORBIDDEN, Json(json!({ "error": "Ban belongs to a server group out of scope" }))).into_response();
+    }
+
+    match enforcement::unban(&state, &ban, &user.sub, reason).await {
+        Ok(true) => {
+            let _ = log_admin_action(
+                &state.db,
+                &user.sub,
+                "unban",
+                &format!("BanID: {}, Target: {} ({})", id, ban.name, ban.steam_id),
+                &format!("Reason: {} (Unban commands queued)", reason)
+            ).await;
+            (StatusCode::OK, Json("Ban lifted, unban process started in background")).into_response()
+        },
+        Ok(false) => (StatusCode::CONFLICT, Json(json!({ "error": format!("Ban is already {}", ban.status) }))).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
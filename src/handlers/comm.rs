@@ -0,0 +1,352 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use std::sync::Arc;
+use crate::AppState;
+use crate::handlers::auth::Claims;
+use crate::handlers::ban::{ban_in_scope, resolve_ban_group};
+use crate::models::api_key::ServerIdentity;
+use crate::models::comm::{
+    CommBlock, CommCheckQuery, CommCheckResponse, CreateCommBlockRequest, UpdateCommBlockRequest, COMM_TYPES,
+};
+use crate::services::{ban_scope, enforcement};
+use crate::services::steam_api::SteamService;
+use crate::utils::{calculate_expires_at, log_admin_action};
+
+/// 解析禁言时长，格式与封禁时长一致；'permanent' 表示永久
+fn block_expires_at(duration: &str) -> Result<Option<DateTime<Utc>>, String> {
+    calculate_expires_at(duration).map_err(|e| e.to_string())
+}
+
+fn validate_type(block_type: &str) -> Result<(), String> {
+    if COMM_TYPES.contains(&block_type) {
+        Ok(())
+    } else {
+        Err(format!("block_type must be one of {}", COMM_TYPES.join(", ")))
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/comms",
+    responses(
+        (status = 200, description = "List comm blocks", body = Vec<CommBlock>)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_comms(
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    let blocks = sqlx::query_as::<_, CommBlock>("SELECT * FROM comm_blocks ORDER BY created_at DESC")
+        .fetch_all(&state.db)
+        .await;
+
+    match blocks {
+        Ok(data) => (StatusCode::OK, Json(data)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/check_comms",
+    params(
+        ("steam_id" = String, Query, description = "SteamID to check"),
+        ("server_id" = Option<i64>, Query, description = "Requesting server; without it group-scoped blocks are matched everywhere")
+    ),
+    responses(
+        (status = 200, description = "Active comm blocks (empty when none)", body = CommCheckResponse),
+        (status = 400, description = "Invalid steam_id or unknown server_id")
+    ),
+    security(
+        ("jwt" = []),
+        ("api_key" = [])
+    )
+)]
+pub async fn check_comms(
+    State(state): State<Arc<AppState>>,
+    identity: Option<Extension<ServerIdentity>>,
+    Query(params): Query<CommCheckQuery>,
+) -> impl IntoResponse {
+    // A server API key always checks on behalf of its own server, like check_ban
+    let group_id = match (identity, params.server_id) {
+        (Some(Extension(identity)), _) => Some(identity.server.group_id),
+        (None, Some(server_id)) => match ban_scope::server_group(&state.db, server_id).await {
+            Ok(Some(g)) => Some(g),
+            Ok(None) => return (StatusCode::BAD_REQUEST, "Unknown server_id").into_response(),
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        },
+        (None, None) => None,
+    };
+
+    let Some(steam_id_64) = SteamService::new().resolve_steam_id(&params.steam_id).await else {
+        return (StatusCode::BAD_REQUEST, "Invalid steam_id").into_response();
+    };
+
+    let blocks = sqlx::query_as::<_, CommBlock>(
+        "SELECT * FROM comm_blocks WHERE status = 'active' AND (expires_at IS NULL OR expires_at > NOW()) AND steam_id_64 = ? \
+         AND (? IS NULL OR group_id IS NULL OR group_id = ?) ORDER BY created_at DESC"
+    )
+    .bind(&steam_id_64)
+    .bind(group_id)
+    .bind(group_id)
+    .fetch_all(&state.db)
+    .await;
+
+    match blocks {
+        Ok(blocks) => (StatusCode::OK, Json(CommCheckResponse {
+            muted: blocks.iter().any(CommBlock::blocks_voice),
+            gagged: blocks.iter().any(CommBlock::blocks_chat),
+            blocks,
+        })).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/comms",
+    request_body = CreateCommBlockRequest,
+    responses(
+        (status = 201, description = "Comm block created and applied if the player is online", body = CommBlock),
+        (status = 400, description = "Invalid type, duration or SteamID")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn create_comm(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Json(payload): Json<CreateCommBlockRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = validate_type(&payload.block_type) {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))).into_response();
+    }
+    let expires_at = match block_expires_at(&payload.duration) {
+        Ok(t) => t,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))).into_response(),
+    };
+    let group_id = match resolve_ban_group(&user, payload.group_id) {
+        Ok(g) => g,
+        Err((status, msg)) => return (status, Json(msg)).into_response(),
+    };
+
+    let steam_service = SteamService::new();
+    let Some(steam_id_64) = steam_service.resolve_steam_id(&payload.steam_id).await else {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": "Invalid steam_id" }))).into_response();
+    };
+    let steam_id_2 = steam_service.id64_to_id2(&steam_id_64).unwrap_or_else(|| payload.steam_id.clone());
+    let steam_id_3 = steam_service.id64_to_id3(&steam_id_64);
+
+    let result = sqlx::query(
+        "INSERT INTO comm_blocks (name, steam_id, steam_id_3, steam_id_64, block_type, reason, duration, admin_name, group_id, expires_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&payload.name)
+    .bind(&steam_id_2)
+    .bind(&steam_id_3)
+    .bind(&steam_id_64)
+    .bind(&payload.block_type)
+    .bind(&payload.reason)
+    .bind(&payload.duration)
+    .bind(&user.sub)
+    .bind(group_id)
+    .bind(expires_at)
+    .execute(&state.db)
+    .await;
+
+    let id = match result {
+        Ok(res) => res.last_insert_id() as i64,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    let block = match sqlx::query_as::<_, CommBlock>("SELECT * FROM comm_blocks WHERE id = ?")
+        .bind(id)
+        .fetch_one(&state.db)
+        .await
+    {
+        Ok(b) => b,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    enforcement::queue_rcon_comm(&state, &block, true);
+
+    let _ = log_admin_action(
+        &state.db,
+        &user.sub,
+        "create_comm",
+        &format!("User: {}, SteamID64: {}", block.name, steam_id_64),
+        &format!("Type: {}, Reason: {}, Duration: {}", block.block_type, block.reason.clone().unwrap_or_default(), block.duration)
+    ).await;
+
+    (StatusCode::CREATED, Json(block)).into_response()
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/comms/{id}",
+    params(
+        ("id" = i64, Path, description = "Comm block ID")
+    ),
+    request_body = UpdateCommBlockRequest,
+    responses(
+        (status = 200, description = "Comm block updated", body = CommBlock),
+        (status = 400, description = "Invalid type, duration or status"),
+        (status = 403, description = "Out of scope"),
+        (status = 404, description = "Comm block not found")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn update_comm(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path(id): Path<i64>,
+    Json(payload): Json<UpdateCommBlockRequest>,
+) -> impl IntoResponse {
+    if let Some(block_type) = &payload.block_type {
+        if let Err(e) = validate_type(block_type) {
+            return (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))).into_response();
+        }
+    }
+    if let Some(status) = &payload.status {
+        if status != "active" && status != "removed" {
+            return (StatusCode::BAD_REQUEST, Json(json!({ "error": "status must be active or removed" }))).into_response();
+        }
+    }
+    let expires_at = match payload.duration.as_deref().map(block_expires_at).transpose() {
+        Ok(t) => t,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))).into_response(),
+    };
+
+    let old = match sqlx::query_as::<_, CommBlock>("SELECT * FROM comm_blocks WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&state.db)
+        .await
+    {
+        Ok(Some(b)) => b,
+        Ok(None) => return (StatusCode::NOT_FOUND, Json(json!({ "error": "Comm block not found" }))).into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    if !ban_in_scope(&user, old.group_id) {
+        return (StatusCode::FORBIDDEN, Json("Comm block belongs to a server group out of scope")).into_response();
+    }
+
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        if let Some(block_type) = &payload.block_type {
+            sqlx::query("UPDATE comm_blocks SET block_type = ? WHERE id = ?")
+                .bind(block_type).bind(id)
+                .execute(&mut *tx).await?;
+        }
+        if let Some(reason) = &payload.reason {
+            sqlx::query("UPDATE comm_blocks SET reason = ? WHERE id = ?")
+                .bind(reason).bind(id)
+                .execute(&mut *tx).await?;
+        }
+        if let Some(duration) = &payload.duration {
+            sqlx::query("UPDATE comm_blocks SET duration = ?, expires_at = ? WHERE id = ?")
+                .bind(duration).bind(expires_at.flatten()).bind(id)
+                .execute(&mut *tx).await?;
+        }
+        if let Some(status) = &payload.status {
+            sqlx::query("UPDATE comm_blocks SET status = ? WHERE id = ?")
+                .bind(status).bind(id)
+                .execute(&mut *tx).await?;
+        }
+        tx.commit().await
+    }.await;
+
+    if let Err(e) = result {
+        return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+    }
+
+    let new = match sqlx::query_as::<_, CommBlock>("SELECT * FROM comm_blocks WHERE id = ?")
+        .bind(id)
+        .fetch_one(&state.db)
+        .await
+    {
+        Ok(b) => b,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    // 类型或状态变化时，先撤销旧处罚再施加新处罚
+    let was_active = old.status == "active";
+    let is_active = new.status == "active";
+    let type_changed = old.block_type != new.block_type;
+    if was_active && (!is_active || type_changed) {
+        enforcement::queue_rcon_comm(&state, &old, false);
+    }
+    if is_active && (!was_active || type_changed) {
+        enforcement::queue_rcon_comm(&state, &new, true);
+    }
+
+    let _ = log_admin_action(
+        &state.db,
+        &user.sub,
+        "update_comm",
+        &format!("CommID: {}, Target: {} ({})", id, new.name, new.steam_id),
+        &format!("Type: {}, Status: {}, Duration: {}", new.block_type, new.status, new.duration)
+    ).await;
+
+    (StatusCode::OK, Json(new)).into_response()
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/comms/{id}",
+    params(
+        ("id" = i64, Path, description = "Comm block ID")
+    ),
+    responses(
+        (status = 200, description = "Comm block deleted and lifted if the player is online"),
+        (status = 403, description = "Out of scope"),
+        (status = 404, description = "Comm block not found")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn delete_comm(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let block = match sqlx::query_as::<_, CommBlock>("SELECT * FROM comm_blocks WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&state.db)
+        .await
+    {
+        Ok(Some(b)) => b,
+        Ok(None) => return (StatusCode::NOT_FOUND, Json(json!({ "error": "Comm block not found" }))).into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    if !ban_in_scope(&user, block.group_id) {
+        return (StatusCode::FORBIDDEN, Json("Comm block belongs to a server group out of scope")).into_response();
+    }
+
+    match sqlx::query("DELETE FROM comm_blocks WHERE id = ?").bind(id).execute(&state.db).await {
+        Ok(_) => {
+            if block.status == "active" {
+                enforcement::queue_rcon_comm(&state, &block, false);
+            }
+            let _ = log_admin_action(
+                &state.db,
+                &user.sub,
+                "delete_comm",
+                &format!("CommID: {}, Target: {} ({})", id, block.name, block.steam_id),
+                &format!("Deleted {} block", block.block_type)
+            ).await;
+            (StatusCode::OK, Json("Comm block deleted")).into_response()
+        },
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
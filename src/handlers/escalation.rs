@@ -0,0 +1,255 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::json;
+use std::sync::Arc;
+use crate::AppState;
+use crate::handlers::auth::Claims;
+use crate::models::escalation::{BanCategory, BanCategoryRequest, EscalationProposal, ProposeQuery};
+use crate::services::escalation;
+use crate::services::steam_api::SteamService;
+use crate::utils::{log_admin_action, parse_duration};
+
+/// 阶梯每一级必须是相对时长或 permanent（绝对截止时间对升级没有意义）
+fn validate_category(payload: &BanCategoryRequest) -> Result<(), String> {
+    if payload.name.trim().is_empty() {
+        return Err("Category name cannot be empty".to_string());
+    }
+    if payload.steps.is_empty() {
+        return Err("An escalation ladder needs at least one step".to_string());
+    }
+    if payload.lookback_days.is_some_and(|d| d <= 0) {
+        return Err("lookback_days must be positive".to_string());
+    }
+    for step in &payload.steps {
+        if step != "permanent" {
+            parse_duration(step).map_err(|e| format!("Step '{}': {}", step, e))?;
+        }
+    }
+    Ok(())
+}
+
+fn ladder_summary(payload: &BanCategoryRequest) -> String {
+    format!("Steps: {}, Lookback: {}", payload.steps.join(" -> "),
+        payload.lookback_days.map(|d| format!("{}d", d)).unwrap_or_else(|| "all".to_string()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/ban-categories",
+    responses(
+        (status = 200, description = "Ban categories with their escalation ladders", body = Vec<BanCategory>)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_categories(
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    let categories = sqlx::query_as::<_, BanCategory>("SELECT * FROM ban_categories ORDER BY name")
+        .fetch_all(&state.db)
+        .await;
+
+    match categories {
+        Ok(mut data) => {
+            if let Err(e) = escalation::attach_steps(&state.db, &mut data).await {
+                return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+            }
+            (StatusCode::OK, Json(data)).into_response()
+        },
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/ban-categories",
+    request_body = BanCategoryRequest,
+    responses(
+        (status = 201, description = "Category created"),
+        (status = 400, description = "Invalid name or ladder"),
+        (status = 409, description = "Name already exists")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn create_category(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Json(payload): Json<BanCategoryRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = validate_category(&payload) {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))).into_response();
+    }
+
+    let result: Result<u64, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let id = sqlx::query("INSERT INTO ban_categories (name, description, lookback_days) VALUES (?, ?, ?)")
+            .bind(payload.name.trim())
+            .bind(&payload.description)
+            .bind(payload.lookback_days)
+            .execute(&mut *tx)
+            .await?
+            .last_insert_id();
+        escalation::set_steps(&mut tx, id as i64, &payload.steps).await?;
+        tx.commit().await?;
+        Ok(id)
+    }.await;
+
+    match result {
+        Ok(id) => {
+            let _ = log_admin_action(
+                &state.db,
+                &user.sub,
+                "create_ban_category",
+                &format!("Category: {} (ID: {})", payload.name.trim(), id),
+                &ladder_summary(&payload)
+            ).await;
+            (StatusCode::CREATED, Json(json!({ "id": id }))).into_response()
+        },
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            (StatusCode::CONFLICT, Json(json!({ "error": "A category with this name already exists" }))).into_response()
+        },
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/ban-categories/{id}",
+    params(
+        ("id" = i64, Path, description = "Category ID")
+    ),
+    request_body = BanCategoryRequest,
+    responses(
+        (status = 200, description = "Category and ladder replaced"),
+        (status = 400, description = "Invalid name or ladder"),
+        (status = 404, description = "Category not found")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn update_category(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path(id): Path<i64>,
+    Json(payload): Json<BanCategoryRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = validate_category(&payload) {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))).into_response();
+    }
+
+    // 已有封禁记录的 ladder_step 保持不变，新阶梯只影响之后的封禁
+    let result: Result<bool, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let exists = sqlx::query_scalar::<_, i64>("SELECT id FROM ban_categories WHERE id = ? FOR UPDATE")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .is_some();
+        if !exists {
+            return Ok(false);
+        }
+        sqlx::query("UPDATE ban_categories SET name = ?, description = ?, lookback_days = ? WHERE id = ?")
+            .bind(payload.name.trim())
+            .bind(&payload.description)
+            .bind(payload.lookback_days)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        escalation::set_steps(&mut tx, id, &payload.steps).await?;
+        tx.commit().await?;
+        Ok(true)
+    }.await;
+
+    match result {
+        Ok(false) => (StatusCode::NOT_FOUND, Json(json!({ "error": "Category not found" }))).into_response(),
+        Ok(true) => {
+            let _ = log_admin_action(
+                &state.db,
+                &user.sub,
+                "update_ban_category",
+                &format!("Category: {} (ID: {})", payload.name.trim(), id),
+                &ladder_summary(&payload)
+            ).await;
+            (StatusCode::OK, Json("Category updated")).into_response()
+        },
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            (StatusCode::CONFLICT, Json(json!({ "error": "A category with this name already exists" }))).into_response()
+        },
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/ban-categories/{id}",
+    params(
+        ("id" = i64, Path, description = "Category ID")
+    ),
+    responses(
+        (status = 200, description = "Category deleted; existing bans keep their duration"),
+        (status = 404, description = "Category not found")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn delete_category(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    match sqlx::query("DELETE FROM ban_categories WHERE id = ?").bind(id).execute(&state.db).await {
+        Ok(r) if r.rows_affected() == 0 => (StatusCode::NOT_FOUND, Json(json!({ "error": "Category not found" }))).into_response(),
+        Ok(_) => {
+            let _ = log_admin_action(&state.db, &user.sub, "delete_ban_category", &format!("CategoryID: {}", id), "Deleted ban category").await;
+            (StatusCode::OK, Json("Category deleted")).into_response()
+        },
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/ban-categories/{id}/propose",
+    params(
+        ("id" = i64, Path, description = "Category ID"),
+        ("steam_id" = String, Query, description = "Player SteamID (any format)")
+    ),
+    responses(
+        (status = 200, description = "Next ladder step for this player", body = EscalationProposal),
+        (status = 400, description = "Invalid SteamID"),
+        (status = 404, description = "Category not found")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn propose_escalation(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    Query(query): Query<ProposeQuery>,
+) -> impl IntoResponse {
+    let Some(steam_id_64) = SteamService::new().resolve_steam_id(&query.steam_id).await else {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": "Invalid steam_id" }))).into_response();
+    };
+
+    let category = match escalation::load_category(&state.db, id).await {
+        Ok(Some(c)) => c,
+        Ok(None) => return (StatusCode::NOT_FOUND, Json(json!({ "error": "Category not found" }))).into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    match escalation::propose(&state.db, &category, &steam_id_64).await {
+        Ok(Some(p)) => (StatusCode::OK, Json(p)).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, Json(json!({ "error": "Category has no ladder steps" }))).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
@@ -0,0 +1,358 @@
+use axum::{
+    body::Body,
+    extract::{Extension, Multipart, Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tokio_util::io::ReaderStream;
+use crate::AppState;
+use crate::handlers::auth::Claims;
+use crate::handlers::ban::ban_in_scope;
+use crate::models::ban::Ban;
+use crate::models::evidence::{Evidence, EvidenceUploadForm, UpdateEvidenceRequest};
+use crate::services::evidence::{self as storage, EvidenceError};
+use crate::utils::log_admin_action;
+
+async fn load_ban_in_scope(state: &AppState, user: &Claims, ban_id: i64) -> Result<Ban, Response> {
+    let ban = match sqlx::query_as::<_, Ban>("SELECT * FROM bans WHERE id = ?")
+        .bind(ban_id)
+        .fetch_optional(&state.db)
+        .await
+    {
+        Ok(Some(b)) => b,
+        Ok(None) => return Err((StatusCode::NOT_FOUND, Json(json!({ "error": "Ban not found" }))).into_response()),
+        Err(e) => return Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()),
+    };
+    if !ban_in_scope(user, ban.group_id) {
+        return Err((StatusCode::FORBIDDEN, Json(json!({ "error": "Ban belongs to a server group out of scope" }))).into_response());
+    }
+    Ok(ban)
+}
+
+async fn load_in_scope(state: &AppState, user: &Claims, id: i64) -> Result<Evidence, Response> {
+    let evidence = match sqlx::query_as::<_, Evidence>("SELECT * FROM ban_evidence WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&state.db)
+        .await
+    {
+        Ok(Some(e)) => e,
+        Ok(None) => return Err((StatusCode::NOT_FOUND, Json(json!({ "error": "Evidence not found" }))).into_response()),
+        Err(e) => return Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()),
+    };
+    load_ban_in_scope(state, user, evidence.ban_id).await?;
+    Ok(evidence)
+}
+
+/// Streams a stored file; everything is sent as an attachment so browsers never render it inline
+async fn file_response(evidence: &Evidence) -> Response {
+    let file = match tokio::fs::File::open(storage::path_for(&evidence.sha256)).await {
+        Ok(f) => f,
+        Err(e) => {
+            tracing::error!("Evidence file {} missing: {}", evidence.sha256, e);
+            return (StatusCode::NOT_FOUND, Json(json!({ "error": "Evidence file missing from storage" }))).into_response();
+        }
+    };
+
+    (
+        [
+            (header::CONTENT_TYPE, evidence.content_type.clone()),
+            (header::CONTENT_LENGTH, evidence.size_bytes.to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", evidence.file_name)),
+            (header::ETAG, format!("\"{}\"", evidence.sha256)),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+        ],
+        Body::from_stream(ReaderStream::new(file)),
+    ).into_response()
+}
+
+fn upload_error(e: EvidenceError) -> Response {
+    let status = match e {
+        EvidenceError::TooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+        EvidenceError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        _ => StatusCode::BAD_REQUEST,
+    };
+    (status, Json(json!({ "error": e.to_string() }))).into_response()
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/bans/{id}/evidence",
+    params(
+        ("id" = i64, Path, description = "Ban ID")
+    ),
+    request_body(content = EvidenceUploadForm, content_type = "multipart/form-data"),
+    responses(
+        (status = 201, description = "Evidence uploaded", body = Evidence),
+        (status = 400, description = "Missing file or file type not allowed"),
+        (status = 413, description = "File too large")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn upload_evidence(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path(ban_id): Path<i64>,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let ban = match load_ban_in_scope(&state, &user, ban_id).await {
+        Ok(b) => b,
+        Err(resp) => return resp,
+    };
+
+    let mut description = None;
+    let mut is_public = false;
+    let mut upload = None;
+
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(f)) => f,
+            Ok(None) => break,
+            Err(e) => return (e.status(), Json(json!({ "error": e.body_text() }))).into_response(),
+        };
+        match field.name() {
+            Some("file") if upload.is_none() => {
+                let file_name = storage::sanitize_file_name(field.file_name().unwrap_or_default());
+                let Some(content_type) = storage::content_type_for(&file_name) else {
+                    return (StatusCode::BAD_REQUEST, Json(json!({ "error": "File type not allowed (demo, image, text or log files only)" }))).into_response();
+                };
+                match storage::store(field, content_type).await {
+                    Ok(stored) => upload = Some((file_name, content_type, stored)),
+                    Err(e) => return upload_error(e),
+                }
+            }
+            Some("file") => {
+                return (StatusCode::BAD_REQUEST, Json(json!({ "error": "Upload one file per request" }))).into_response();
+            }
+            Some("description") => {
+                description = field.text().await.ok().map(|d| d.trim().to_string()).filter(|d| !d.is_empty());
+            }
+            Some("public") => {
+                is_public = matches!(field.text().await.as_deref(), Ok("true") | Ok("1"));
+            }
+            _ => {}
+        }
+    }
+
+    let Some((file_name, content_type, stored)) = upload else {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": "Missing 'file' field" }))).into_response();
+    };
+
+    let result = sqlx::query(
+        "INSERT INTO ban_evidence (ban_id, file_name, content_type, size_bytes, sha256, description, is_public, uploaded_by) VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(ban_id)
+    .bind(&file_name)
+    .bind(content_type)
+    .bind(stored.size as i64)
+    .bind(&stored.sha256)
+    .bind(&description)
+    .bind(is_public)
+    .bind(&user.sub)
+    .execute(&state.db)
+    .await;
+
+    match result {
+        Ok(res) => {
+            let id = res.last_insert_id() as i64;
+            let _ = log_admin_action(
+                &state.db,
+                &user.sub,
+                "upload_evidence",
+                &format!("BanID: {}, Target: {} ({})", ban_id, ban.name, ban.steam_id),
+                &format!("File: {} ({} bytes, sha256 {}){}", file_name, stored.size, stored.sha256, if is_public { ", public" } else { "" })
+            ).await;
+            match sqlx::query_as::<_, Evidence>("SELECT * FROM ban_evidence WHERE id = ?").bind(id).fetch_one(&state.db).await {
+                Ok(evidence) => (StatusCode::CREATED, Json(evidence)).into_response(),
+                Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+            }
+        },
+        Err(e) => {
+            storage::remove_unused(&state.db, &[stored.sha256]).await;
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        },
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/bans/{id}/evidence",
+    params(
+        ("id" = i64, Path, description = "Ban ID")
+    ),
+    responses(
+        (status = 200, description = "Evidence attached to the ban", body = Vec<Evidence>)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_evidence(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path(ban_id): Path<i64>,
+) -> impl IntoResponse {
+    if let Err(resp) = load_ban_in_scope(&state, &user, ban_id).await {
+        return resp;
+    }
+
+    let evidence = sqlx::query_as::<_, Evidence>("SELECT * FROM ban_evidence WHERE ban_id = ? ORDER BY created_at")
+        .bind(ban_id)
+        .fetch_all(&state.db)
+        .await;
+
+    match evidence {
+        Ok(data) => (StatusCode::OK, Json(data)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/evidence/{id}/download",
+    params(
+        ("id" = i64, Path, description = "Evidence ID")
+    ),
+    responses(
+        (status = 200, description = "File content"),
+        (status = 404, description = "Evidence not found")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn download_evidence(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    match load_in_scope(&state, &user, id).await {
+        Ok(evidence) => file_response(&evidence).await,
+        Err(resp) => resp,
+    }
+}
+
+// 公开封禁页面上的证据下载，仅限标记为公开的文件
+#[utoipa::path(
+    get,
+    path = "/api/bans/public/evidence/{id}",
+    params(
+        ("id" = i64, Path, description = "Evidence ID")
+    ),
+    responses(
+        (status = 200, description = "File content"),
+        (status = 404, description = "Evidence not found or not public")
+    )
+)]
+pub async fn download_public_evidence(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    match sqlx::query_as::<_, Evidence>("SELECT * FROM ban_evidence WHERE id = ? AND is_public = TRUE")
+        .bind(id)
+        .fetch_optional(&state.db)
+        .await
+    {
+        Ok(Some(evidence)) => file_response(&evidence).await,
+        Ok(None) => (StatusCode::NOT_FOUND, Json(json!({ "error": "Evidence not found" }))).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/evidence/{id}",
+    params(
+        ("id" = i64, Path, description = "Evidence ID")
+    ),
+    request_body = UpdateEvidenceRequest,
+    responses(
+        (status = 200, description = "Evidence updated"),
+        (status = 404, description = "Evidence not found")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn update_evidence(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path(id): Path<i64>,
+    Json(payload): Json<UpdateEvidenceRequest>,
+) -> impl IntoResponse {
+    let evidence = match load_in_scope(&state, &user, id).await {
+        Ok(e) => e,
+        Err(resp) => return resp,
+    };
+
+    let description = match payload.description {
+        Some(d) => Some(d.trim().to_string()).filter(|d| !d.is_empty()),
+        None => evidence.description,
+    };
+    let is_public = payload.is_public.unwrap_or(evidence.is_public);
+
+    let result = sqlx::query("UPDATE ban_evidence SET description = ?, is_public = ? WHERE id = ?")
+        .bind(&description)
+        .bind(is_public)
+        .bind(id)
+        .execute(&state.db)
+        .await;
+
+    match result {
+        Ok(_) => {
+            let _ = log_admin_action(
+                &state.db,
+                &user.sub,
+                "update_evidence",
+                &format!("EvidenceID: {}, BanID: {}", id, evidence.ban_id),
+                &format!("File: {}, Public: {}", evidence.file_name, is_public)
+            ).await;
+            (StatusCode::OK, Json("Evidence updated")).into_response()
+        },
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/evidence/{id}",
+    params(
+        ("id" = i64, Path, description = "Evidence ID")
+    ),
+    responses(
+        (status = 200, description = "Evidence deleted"),
+        (status = 404, description = "Evidence not found")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn delete_evidence(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let evidence = match load_in_scope(&state, &user, id).await {
+        Ok(e) => e,
+        Err(resp) => return resp,
+    };
+
+    match sqlx::query("DELETE FROM ban_evidence WHERE id = ?").bind(id).execute(&state.db).await {
+        Ok(_) => {
+            storage::remove_unused(&state.db, std::slice::from_ref(&evidence.sha256)).await;
+            let _ = log_admin_action(
+                &state.db,
+                &user.sub,
+                "delete_evidence",
+                &format!("EvidenceID: {}, BanID: {}", id, evidence.ban_id),
+                &format!("File: {} (sha256 {})", evidence.file_name, evidence.sha256)
+            ).await;
+            (StatusCode::OK, Json("Evidence deleted")).into_response()
+        },
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
@@ -0,0 +1,85 @@
+use axum::{
+    extract::{Extension, Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use crate::AppState;
+use crate::handlers::auth::Claims;
+use crate::models::ban::BanSearchQuery;
+use crate::services::ban_export::{self, ExportFormat};
+use crate::services::ban_search::BanFilters;
+use crate::utils::log_admin_action;
+
+#[derive(Deserialize)]
+pub struct ExportQuery {
+    format: String,
+    fields: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/bans/export",
+    params(
+        ("format" = String, Query, description = "banned_user, banned_ip, csv or json"),
+        ("fields" = Option<String>, Query, description = "Comma separated columns for csv / json"),
+        ("status" = Option<String>, Query, description = "Same filters and sort as GET /api/bans, without pagination"),
+        ("ban_type" = Option<String>, Query, description = "account or ip"),
+        ("admin_name" = Option<String>, Query, description = "Banning admin"),
+        ("server_id" = Option<i64>, Query, description = "Server the ban was issued on"),
+        ("reason" = Option<String>, Query, description = "Text contained in the reason"),
+        ("steam_id" = Option<String>, Query, description = "SteamID in any format"),
+        ("player" = Option<String>, Query, description = "Player name fragment or SteamID"),
+        ("created_from" = Option<String>, Query, description = "Created at or after"),
+        ("created_to" = Option<String>, Query, description = "Created before"),
+        ("expires_from" = Option<String>, Query, description = "Expires at or after"),
+        ("expires_to" = Option<String>, Query, description = "Expires before"),
+        ("sort" = Option<String>, Query, description = "created_at (default), expires_at, id or name"),
+        ("order" = Option<String>, Query, description = "desc (default) or asc")
+    ),
+    responses(
+        (status = 200, description = "Streamed export file"),
+        (status = 400, description = "Unknown format, field or filter")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn export_bans(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Query(options): Query<ExportQuery>,
+    Query(search): Query<BanSearchQuery>,
+) -> impl IntoResponse {
+    let Some(format) = ExportFormat::parse(&options.format) else {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": "Format must be one of: banned_user, banned_ip, csv, json" }))).into_response();
+    };
+    let fields = match ban_export::parse_fields(options.fields.as_deref()) {
+        Ok(f) => f,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))).into_response(),
+    };
+    let filters = match BanFilters::resolve(&search).await {
+        Ok(f) => f,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))).into_response(),
+    };
+
+    let _ = log_admin_action(
+        &state.db,
+        &user.sub,
+        "export_bans",
+        format.file_name(),
+        &format!("Fields: {}", fields.join(", "))
+    ).await;
+
+    (
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", format.file_name())),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+        ],
+        ban_export::stream(state.db.clone(), filters, format, fields),
+    ).into_response()
+}
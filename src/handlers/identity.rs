@@ -0,0 +1,47 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::json;
+use std::sync::Arc;
+use crate::AppState;
+use crate::models::identity::{AssociationsQuery, LinkedAccount};
+use crate::services::identity;
+use crate::services::steam_api::SteamService;
+
+#[utoipa::path(
+    get,
+    path = "/api/players/{steam_id}/associations",
+    params(
+        ("steam_id" = String, Path, description = "SteamID in any format"),
+        ("min_confidence" = Option<f64>, Query, description = "Only return links at least this confident (0..1)")
+    ),
+    responses(
+        (status = 200, description = "Known associated accounts, most likely first", body = Vec<LinkedAccount>),
+        (status = 400, description = "Unrecognised SteamID")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_associations(
+    State(state): State<Arc<AppState>>,
+    Path(steam_id): Path<String>,
+    Query(params): Query<AssociationsQuery>,
+) -> impl IntoResponse {
+    let Some(steam_id_64) = SteamService::new().resolve_steam_id(&steam_id).await else {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": "Unrecognised SteamID" }))).into_response();
+    };
+
+    match identity::linked_accounts(&state.db, &steam_id_64).await {
+        Ok(mut data) => {
+            if let Some(min) = params.min_confidence {
+                data.retain(|a| a.confidence >= min);
+            }
+            (StatusCode::OK, Json(data)).into_response()
+        },
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
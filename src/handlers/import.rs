@@ -0,0 +1,147 @@
+use axum::{
+    extract::{Extension, Multipart, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use crate::AppState;
+use crate::handlers::auth::Claims;
+use crate::models::import::{CfgImportForm, ImportReport, SourceBansImportRequest};
+use crate::services::import::Importer;
+use crate::utils::log_admin_action;
+
+#[derive(Deserialize)]
+pub struct CfgImportQuery {
+    dry_run: Option<bool>,
+}
+
+/// Writes the import unless it is a dry run, and logs what was imported
+async fn finish(state: &AppState, user: &Claims, importer: Importer, dry_run: bool, source: &str) -> axum::response::Response {
+    if dry_run {
+        return (StatusCode::OK, Json(importer.into_report())).into_response();
+    }
+
+    match importer.commit(&state.db, &user.sub).await {
+        Ok(report) => {
+            let _ = log_admin_action(
+                &state.db,
+                &user.sub,
+                "import_bans",
+                source,
+                &format!(
+                    "Bans: {} imported, {} duplicates, {} skipped; Comms: {} imported; Admins: {} imported",
+                    report.bans.imported, report.bans.duplicates, report.bans.skipped,
+                    report.comms.imported, report.admins.imported
+                )
+            ).await;
+            (StatusCode::OK, Json(report)).into_response()
+        },
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": format!("Import rolled back: {}", e) }))).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/import/sourcebans",
+    request_body = SourceBansImportRequest,
+    responses(
+        (status = 200, description = "Import report (nothing is written on a dry run)", body = ImportReport),
+        (status = 400, description = "Invalid table prefix"),
+        (status = 502, description = "Could not read the SourceBans database")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn import_sourcebans(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Json(payload): Json<SourceBansImportRequest>,
+) -> impl IntoResponse {
+    let prefix = payload.table_prefix.as_deref().unwrap_or("sb");
+    if !Regex::new(r"^[A-Za-z0-9_]{1,32}$").unwrap().is_match(prefix) {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": "Invalid table prefix" }))).into_response();
+    }
+    let dry_run = payload.dry_run.unwrap_or(true);
+
+    let mut importer = match Importer::new(&state.db, dry_run).await {
+        Ok(i) => i,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    let read = importer.read_sourcebans(
+        &payload.database_url,
+        prefix,
+        payload.include_comms.unwrap_or(true),
+        payload.include_admins.unwrap_or(true),
+    ).await;
+    if let Err(e) = read {
+        return (StatusCode::BAD_GATEWAY, Json(json!({ "error": format!("Failed to read SourceBans database: {}", e) }))).into_response();
+    }
+
+    finish(&state, &user, importer, dry_run, &format!("SourceBans ({}_*)", prefix)).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/import/cfg",
+    params(
+        ("dry_run" = Option<bool>, Query, description = "Only report what would be imported (default true)")
+    ),
+    request_body(content = CfgImportForm, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Import report (nothing is written on a dry run)", body = ImportReport),
+        (status = 400, description = "No file uploaded")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn import_cfg(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Query(params): Query<CfgImportQuery>,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let mut dry_run = params.dry_run.unwrap_or(true);
+    let mut files = Vec::new();
+
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(f)) => f,
+            Ok(None) => break,
+            Err(e) => return (e.status(), Json(json!({ "error": e.body_text() }))).into_response(),
+        };
+        match field.name() {
+            Some("file") => {
+                let file_name = field.file_name().unwrap_or("upload.cfg").to_string();
+                match field.bytes().await {
+                    Ok(bytes) => files.push((file_name, String::from_utf8_lossy(&bytes).into_owned())),
+                    Err(e) => return (e.status(), Json(json!({ "error": e.body_text() }))).into_response(),
+                }
+            }
+            Some("dry_run") => {
+                dry_run = !matches!(field.text().await.as_deref(), Ok("false") | Ok("0"));
+            }
+            _ => {}
+        }
+    }
+
+    if files.is_empty() {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": "Missing 'file' field" }))).into_response();
+    }
+
+    let mut importer = match Importer::new(&state.db, dry_run).await {
+        Ok(i) => i,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    for (file_name, text) in &files {
+        importer.read_cfg(file_name, text, &user.sub).await;
+    }
+
+    let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+    finish(&state, &user, importer, dry_run, &names.join(", ")).await
+}
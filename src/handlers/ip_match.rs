@@ -0,0 +1,391 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::json;
+use std::sync::Arc;
+use crate::AppState;
+use crate::handlers::auth::Claims;
+use crate::handlers::ban::ban_in_scope;
+use crate::models::ban::Ban;
+use crate::models::ip_match::{
+    CreateIpMatchExemptionRequest, DecideIpMatchRequest, IpMatchExemption, IpMatchPolicy, IpMatchReview,
+    IpMatchReviewQuery, UpdateIpMatchPolicyRequest, EXEMPTION_KINDS, IP_MATCH_MODES,
+};
+use crate::services::{enforcement, ip_match};
+use crate::services::enforcement::LinkedTarget;
+use crate::services::steam_api::SteamService;
+use crate::utils::ip_range::IpRange;
+use crate::utils::log_admin_action;
+
+#[utoipa::path(
+    get,
+    path = "/api/ip-match/policy",
+    responses(
+        (status = 200, description = "Current IP-match policy", body = IpMatchPolicy)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn get_policy(
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    match ip_match::policy(&state.db).await {
+        Ok(policy) => (StatusCode::OK, Json(policy)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/ip-match/policy",
+    request_body = UpdateIpMatchPolicyRequest,
+    responses(
+        (status = 200, description = "Policy updated", body = IpMatchPolicy),
+        (status = 400, description = "Unknown mode")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn update_policy(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Json(payload): Json<UpdateIpMatchPolicyRequest>,
+) -> impl IntoResponse {
+    if !IP_MATCH_MODES.contains(&payload.mode.as_str()) {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": format!("Mode must be one of: {}", IP_MATCH_MODES.join(", ")) }))).into_response();
+    }
+
+    let result = sqlx::query(
+        "INSERT INTO ip_match_policy (id, mode, exempt_whitelisted, updated_by) VALUES (1, ?, COALESCE(?, TRUE), ?) \
+         ON DUPLICATE KEY UPDATE mode = VALUES(mode), exempt_whitelisted = COALESCE(?, exempt_whitelisted), updated_by = VALUES(updated_by)"
+    )
+    .bind(&payload.mode)
+    .bind(payload.exempt_whitelisted)
+    .bind(&user.sub)
+    .bind(payload.exempt_whitelisted)
+    .execute(&state.db)
+    .await;
+
+    if let Err(e) = result {
+        return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+    }
+
+    match ip_match::policy(&state.db).await {
+        Ok(policy) => {
+            let _ = log_admin_action(
+                &state.db,
+                &user.sub,
+                "update_ip_match_policy",
+                &format!("Mode: {}", policy.mode),
+                &format!("Exempt whitelisted: {}", policy.exempt_whitelisted)
+            ).await;
+            (StatusCode::OK, Json(policy)).into_response()
+        },
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/ip-match/exemptions",
+    responses(
+        (status = 200, description = "Exempt SteamIDs and trusted IP ranges", body = Vec<IpMatchExemption>)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_exemptions(
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    let result = sqlx::query_as::<_, IpMatchExemption>(
+        "SELECT id, kind, value, note, created_by, created_at FROM ip_match_exemptions ORDER BY kind, created_at DESC"
+    )
+    .fetch_all(&state.db)
+    .await;
+
+    match result {
+        Ok(data) => (StatusCode::OK, Json(data)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/ip-match/exemptions",
+    request_body = CreateIpMatchExemptionRequest,
+    responses(
+        (status = 201, description = "Exemption added", body = IpMatchExemption),
+        (status = 400, description = "Invalid SteamID or IP range"),
+        (status = 409, description = "Already exempt")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn create_exemption(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Json(payload): Json<CreateIpMatchExemptionRequest>,
+) -> impl IntoResponse {
+    let (value, range) = match payload.kind.as_str() {
+        "steam_id" => match SteamService::new().resolve_steam_id(payload.value.trim()).await {
+            Some(id64) => (id64, None),
+            None => return (StatusCode::BAD_REQUEST, Json(json!({ "error": "Unrecognised SteamID" }))).into_response(),
+        },
+        "ip_range" => match IpRange::parse(&payload.value) {
+            Ok(range) => (payload.value.trim().to_string(), Some(range)),
+            Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))).into_response(),
+        },
+        _ => return (StatusCode::BAD_REQUEST, Json(json!({ "error": format!("Kind must be one of: {}", EXEMPTION_KINDS.join(", ")) }))).into_response(),
+    };
+
+    let result = sqlx::query(
+        "INSERT INTO ip_match_exemptions (kind, value, ip_start, ip_end, note, created_by) VALUES (?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&payload.kind)
+    .bind(&value)
+    .bind(range.map(|r| r.start_key()))
+    .bind(range.map(|r| r.end_key()))
+    .bind(&payload.note)
+    .bind(&user.sub)
+    .execute(&state.db)
+    .await;
+
+    let id = match result {
+        Ok(res) => res.last_insert_id() as i64,
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            return (StatusCode::CONFLICT, Json(json!({ "error": "Already exempt" }))).into_response();
+        },
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    let _ = log_admin_action(
+        &state.db,
+        &user.sub,
+        "create_ip_match_exemption",
+        &format!("{}: {}", payload.kind, value),
+        payload.note.as_deref().unwrap_or("")
+    ).await;
+
+    match sqlx::query_as::<_, IpMatchExemption>("SELECT id, kind, value, note, created_by, created_at FROM ip_match_exemptions WHERE id = ?")
+        .bind(id)
+        .fetch_one(&state.db)
+        .await
+    {
+        Ok(exemption) => (StatusCode::CREATED, Json(exemption)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/ip-match/exemptions/{id}",
+    params(
+        ("id" = i64, Path, description = "Exemption ID")
+    ),
+    responses(
+        (status = 200, description = "Exemption removed"),
+        (status = 404, description = "Exemption not found")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn delete_exemption(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let existing = sqlx::query_as::<_, (String, String)>("SELECT kind, value FROM ip_match_exemptions WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&state.db)
+        .await;
+
+    let (kind, value) = match existing {
+        Ok(Some(row)) => row,
+        Ok(None) => return (StatusCode::NOT_FOUND, Json(json!({ "error": "Exemption not found" }))).into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    match sqlx::query("DELETE FROM ip_match_exemptions WHERE id = ?").bind(id).execute(&state.db).await {
+        Ok(_) => {
+            let _ = log_admin_action(
+                &state.db,
+                &user.sub,
+                "delete_ip_match_exemption",
+                &format!("{}: {}", kind, value),
+                ""
+            ).await;
+            (StatusCode::OK, Json("Exemption removed")).into_response()
+        },
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/ip-match/reviews",
+    params(
+        ("status" = Option<String>, Query, description = "pending (default), confirmed, dismissed or all")
+    ),
+    responses(
+        (status = 200, description = "Flagged IP matches", body = Vec<IpMatchReview>)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_reviews(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<IpMatchReviewQuery>,
+) -> impl IntoResponse {
+    let status = params.status.unwrap_or_else(|| "pending".to_string());
+    let result = if status == "all" {
+        sqlx::query_as::<_, IpMatchReview>("SELECT * FROM ip_match_reviews ORDER BY last_seen DESC LIMIT 500")
+            .fetch_all(&state.db)
+            .await
+    } else {
+        sqlx::query_as::<_, IpMatchReview>("SELECT * FROM ip_match_reviews WHERE status = ? ORDER BY last_seen DESC LIMIT 500")
+            .bind(&status)
+            .fetch_all(&state.db)
+            .await
+    };
+
+    match result {
+        Ok(data) => (StatusCode::OK, Json(data)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/ip-match/reviews/{id}/status",
+    params(
+        ("id" = i64, Path, description = "Review ID")
+    ),
+    request_body = DecideIpMatchRequest,
+    responses(
+        (status = 200, description = "Match confirmed (ban created) or dismissed", body = IpMatchReview),
+        (status = 400, description = "Invalid status"),
+        (status = 403, description = "Matched ban is out of scope"),
+        (status = 404, description = "Review not found"),
+        (status = 409, description = "Already decided, or the matched ban is no longer active")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn decide_review(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path(id): Path<i64>,
+    Json(payload): Json<DecideIpMatchRequest>,
+) -> impl IntoResponse {
+    if payload.status != "confirmed" && payload.status != "dismissed" {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": "Status must be 'confirmed' or 'dismissed'" }))).into_response();
+    }
+
+    let review = match sqlx::query_as::<_, IpMatchReview>("SELECT * FROM ip_match_reviews WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&state.db)
+        .await
+    {
+        Ok(Some(r)) => r,
+        Ok(None) => return (StatusCode::NOT_FOUND, Json(json!({ "error": "Review not found" }))).into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    if review.status != "pending" {
+        return (StatusCode::CONFLICT, Json(json!({ "error": format!("Review already {}", review.status) }))).into_response();
+    }
+
+    let parent = match sqlx::query_as::<_, Ban>("SELECT * FROM bans WHERE id = ?")
+        .bind(review.parent_ban_id)
+        .fetch_one(&state.db)
+        .await
+    {
+        Ok(b) => b,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    if !ban_in_scope(&user, parent.group_id) {
+        return (StatusCode::FORBIDDEN, Json(json!({ "error": "Matched ban belongs to a server group out of scope" }))).into_response();
+    }
+    let parent_active = parent.status == "active" && parent.expires_at.is_none_or(|t| t > chrono::Utc::now());
+    if payload.status == "confirmed" && !parent_active {
+        return (StatusCode::CONFLICT, Json(json!({ "error": "Matched ban is no longer active" }))).into_response();
+    }
+
+    // 先占住这条记录，避免两名管理员同时确认而重复封禁
+    let claimed = sqlx::query(
+        "UPDATE ip_match_reviews SET status = ?, decided_by = ?, decision_note = ?, decided_at = NOW() WHERE id = ? AND status = 'pending'"
+    )
+    .bind(&payload.status)
+    .bind(&user.sub)
+    .bind(&payload.note)
+    .bind(id)
+    .execute(&state.db)
+    .await;
+
+    match claimed {
+        Ok(res) if res.rows_affected() == 0 => {
+            return (StatusCode::CONFLICT, Json(json!({ "error": "Review already decided" }))).into_response();
+        },
+        Ok(_) => {},
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+
+    let mut details = payload.note.clone().unwrap_or_default();
+    if payload.status == "confirmed" {
+        let reason = match review.source.as_str() {
+            "alt_link" => format!("关联账号封禁 (Linked to banned account, confirmed by {})", user.sub),
+            _ => format!("同IP关联封禁 (Shared IP with ban #{}, confirmed by {})", parent.id, user.sub),
+        };
+        let target = LinkedTarget {
+            name: review.name.as_deref().unwrap_or("Auto-Banned"),
+            steam_id: &review.steam_id,
+            steam_id_64: review.steam_id_64.as_deref(),
+            ip: &review.ip,
+            server_id: review.server_id,
+        };
+        match enforcement::ban_linked_account(&state, &parent, &target, &reason, &user.sub).await {
+            Ok(ban) => {
+                let _ = sqlx::query("UPDATE ip_match_reviews SET created_ban_id = ? WHERE id = ?")
+                    .bind(ban.id)
+                    .bind(id)
+                    .execute(&state.db)
+                    .await;
+                details = format!("Ban #{} created. {}", ban.id, details);
+            },
+            Err(e) => {
+                // 封禁未能创建时退回待审核状态
+                let _ = sqlx::query("UPDATE ip_match_reviews SET status = 'pending', decided_by = NULL, decision_note = NULL, decided_at = NULL WHERE id = ?")
+                    .bind(id)
+                    .execute(&state.db)
+                    .await;
+                return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+            }
+        }
+    }
+
+    let _ = log_admin_action(
+        &state.db,
+        &user.sub,
+        if payload.status == "confirmed" { "confirm_ip_match" } else { "dismiss_ip_match" },
+        &format!("Review #{}: {} @ {} (ban #{})", id, review.steam_id, review.ip, review.parent_ban_id),
+        details.trim()
+    ).await;
+
+    match sqlx::query_as::<_, IpMatchReview>("SELECT * FROM ip_match_reviews WHERE id = ?")
+        .bind(id)
+        .fetch_one(&state.db)
+        .await
+    {
+        Ok(r) => (StatusCode::OK, Json(r)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
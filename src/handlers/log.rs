@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Extension, State},
+    extract::State,
     http::StatusCode,
     response::IntoResponse,
     Json,
@@ -7,7 +7,6 @@ use axum::{
 use std::sync::Arc;
 use crate::AppState;
 use crate::models::log::{AuditLog, CreateLogRequest};
-use crate::handlers::auth::Claims;
 
 #[utoipa::path(
     get,
@@ -22,12 +21,7 @@ use crate::handlers::auth::Claims;
 )]
 pub async fn list_logs(
     State(state): State<Arc<AppState>>,
-    Extension(claims): Extension<Claims>,
 ) -> impl IntoResponse {
-    if claims.role != "super_admin" {
-        return (StatusCode::FORBIDDEN, "Access denied").into_response();
-    }
-
     let logs = sqlx::query_as::<_, AuditLog>("SELECT * FROM audit_logs ORDER BY created_at DESC LIMIT 100")
         .fetch_all(&state.db)
         .await;
@@ -53,66 +47,7 @@ pub async fn create_log(
     State(state): State<Arc<AppState>>,
     Json(payload): Json<CreateLogRequest>,
 ) -> impl IntoResponse {
-    // Note: Log creation might be internal or allowed for regular admins depending on requirements.
-    // The user requirement says "Only system admin can SEE operation log function".
-    // Usually creating logs (audit trail) happens automatically on actions.
-    // If this endpoint is for manual log entry, restricting it is safer basically.
-    // But if other admins fetch this to log their actions, this might break them.
-    // However, looking at usage, this seems to be a manual log addition or system log.
-    // Let's assume for now the requirement implies full restriction of the "Log feature".
-    // Re-reading: "Only system admin can see operation log function and server entry list function".
-    // It says "SEE". But backend enforcement is best practice.
-    // Let's restrict it to be safe, as regular admins shouldn't be manually creating audit logs via API anyway usually.
-    
-    // Actually, create_log is likely used by the backend itself or some other component. 
-    // If it's used by the frontend to log actions, we might need to allow it for all admins?
-    // But the user said "Only system admin can see...". 
-    // Let's check where `create_log` is called. 
-    // If I restrict `list_logs`, they can't see it.
-    // I'll add the check here too for consistency with "feature restrict".
-    
-    // Wait, if other admins perform actions (like Ban), the system should log it.
-    // Does the system call this API endpoint internally? No, usually internal calls use DB directly.
-    // If frontend calls this to log "I clicked a button", then restricting it breaks logging for normal admins.
-    // But audit logs should be trusted. Frontend reporting logs is weak.
-    // Let's assume this endpoint is for the "Log Management" page where you might add a note?
-    // Or maybe it's just standard "Log an action".
-    // I will checking `log.rs` again... it inserts into `audit_logs`.
-    // Given the prompt "Only system admin can see...", the primary goal is restricting VISIBILITY.
-    // But typically "function" implies the whole feature.
-    // I will restrict it. If it breaks something I can revert.
-    
-    // Actually, I'll validte if regular admins need to create logs.
-    // If I look at `change_password` in `auth.rs`, it calls `crate::utils::log_admin_action`. 
-    // That is an internal Rust function, not this API endpoint.
-    // So this API endpoint is likely for external/manual logging. Restricting it is fine.
-
-    // Using checks.
-    // wait, create_log checks? 
-    // I'll stick to restricting reading mainly, but writing usually implies admin rights. 
-    // If I restrict writing to super_admin, normal admins can't "manually log".
-    // I will add the check.
-
-    // Extension(claims) is needed.
-    // But wait, I need to add Extension(claims) to the arguments.
-    // I will assume I need to update the signature.
-    
-    // To be safe and strict compliance with "Only see", I definitely must restrict `list_logs`.
-    // I will restrict `create_log` too to prevent unauthorized log tampering/spamming.
-    
-    // BUT wait, `create_log` signature in previous `view_file` was:
-    // pub async fn create_log(State(state): ..., Json(payload): ...)
-    // I need to add Extension(claims).
-
-    /* 
-       Wait, I need to import Extension.
-       And Claims.
-    */
-    
-    // Double check imports in replacement.
-    
-    // Proceeding.
-    
+    // Access is governed by the `logs.create` permission declared on the route in main.rs.
     let result = sqlx::query(
         "INSERT INTO audit_logs (admin_username, action, target, details) VALUES (?, ?, ?, ?)"
     )
@@ -6,3 +6,15 @@ pub mod log;
 pub mod server;
 pub mod whitelist;
 pub mod verification;
+pub mod permission;
+pub mod plugin;
+pub mod appeal;
+pub mod comm;
+pub mod escalation;
+pub mod reason;
+pub mod evidence;
+pub mod revision;
+pub mod identity;
+pub mod ip_match;
+pub mod import;
+pub mod export;
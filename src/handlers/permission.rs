@@ -0,0 +1,352 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::AppState;
+use crate::handlers::admin::guard_target;
+use crate::handlers::auth::Claims;
+use crate::models::permission::{
+    is_known_permission, AdminPermissions, PermissionInfo, PermissionOverview,
+    SetPermissionsRequest, ALL_PERMISSIONS,
+};
+use crate::models::user::{Admin, RoleTwoFactorRequest};
+use crate::utils::{log_admin_action, resolve_permissions};
+
+/// 校验请求中的权限键，并确保操作者不能授予自己没有的权限
+fn validate_grant(user: &Claims, permissions: &[String]) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    if let Some(unknown) = permissions.iter().find(|p| !is_known_permission(p)) {
+        return Err((StatusCode::BAD_REQUEST, Json(json!({ "error": format!("Unknown permission '{}'", unknown) }))));
+    }
+    if let Some(missing) = permissions.iter().find(|p| !user.has_permission(p)) {
+        return Err((StatusCode::FORBIDDEN, Json(json!({ "error": format!("Cannot grant permission '{}' you do not hold", missing) }))));
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/permissions",
+    responses(
+        (status = 200, description = "All permissions and role defaults", body = PermissionOverview)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_permissions(
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    let rows = sqlx::query_as::<_, (String, String)>("SELECT role, permission FROM role_permissions ORDER BY role, permission")
+        .fetch_all(&state.db)
+        .await;
+
+    let rows = match rows {
+        Ok(r) => r,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    let mut roles: HashMap<String, Vec<String>> = HashMap::new();
+    roles.insert("admin".to_string(), Vec::new());
+    for (role, permission) in rows {
+        roles.entry(role).or_default().push(permission);
+    }
+    roles.insert(
+        "super_admin".to_string(),
+        ALL_PERMISSIONS.iter().map(|(key, _)| key.to_string()).collect(),
+    );
+
+    let permissions = ALL_PERMISSIONS.iter()
+        .map(|(key, desc)| PermissionInfo { key: key.to_string(), description: desc.to_string() })
+        .collect();
+
+    let require_2fa: Vec<String> = sqlx::query_scalar("SELECT role FROM role_settings WHERE require_2fa = TRUE ORDER BY role")
+        .fetch_all(&state.db)
+        .await
+        .unwrap_or_default();
+
+    (StatusCode::OK, Json(PermissionOverview { permissions, roles, require_2fa })).into_response()
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/roles/{role}/permissions",
+    params(
+        ("role" = String, Path, description = "Role name")
+    ),
+    request_body = SetPermissionsRequest,
+    responses(
+        (status = 200, description = "Role permissions replaced"),
+        (status = 400, description = "Unknown role or permission")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn set_role_permissions(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path(role): Path<String>,
+    Json(payload): Json<SetPermissionsRequest>,
+) -> impl IntoResponse {
+    if role == "super_admin" {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": "super_admin always has every permission" }))).into_response();
+    }
+    if role != "admin" {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": format!("Unknown role '{}'", role) }))).into_response();
+    }
+    if let Err(e) = validate_grant(&user, &payload.permissions) {
+        return e.into_response();
+    }
+
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        sqlx::query("DELETE FROM role_permissions WHERE role = ?")
+            .bind(&role)
+            .execute(&mut *tx)
+            .await?;
+        for permission in &payload.permissions {
+            sqlx::query("INSERT IGNORE INTO role_permissions (role, permission) VALUES (?, ?)")
+                .bind(&role)
+                .bind(permission)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await
+    }.await;
+
+    match result {
+        Ok(_) => {
+            let _ = log_admin_action(
+                &state.db,
+                &user.sub,
+                "set_role_permissions",
+                &format!("Role: {}", role),
+                &payload.permissions.join(", ")
+            ).await;
+            // 访问令牌中携带权限列表，需让该角色下所有管理员重新登录
+            let admin_ids: Vec<i64> = sqlx::query_scalar("SELECT id FROM admins WHERE role = ?")
+                .bind(&role)
+                .fetch_all(&state.db)
+                .await
+                .unwrap_or_default();
+            for id in admin_ids {
+                crate::services::session::invalidate_admin(&state.cache, id).await;
+            }
+            (StatusCode::OK, Json("Role permissions updated")).into_response()
+        },
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/roles/{role}/2fa",
+    params(
+        ("role" = String, Path, description = "Role name")
+    ),
+    request_body = RoleTwoFactorRequest,
+    responses(
+        (status = 200, description = "2FA requirement updated"),
+        (status = 400, description = "Unknown role"),
+        (status = 403, description = "Only super admins can change this")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn set_role_2fa(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path(role): Path<String>,
+    Json(payload): Json<RoleTwoFactorRequest>,
+) -> impl IntoResponse {
+    if user.role != "super_admin" {
+        return (StatusCode::FORBIDDEN, Json(json!({ "error": "Only super admins can change 2FA requirements" }))).into_response();
+    }
+    if role != "admin" && role != "super_admin" {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": format!("Unknown role '{}'", role) }))).into_response();
+    }
+
+    // 已登录但未绑定的管理员在下次登录时才会被要求绑定
+    let result = sqlx::query("INSERT INTO role_settings (role, require_2fa) VALUES (?, ?) ON DUPLICATE KEY UPDATE require_2fa = VALUES(require_2fa)")
+        .bind(&role)
+        .bind(payload.required)
+        .execute(&state.db)
+        .await;
+
+    match result {
+        Ok(_) => {
+            let _ = log_admin_action(
+                &state.db,
+                &user.sub,
+                "set_role_2fa",
+                &format!("Role: {}", role),
+                if payload.required { "2FA required" } else { "2FA optional" }
+            ).await;
+            (StatusCode::OK, Json("Role 2FA requirement updated")).into_response()
+        },
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admins/{id}/permissions",
+    params(
+        ("id" = i64, Path, description = "Admin ID")
+    ),
+    responses(
+        (status = 200, description = "Admin permissions", body = AdminPermissions),
+        (status = 404, description = "Admin not found")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn get_admin_permissions(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    if let Err(resp) = guard_target(&state, &user, id).await {
+        return resp;
+    }
+
+    let admin = match sqlx::query_as::<_, Admin>("SELECT * FROM admins WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&state.db)
+        .await
+    {
+        Ok(Some(a)) => a,
+        Ok(None) => return (StatusCode::NOT_FOUND, Json(json!({ "error": "Admin not found" }))).into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    let role_permissions: Vec<String> = sqlx::query_scalar("SELECT permission FROM role_permissions WHERE role = ? ORDER BY permission")
+        .bind(&admin.role)
+        .fetch_all(&state.db)
+        .await
+        .unwrap_or_default();
+
+    let extra_permissions: Vec<String> = sqlx::query_scalar("SELECT permission FROM admin_permissions WHERE admin_id = ? ORDER BY permission")
+        .bind(id)
+        .fetch_all(&state.db)
+        .await
+        .unwrap_or_default();
+
+    match resolve_permissions(&state.db, admin.id, &admin.role).await {
+        Ok(effective) => (StatusCode::OK, Json(AdminPermissions {
+            admin_id: admin.id,
+            role: admin.role,
+            role_permissions,
+            extra_permissions,
+            effective,
+        })).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admins/{id}/permissions",
+    params(
+        ("id" = i64, Path, description = "Admin ID")
+    ),
+    request_body = SetPermissionsRequest,
+    responses(
+        (status = 200, description = "Extra permissions replaced"),
+        (status = 400, description = "Unknown permission"),
+        (status = 403, description = "Admin is outside your server groups")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn set_admin_permissions(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path(id): Path<i64>,
+    Json(payload): Json<SetPermissionsRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = validate_grant(&user, &payload.permissions) {
+        return e.into_response();
+    }
+    if let Err(resp) = guard_target(&state, &user, id).await {
+        return resp;
+    }
+
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        sqlx::query("DELETE FROM admin_permissions WHERE admin_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        for permission in &payload.permissions {
+            sqlx::query("INSERT IGNORE INTO admin_permissions (admin_id, permission) VALUES (?, ?)")
+                .bind(id)
+                .bind(permission)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await
+    }.await;
+
+    match result {
+        Ok(_) => {
+            let _ = log_admin_action(
+                &state.db,
+                &user.sub,
+                "set_admin_permissions",
+                &format!("AdminID: {}", id),
+                &payload.permissions.join(", ")
+            ).await;
+            crate::services::session::invalidate_admin(&state.cache, id).await;
+            (StatusCode::OK, Json("Admin permissions updated")).into_response()
+        },
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn caller(role: &str, permissions: &[&str]) -> Claims {
+        Claims {
+            sub: "caller".to_string(),
+            role: role.to_string(),
+            permissions: permissions.iter().map(|p| p.to_string()).collect(),
+            server_groups: None,
+            sid: String::new(),
+            exp: 0,
+        }
+    }
+
+    fn grant(user: &Claims, permissions: &[&str]) -> Result<(), StatusCode> {
+        let permissions: Vec<String> = permissions.iter().map(|p| p.to_string()).collect();
+        validate_grant(user, &permissions).map_err(|(status, _)| status)
+    }
+
+    #[test]
+    fn grants_only_known_permissions_the_caller_holds() {
+        let admin = caller("admin", &["bans.view", "bans.create", "admins.manage"]);
+        assert_eq!(grant(&admin, &[]), Ok(()));
+        assert_eq!(grant(&admin, &["bans.view", "bans.create"]), Ok(()));
+        assert_eq!(grant(&admin, &["bans.view", "bans.delete"]), Err(StatusCode::FORBIDDEN));
+        assert_eq!(grant(&admin, &["bans.everything"]), Err(StatusCode::BAD_REQUEST));
+        assert_eq!(grant(&admin, &["bans.delete", "bans.everything"]), Err(StatusCode::BAD_REQUEST));
+    }
+
+    #[test]
+    fn super_admins_can_grant_every_known_permission() {
+        let super_admin = caller("super_admin", &[]);
+        let all: Vec<&str> = ALL_PERMISSIONS.iter().map(|(key, _)| *key).collect();
+        assert_eq!(grant(&super_admin, &all), Ok(()));
+        assert_eq!(grant(&super_admin, &["bans.everything"]), Err(StatusCode::BAD_REQUEST));
+    }
+}
@@ -0,0 +1,100 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use crate::AppState;
+use crate::handlers::server::Player;
+use crate::models::api_key::ServerIdentity;
+use crate::utils::log_admin_action;
+
+/// How long a reported player list is served instead of querying RCON
+pub const PLAYER_REPORT_TTL: Duration = Duration::from_secs(120);
+
+pub fn player_report_key(server_id: i64) -> String {
+    format!("server_players:{}", server_id)
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct ReportPlayersRequest {
+    pub players: Vec<Player>,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct ServerEventRequest {
+    /// Event name, stored as the audit log action
+    pub event: String,
+    pub target: Option<String>,
+    pub details: Option<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/plugin/players",
+    request_body = ReportPlayersRequest,
+    responses(
+        (status = 200, description = "Player list stored"),
+        (status = 403, description = "Server API key with players.report scope required")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+pub async fn report_players(
+    State(state): State<Arc<AppState>>,
+    identity: ServerIdentity,
+    Json(payload): Json<ReportPlayersRequest>,
+) -> impl IntoResponse {
+    let raw = match serde_json::to_string(&payload.players) {
+        Ok(raw) => raw,
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+
+    match state.cache.set(&player_report_key(identity.server.id), &raw, PLAYER_REPORT_TTL).await {
+        Ok(_) => (StatusCode::OK, Json(json!({ "received": payload.players.len() }))).into_response(),
+        Err(e) => (StatusCode::SERVICE_UNAVAILABLE, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/plugin/events",
+    request_body = ServerEventRequest,
+    responses(
+        (status = 200, description = "Event written to the audit log"),
+        (status = 400, description = "Invalid event name"),
+        (status = 403, description = "Server API key with events.push scope required")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+pub async fn push_event(
+    State(state): State<Arc<AppState>>,
+    identity: ServerIdentity,
+    Json(payload): Json<ServerEventRequest>,
+) -> impl IntoResponse {
+    let event = payload.event.trim();
+    if event.is_empty() || event.len() > 64 {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": "Event name must be 1-64 characters" }))).into_response();
+    }
+
+    let target = payload.target.unwrap_or_else(|| format!("ServerID: {}", identity.server.id));
+    let result = log_admin_action(
+        &state.db,
+        &identity.actor(),
+        event,
+        &target.chars().take(128).collect::<String>(),
+        payload.details.as_deref().unwrap_or("")
+    ).await;
+
+    match result {
+        Ok(_) => (StatusCode::OK, Json(json!({ "msg": "Event recorded" }))).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
@@ -0,0 +1,200 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use regex::Regex;
+use serde_json::json;
+use std::sync::Arc;
+use crate::AppState;
+use crate::handlers::auth::Claims;
+use crate::models::reason::{ReasonTemplate, ReasonTemplateRequest};
+use crate::utils::{log_admin_action, parse_duration};
+
+/// Loads the template a ban/kick request refers to; unknown IDs are rejected with 400
+pub async fn requested_template(state: &AppState, id: Option<i64>) -> Result<Option<ReasonTemplate>, Response> {
+    let Some(id) = id else {
+        return Ok(None);
+    };
+    match sqlx::query_as::<_, ReasonTemplate>("SELECT * FROM reason_templates WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&state.db)
+        .await
+    {
+        Ok(Some(t)) => Ok(Some(t)),
+        Ok(None) => Err((StatusCode::BAD_REQUEST, Json(json!({ "error": "Unknown reason template" }))).into_response()),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()),
+    }
+}
+
+fn validate(payload: &ReasonTemplateRequest) -> Result<(), String> {
+    let code_re = Regex::new(r"^[a-z0-9_]{1,32}$").unwrap();
+    if !code_re.is_match(&payload.code) {
+        return Err("code must be 1-32 lowercase letters, digits or underscores".to_string());
+    }
+    if payload.text_zh.trim().is_empty() || payload.text_en.trim().is_empty() {
+        return Err("Both text_zh and text_en are required".to_string());
+    }
+    // 模板默认时长只能是相对时长，"Until ..." 这类绝对时间没有意义
+    if let Some(duration) = payload.default_duration.as_deref().filter(|d| *d != "permanent") {
+        parse_duration(duration).map_err(|e| format!("default_duration: {}", e))?;
+    }
+    if let Some(ban_type) = &payload.default_ban_type {
+        if ban_type != "account" && ban_type != "ip" {
+            return Err("default_ban_type must be account or ip".to_string());
+        }
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/reason-templates",
+    responses(
+        (status = 200, description = "Reason templates", body = Vec<ReasonTemplate>)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_reasons(
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    let templates = sqlx::query_as::<_, ReasonTemplate>("SELECT * FROM reason_templates ORDER BY code")
+        .fetch_all(&state.db)
+        .await;
+
+    match templates {
+        Ok(data) => (StatusCode::OK, Json(data)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/reason-templates",
+    request_body = ReasonTemplateRequest,
+    responses(
+        (status = 201, description = "Reason template created"),
+        (status = 400, description = "Invalid template"),
+        (status = 409, description = "Code already exists")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn create_reason(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Json(payload): Json<ReasonTemplateRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = validate(&payload) {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))).into_response();
+    }
+
+    let result = sqlx::query(
+        "INSERT INTO reason_templates (code, text_zh, text_en, default_duration, default_ban_type) VALUES (?, ?, ?, ?, ?)"
+    )
+    .bind(&payload.code)
+    .bind(payload.text_zh.trim())
+    .bind(payload.text_en.trim())
+    .bind(&payload.default_duration)
+    .bind(&payload.default_ban_type)
+    .execute(&state.db)
+    .await;
+
+    match result {
+        Ok(res) => {
+            let _ = log_admin_action(&state.db, &user.sub, "create_reason_template", &format!("Code: {}", payload.code), payload.text_zh.trim()).await;
+            (StatusCode::CREATED, Json(json!({ "id": res.last_insert_id() }))).into_response()
+        },
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            (StatusCode::CONFLICT, Json(json!({ "error": "A template with this code already exists" }))).into_response()
+        },
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/reason-templates/{id}",
+    params(
+        ("id" = i64, Path, description = "Template ID")
+    ),
+    request_body = ReasonTemplateRequest,
+    responses(
+        (status = 200, description = "Reason template updated"),
+        (status = 400, description = "Invalid template"),
+        (status = 404, description = "Template not found")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn update_reason(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path(id): Path<i64>,
+    Json(payload): Json<ReasonTemplateRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = validate(&payload) {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))).into_response();
+    }
+    if let Err(resp) = requested_template(&state, Some(id)).await {
+        return resp;
+    }
+
+    // 已有封禁保存的是当时的原因文本，修改模板不会改写历史记录
+    let result = sqlx::query(
+        "UPDATE reason_templates SET code = ?, text_zh = ?, text_en = ?, default_duration = ?, default_ban_type = ? WHERE id = ?"
+    )
+    .bind(&payload.code)
+    .bind(payload.text_zh.trim())
+    .bind(payload.text_en.trim())
+    .bind(&payload.default_duration)
+    .bind(&payload.default_ban_type)
+    .bind(id)
+    .execute(&state.db)
+    .await;
+
+    match result {
+        Ok(_) => {
+            let _ = log_admin_action(&state.db, &user.sub, "update_reason_template", &format!("Code: {} (ID: {})", payload.code, id), payload.text_zh.trim()).await;
+            (StatusCode::OK, Json("Reason template updated")).into_response()
+        },
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            (StatusCode::CONFLICT, Json(json!({ "error": "A template with this code already exists" }))).into_response()
+        },
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/reason-templates/{id}",
+    params(
+        ("id" = i64, Path, description = "Template ID")
+    ),
+    responses(
+        (status = 200, description = "Reason template deleted"),
+        (status = 404, description = "Template not found")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn delete_reason(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    match sqlx::query("DELETE FROM reason_templates WHERE id = ?").bind(id).execute(&state.db).await {
+        Ok(r) if r.rows_affected() == 0 => (StatusCode::NOT_FOUND, Json(json!({ "error": "Reason template not found" }))).into_response(),
+        Ok(_) => {
+            let _ = log_admin_action(&state.db, &user.sub, "delete_reason_template", &format!("TemplateID: {}", id), "Deleted reason template").await;
+            (StatusCode::OK, Json("Reason template deleted")).into_response()
+        },
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
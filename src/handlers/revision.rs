@@ -0,0 +1,185 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::json;
+use std::sync::Arc;
+use crate::AppState;
+use crate::handlers::auth::Claims;
+use crate::handlers::ban::{ban_in_scope, check_ip_range};
+use crate::models::ban::Ban;
+use crate::models::revision::BanRevision;
+use crate::services::revision;
+use crate::services::steam_api::SteamService;
+use crate::utils::log_admin_action;
+
+#[utoipa::path(
+    get,
+    path = "/api/bans/{id}/revisions",
+    params(
+        ("id" = i64, Path, description = "Ban ID")
+    ),
+    responses(
+        (status = 200, description = "Edit history of the ban, newest first", body = Vec<BanRevision>),
+        (status = 404, description = "Ban not found")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_revisions(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let group_id: Option<Option<i64>> = sqlx::query_scalar("SELECT group_id FROM bans WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&state.db)
+        .await
+        .unwrap_or(None);
+    match group_id {
+        None => return (StatusCode::NOT_FOUND, Json(json!({ "error": "Ban not found" }))).into_response(),
+        Some(g) if !ban_in_scope(&user, g) => {
+            return (StatusCode::FORBIDDEN, Json(json!({ "error": "Ban belongs to a server group out of scope" }))).into_response();
+        }
+        Some(_) => {}
+    }
+
+    match revision::list(&state.db, id).await {
+        Ok(data) => (StatusCode::OK, Json(data)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/bans/{id}/revisions/{revision}/revert",
+    params(
+        ("id" = i64, Path, description = "Ban ID"),
+        ("revision" = i32, Path, description = "Revision to restore")
+    ),
+    responses(
+        (status = 200, description = "Ban restored to the revision"),
+        (status = 403, description = "Revision is out of your scope"),
+        (status = 404, description = "Ban or revision not found"),
+        (status = 409, description = "Revision has a different status; lift or reactivate the ban instead")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn revert_ban(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path((id, rev)): Path<(i64, i32)>,
+) -> impl IntoResponse {
+    let ban = match sqlx::query_as::<_, Ban>("SELECT * FROM bans WHERE id = ?").bind(id).fetch_optional(&state.db).await {
+        Ok(Some(b)) => b,
+        Ok(None) => return (StatusCode::NOT_FOUND, Json(json!({ "error": "Ban not found" }))).into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    if !ban_in_scope(&user, ban.group_id) {
+        return (StatusCode::FORBIDDEN, Json(json!({ "error": "Ban belongs to a server group out of scope" }))).into_response();
+    }
+
+    let target = match revision::find(&state.db, id, rev).await {
+        Ok(Some(r)) => r.snapshot,
+        Ok(None) => return (StatusCode::NOT_FOUND, Json(json!({ "error": "Revision not found" }))).into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    // 回滚与手动编辑受同样的限制：范围不能超出自己的服务器组，过宽的 IP 段仍需超级管理员
+    let groups = &target.server_group_ids;
+    if !user.is_global() && (groups.is_empty() || groups.iter().any(|g| !user.can_access_group(*g))) {
+        return (StatusCode::FORBIDDEN, Json(json!({ "error": "Ban scope must stay within your server groups" }))).into_response();
+    }
+    let range = if target.ban_type == "ip" {
+        match check_ip_range(&user, &target.ip, true) {
+            Ok(r) => Some(r),
+            Err((status, msg)) => return (status, Json(json!({ "error": msg }))).into_response(),
+        }
+    } else {
+        None
+    };
+
+    let before = match revision::snapshot(&state.db, id).await {
+        Ok(Some(s)) => s,
+        Ok(None) => return (StatusCode::NOT_FOUND, Json(json!({ "error": "Ban not found" }))).into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    // 状态变化必须经过解封接口（记录原因并下发 sm_unban）或重新启用封禁，回滚只恢复封禁内容
+    if target.status != before.status {
+        return (StatusCode::CONFLICT, Json(json!({
+            "error": format!("Revision {} has status '{}' but the ban is '{}'; use the unban endpoint or set the status to active instead", rev, target.status, before.status)
+        }))).into_response();
+    }
+
+    // 早期修订没有记录 SteamID3/64，按 SteamID 重新换算，避免仍指向另一个账号
+    let (steam_id_3, steam_id_64) = match &target.steam_id_64 {
+        Some(id64) => (target.steam_id_3.clone(), Some(id64.clone())),
+        None => {
+            let steam_service = SteamService::new();
+            let id64 = steam_service.resolve_steam_id(&target.steam_id).await;
+            (id64.as_deref().and_then(|id| steam_service.id64_to_id3(id)), id64)
+        }
+    };
+
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        sqlx::query(
+            "UPDATE bans SET name = ?, steam_id = ?, steam_id_3 = ?, steam_id_64 = ?, ip = ?, ip_start = ?, ip_end = ?, ban_type = ?, reason = ?, duration = ?, expires_at = ?, extend_to_linked = COALESCE(?, extend_to_linked) WHERE id = ?"
+        )
+        .bind(&target.name)
+        .bind(&target.steam_id)
+        .bind(&steam_id_3)
+        .bind(&steam_id_64)
+        .bind(&target.ip)
+        .bind(range.map(|r| r.start_key()))
+        .bind(range.map(|r| r.end_key()))
+        .bind(&target.ban_type)
+        .bind(&target.reason)
+        .bind(&target.duration)
+        .bind(target.expires_at)
+        .bind(target.extend_to_linked)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM ban_server_groups WHERE ban_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        for group_id in groups {
+            sqlx::query("INSERT IGNORE INTO ban_server_groups (ban_id, group_id) VALUES (?, ?)")
+                .bind(id)
+                .bind(group_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await
+    }.await;
+
+    if let Err(e) = result {
+        return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+    }
+
+    let note = format!("Reverted to revision {}", rev);
+    let changes = match revision::record(&state.db, id, Some(&before), "revert", &user.sub, Some(&note)).await {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::error!("Failed to record revert of ban {}: {}", id, e);
+            Vec::new()
+        }
+    };
+    let _ = log_admin_action(
+        &state.db,
+        &user.sub,
+        "revert_ban",
+        &format!("BanID: {}, Target: {} ({})", id, ban.name, ban.steam_id),
+        &format!("{}: {}", note, revision::summarize(&changes))
+    ).await;
+
+    (StatusCode::OK, Json("Ban reverted")).into_response()
+}
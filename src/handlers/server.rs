@@ -9,7 +9,9 @@ use crate::models::server::{
     ServerGroup, Server, GroupWithServers, 
     CreateGroupRequest, CreateServerRequest, UpdateServerRequest, CheckServerRequest
 };
+use crate::models::api_key::{is_known_scope, CreateApiKeyRequest, CreatedApiKey, ServerApiKey, PLUGIN_SCOPES};
 use crate::handlers::auth::Claims;
+use crate::services::api_key;
 use crate::utils::log_admin_action; // Ensure this is accessible
 use crate::utils::rcon::check_rcon;
 
@@ -27,6 +29,7 @@ use crate::utils::rcon::check_rcon;
 )]
 pub async fn list_server_groups(
     State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
 ) -> impl IntoResponse {
     // Fetch all groups
     let groups = sqlx::query_as::<_, ServerGroup>("SELECT * FROM server_groups ORDER BY id ASC")
@@ -42,7 +45,7 @@ pub async fn list_server_groups(
 
     // Combine
     let mut result = Vec::new();
-    for g in groups {
+    for g in groups.into_iter().filter(|g| user.can_access_group(g.id)) {
         let group_servers: Vec<Server> = servers.iter()
             .filter(|s| s.group_id == g.id)
             .map(|s| Server {
@@ -84,6 +87,10 @@ pub async fn create_group(
     Extension(user): Extension<Claims>,
     Json(payload): Json<CreateGroupRequest>,
 ) -> impl IntoResponse {
+    if !user.is_global() {
+        return (StatusCode::FORBIDDEN, Json("Scoped admins cannot create server groups")).into_response();
+    }
+
     let result = sqlx::query("INSERT INTO server_groups (name) VALUES (?)")
         .bind(&payload.name)
         .execute(&state.db)
@@ -105,7 +112,9 @@ pub async fn create_group(
         ("id" = i64, Path, description = "Group ID")
     ),
     responses(
-        (status = 200, description = "Group deleted")
+        (status = 200, description = "Group deleted"),
+        (status = 403, description = "Scoped admins cannot delete server groups"),
+        (status = 409, description = "Group still has assigned admins or bans")
     ),
     security(
         ("jwt" = [])
@@ -116,6 +125,38 @@ pub async fn delete_group(
     Extension(user): Extension<Claims>,
     Path(id): Path<i64>,
 ) -> impl IntoResponse {
+    if !user.is_global() {
+        return (StatusCode::FORBIDDEN, Json("Scoped admins cannot delete server groups")).into_response();
+    }
+
+    // Refuse instead of letting the foreign keys silently drop admin scopes and ban scopes.
+    let in_use: Result<(i64, i64), sqlx::Error> = async {
+        let admins: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM admin_server_groups WHERE group_id = ?")
+            .bind(id)
+            .fetch_one(&state.db)
+            .await?;
+        let bans: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM bans b WHERE b.group_id = ? \
+             OR EXISTS (SELECT 1 FROM ban_server_groups s WHERE s.ban_id = b.id AND s.group_id = ?)"
+        )
+        .bind(id)
+        .bind(id)
+        .fetch_one(&state.db)
+        .await?;
+        Ok((admins, bans))
+    }.await;
+
+    match in_use {
+        Ok((0, 0)) => {}
+        Ok((admins, bans)) => {
+            return (
+                StatusCode::CONFLICT,
+                Json(format!("Server group still has {} assigned admin(s) and {} ban(s)", admins, bans)),
+            ).into_response();
+        }
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+
     let result = sqlx::query("DELETE FROM server_groups WHERE id = ?")
         .bind(id)
         .execute(&state.db)
@@ -148,6 +189,10 @@ pub async fn create_server(
     Extension(user): Extension<Claims>,
     Json(payload): Json<CreateServerRequest>,
 ) -> impl IntoResponse {
+    if !user.can_access_group(payload.group_id) {
+        return (StatusCode::FORBIDDEN, Json("Server group out of scope")).into_response();
+    }
+
     let result = sqlx::query(
         "INSERT INTO servers (group_id, name, ip, port, rcon_password, verification_enabled) VALUES (?, ?, ?, ?, ?, ?)"
     )
@@ -189,6 +234,10 @@ pub async fn update_server(
     Path(id): Path<i64>,
     Json(payload): Json<UpdateServerRequest>,
 ) -> impl IntoResponse {
+    if let Err(resp) = fetch_scoped_server(&state, &user, id).await {
+        return resp;
+    }
+
     if let Some(name) = payload.name {
         let _ = sqlx::query("UPDATE servers SET name = ? WHERE id = ?").bind(name).bind(id).execute(&state.db).await;
     }
@@ -228,6 +277,10 @@ pub async fn delete_server(
     Extension(user): Extension<Claims>,
     Path(id): Path<i64>,
 ) -> impl IntoResponse {
+    if let Err(resp) = fetch_scoped_server(&state, &user, id).await {
+        return resp;
+    }
+
     let result = sqlx::query("DELETE FROM servers WHERE id = ?")
         .bind(id)
         .execute(&state.db)
@@ -242,6 +295,208 @@ pub async fn delete_server(
     }
 }
 
+/// Loads a server and checks it belongs to a group the admin is scoped to.
+async fn fetch_scoped_server(
+    state: &Arc<AppState>,
+    user: &Claims,
+    id: i64,
+) -> Result<Server, axum::response::Response> {
+    let server = sqlx::query_as::<_, Server>("SELECT * FROM servers WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&state.db)
+        .await
+        .unwrap_or(None);
+
+    match server {
+        Some(s) if user.can_access_group(s.group_id) => Ok(s),
+        Some(_) => Err((StatusCode::FORBIDDEN, Json("Server group out of scope")).into_response()),
+        None => Err((StatusCode::NOT_FOUND, "Server not found").into_response()),
+    }
+}
+
+// --- Plugin API Keys ---
+
+#[utoipa::path(
+    get,
+    path = "/api/servers/{id}/api-keys",
+    params(
+        ("id" = i64, Path, description = "Server ID")
+    ),
+    responses(
+        (status = 200, description = "API keys of the server, including revoked ones", body = Vec<ServerApiKey>)
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn list_api_keys(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    if let Err(resp) = fetch_scoped_server(&state, &user, id).await {
+        return resp;
+    }
+
+    let keys = sqlx::query_as::<_, ServerApiKey>("SELECT * FROM server_api_keys WHERE server_id = ? ORDER BY id DESC")
+        .bind(id)
+        .fetch_all(&state.db)
+        .await;
+
+    match keys {
+        Ok(data) => (StatusCode::OK, Json(data)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/servers/{id}/api-keys",
+    params(
+        ("id" = i64, Path, description = "Server ID")
+    ),
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 201, description = "API key created, the plain key is only shown once", body = CreatedApiKey),
+        (status = 400, description = "Unknown scope")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn create_api_key(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path(id): Path<i64>,
+    Json(payload): Json<CreateApiKeyRequest>,
+) -> impl IntoResponse {
+    if let Err(resp) = fetch_scoped_server(&state, &user, id).await {
+        return resp;
+    }
+
+    let scopes = payload.scopes
+        .unwrap_or_else(|| PLUGIN_SCOPES.iter().map(|(k, _)| k.to_string()).collect());
+    if scopes.is_empty() {
+        return (StatusCode::BAD_REQUEST, Json("At least one scope is required")).into_response();
+    }
+    if let Some(unknown) = scopes.iter().find(|s| !is_known_scope(s)) {
+        return (StatusCode::BAD_REQUEST, Json(format!("Unknown scope '{}'", unknown))).into_response();
+    }
+
+    match api_key::create(&state.db, id, &scopes, &user.sub).await {
+        Ok(created) => {
+            let _ = log_admin_action(
+                &state.db,
+                &user.sub,
+                "create_api_key",
+                &format!("ServerID: {}", id),
+                &format!("Key {} scopes: {}", created.info.key_prefix, created.info.scopes)
+            ).await;
+            (StatusCode::CREATED, Json(created)).into_response()
+        },
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/servers/{id}/api-keys/{key_id}/rotate",
+    params(
+        ("id" = i64, Path, description = "Server ID"),
+        ("key_id" = i64, Path, description = "API key ID")
+    ),
+    responses(
+        (status = 201, description = "Old key revoked, new key with the same scopes returned once", body = CreatedApiKey),
+        (status = 404, description = "Active key not found")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn rotate_api_key(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path((id, key_id)): Path<(i64, i64)>,
+) -> impl IntoResponse {
+    if let Err(resp) = fetch_scoped_server(&state, &user, id).await {
+        return resp;
+    }
+
+    let old = sqlx::query_as::<_, ServerApiKey>("SELECT * FROM server_api_keys WHERE id = ? AND server_id = ? AND revoked_at IS NULL")
+        .bind(key_id)
+        .bind(id)
+        .fetch_optional(&state.db)
+        .await;
+    let old = match old {
+        Ok(Some(k)) => k,
+        Ok(None) => return (StatusCode::NOT_FOUND, Json("API key not found")).into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    let scopes: Vec<String> = old.scopes.split(',').map(|s| s.to_string()).collect();
+    let created = match api_key::create(&state.db, id, &scopes, &user.sub).await {
+        Ok(c) => c,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    if let Err(e) = sqlx::query("UPDATE server_api_keys SET revoked_at = NOW() WHERE id = ?")
+        .bind(key_id)
+        .execute(&state.db)
+        .await
+    {
+        return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+    }
+
+    let _ = log_admin_action(
+        &state.db,
+        &user.sub,
+        "rotate_api_key",
+        &format!("ServerID: {}", id),
+        &format!("Key {} replaced by {}", old.key_prefix, created.info.key_prefix)
+    ).await;
+    (StatusCode::CREATED, Json(created)).into_response()
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/servers/{id}/api-keys/{key_id}",
+    params(
+        ("id" = i64, Path, description = "Server ID"),
+        ("key_id" = i64, Path, description = "API key ID")
+    ),
+    responses(
+        (status = 200, description = "API key revoked"),
+        (status = 404, description = "Active key not found")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn revoke_api_key(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
+    Path((id, key_id)): Path<(i64, i64)>,
+) -> impl IntoResponse {
+    if let Err(resp) = fetch_scoped_server(&state, &user, id).await {
+        return resp;
+    }
+
+    let result = sqlx::query("UPDATE server_api_keys SET revoked_at = NOW() WHERE id = ? AND server_id = ? AND revoked_at IS NULL")
+        .bind(key_id)
+        .bind(id)
+        .execute(&state.db)
+        .await;
+
+    match result {
+        Ok(r) if r.rows_affected() == 0 => (StatusCode::NOT_FOUND, Json("API key not found")).into_response(),
+        Ok(_) => {
+            let _ = log_admin_action(&state.db, &user.sub, "revoke_api_key", &format!("ServerID: {}", id), &format!("KeyID: {}", key_id)).await;
+            (StatusCode::OK, Json("API key revoked")).into_response()
+        },
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
 // --- Status Check ---
 
 #[utoipa::path(
@@ -285,7 +540,7 @@ use serde::{Serialize, Deserialize};
 use regex::Regex;
 use crate::utils::rcon::send_command;
 
-#[derive(Serialize, utoipa::ToSchema)]
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
 pub struct Player {
     pub userid: i32,
     pub name: String,
@@ -297,14 +552,24 @@ pub struct Player {
 #[derive(Deserialize, utoipa::ToSchema)]
 pub struct KickPlayerRequest {
     pub userid: i32,
+    /// Custom reason; overrides the template text when both are given
     pub reason: Option<String>,
+    pub reason_template_id: Option<i64>,
 }
 
 #[derive(Deserialize, utoipa::ToSchema)]
 pub struct BanPlayerRequest {
     pub userid: i32,
-    pub duration: i32, // minutes, 0 = permanent
+    /// Minutes, 0 = permanent; falls back to the reason template's default when omitted
+    pub duration: Option<i32>,
+    /// Custom reason; overrides the template text when both are given
     pub reason: Option<String>,
+    /// Reason category; its escalation ladder decides the duration unless `duration` is given
+    pub category_id: Option<i64>,
+    /// Reason template; also supplies the default duration and ban_type
+    pub reason_template_id: Option<i64>,
+    /// Extend the ban to linked accounts; defaults to true for IP bans
+    pub extend_to_linked: Option<bool>,
 }
 
 #[utoipa::path(
@@ -323,51 +588,17 @@ pub struct BanPlayerRequest {
 )]
 pub async fn get_server_players(
     State(state): State<Arc<AppState>>,
+    Extension(user): Extension<Claims>,
     Path(id): Path<i64>,
 ) -> impl IntoResponse {
     // Get server info
-    let server = sqlx::query_as::<_, Server>("SELECT * FROM servers WHERE id = ?")
-        .bind(id)
-        .fetch_optional(&state.db)
-        .await
-        .unwrap_or(None);
-
-    let server = match server {
-        Some(s) => s,
-        None => return (StatusCode::NOT_FOUND, "Server not found").into_response(),
+    let server = match fetch_scoped_server(&state, &user, id).await {
+        Ok(s) => s,
+        Err(resp) => return resp,
     };
 
-    let address = format!("{}:{}", server.ip, server.port);
-    let pwd = server.rcon_password.unwrap_or_default();
-
-    match send_command(&address, &pwd, "status").await {
-        Ok(output) => {
-            tracing::info!("RCON 'status' output: \n{}", output); // Debug log
-
-            let mut players = Vec::new();
-            // Regex to parse status output
-            // Regex: #\s*(\d+)\s+\d+\s+"(.+?)"\s+(\S+)\s+(\S+)\s+(\d+)
-            // Output format: # userid slot "name" steamid time ping ...
-            let re = Regex::new(r#"#\s+(\d+)\s+\d+\s+"(.+?)"\s+(\S+)\s+(\S+)\s+(\d+)"#).unwrap();
-
-            for cap in re.captures_iter(&output) {
-                 let userid = cap[1].parse::<i32>().unwrap_or(-1);
-                 let name = cap[2].to_string();
-                 let steam_id = cap[3].to_string();
-                 let time = cap[4].to_string();
-                 let ping = cap[5].parse::<i32>().unwrap_or(0);
-
-                 players.push(Player {
-                     userid,
-                     name,
-                     steam_id,
-                     time,
-                     ping,
-                 });
-            }
-
-            (StatusCode::OK, Json(players)).into_response()
-        },
+    match crate::services::enforcement::online_players(&state, &server).await {
+        Ok(players) => (StatusCode::OK, Json(players)).into_response(),
         Err(e) => (StatusCode::BAD_REQUEST, Json(format!("RCON Error: {}", e))).into_response(),
     }
 }
@@ -392,22 +623,24 @@ pub async fn kick_player(
     Path(id): Path<i64>,
     Json(payload): Json<KickPlayerRequest>,
 ) -> impl IntoResponse {
-     let server = sqlx::query_as::<_, Server>("SELECT * FROM servers WHERE id = ?")
-        .bind(id)
-        .fetch_optional(&state.db)
-        .await
-        .unwrap_or(None);
+    let server = match fetch_scoped_server(&state, &user, id).await {
+        Ok(s) => s,
+        Err(resp) => return resp,
+    };
 
-    let server = match server {
-        Some(s) => s,
-        None => return (StatusCode::NOT_FOUND, "Server not found").into_response(),
+    let template = match crate::handlers::reason::requested_template(&state, payload.reason_template_id).await {
+        Ok(t) => t,
+        Err(resp) => return resp,
     };
 
     let address = format!("{}:{}", server.ip, server.port);
     let pwd = server.rcon_password.unwrap_or_default();
     
     // Command: kickid <userid> [reason]
-    let reason = payload.reason.unwrap_or("Kicked by admin".to_string());
+    let reason = match &template {
+        Some(t) => t.reason(payload.reason.as_deref()),
+        None => payload.reason.unwrap_or("Kicked by admin".to_string()),
+    };
     let command = format!("kickid {} \"{}\"", payload.userid, reason);
 
     match send_command(&address, &pwd, &command).await {
@@ -445,15 +678,14 @@ pub async fn ban_player(
     Path(id): Path<i64>,
     Json(payload): Json<BanPlayerRequest>,
 ) -> impl IntoResponse {
-     let server = sqlx::query_as::<_, Server>("SELECT * FROM servers WHERE id = ?")
-        .bind(id)
-        .fetch_optional(&state.db)
-        .await
-        .unwrap_or(None);
+    let server = match fetch_scoped_server(&state, &user, id).await {
+        Ok(s) => s,
+        Err(resp) => return resp,
+    };
 
-    let server = match server {
-        Some(s) => s,
-        None => return (StatusCode::NOT_FOUND, "Server not found").into_response(),
+    let template = match crate::handlers::reason::requested_template(&state, payload.reason_template_id).await {
+        Ok(t) => t,
+        Err(resp) => return resp,
     };
 
     let address = format!("{}:{}", server.ip, server.port);
@@ -518,44 +750,97 @@ pub async fn ban_player(
     ));
 
     // 2. Insert Ban into DB
-    let expires_at = if payload.duration > 0 {
-         Some(chrono::Utc::now() + chrono::Duration::minutes(payload.duration as i64))
-    } else {
-         None
+    let steam_id_64 = crate::services::steam_api::SteamService::new().resolve_steam_id(&steam_id).await;
+
+    // 与手动封禁相同：填写的分钟数优先，否则按升级阶梯，再否则使用原因模板的默认时长
+    let mut duration = payload.duration
+        .map(|m| crate::services::import::duration_label(i64::from(m) * 60))
+        .unwrap_or_default();
+    let mut ladder = None;
+    if let Some(category_id) = payload.category_id {
+        let Some(steam_id_64) = &steam_id_64 else {
+            return (StatusCode::BAD_REQUEST, Json("Cannot apply a ban category: player SteamID unknown")).into_response();
+        };
+        let choice = match crate::services::escalation::choose(&state.db, category_id, steam_id_64, &duration).await {
+            Ok(Some(c)) => c,
+            Ok(None) => return (StatusCode::BAD_REQUEST, Json("Unknown ban category")).into_response(),
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        };
+        duration = choice.duration.clone();
+        ladder = Some(choice);
+    }
+    if duration.trim().is_empty() {
+        duration = template.as_ref()
+            .and_then(|t| t.default_duration.clone())
+            .unwrap_or("permanent".to_string());
+    }
+    let expires_at = match crate::utils::calculate_expires_at(&duration) {
+        Ok(t) => t,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(e.to_string())).into_response(),
     };
+    // sm_ban 只接受分钟数，0 表示永久
+    let minutes = expires_at.map_or(0, |t| (t - chrono::Utc::now()).num_minutes().max(1));
     
     let ip_only = ip.split(':').next().unwrap_or(&ip).to_string();
-    let reason = payload.reason.clone().unwrap_or("Banned by admin".to_string());
+    let ip_key = crate::utils::ip_range::parse_player_ip(&ip_only).map(crate::utils::ip_range::key);
+    let reason = match &template {
+        Some(t) => t.reason(payload.reason.as_deref()),
+        None => payload.reason.clone()
+            .or_else(|| ladder.as_ref().map(|c| c.category_name.clone()))
+            .unwrap_or("Banned by admin".to_string()),
+    };
+    let ban_type = template.as_ref()
+        .and_then(|t| t.default_ban_type.clone())
+        .unwrap_or("ip".to_string());
 
     tracing::info!("Attempting to insert ban for: Name={}, SteamID={}, IP={}", name, steam_id, ip_only);
 
     let db_result = sqlx::query(
-        "INSERT INTO bans (name, steam_id, ip, ban_type, reason, duration, admin_name, expires_at, created_at, status, server_id) VALUES (?, ?, ?, ?, ?, ?, ?, ?, NOW(), 'active', ?)"
+        "INSERT INTO bans (name, steam_id, steam_id_64, ip, ip_start, ip_end, ban_type, reason, duration, admin_name, expires_at, created_at, status, server_id, group_id, category_id, ladder_step, reason_template_id, extend_to_linked) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, NOW(), 'active', ?, ?, ?, ?, ?, ?)"
     )
     .bind(&name)
     .bind(&steam_id)
+    .bind(&steam_id_64)
     .bind(&ip_only)
-    .bind("ip") // Changed to 'ip' as requested
+    .bind(&ip_key)
+    .bind(&ip_key)
+    .bind(&ban_type)
     .bind(&reason)
-    .bind(payload.duration.to_string())
+    .bind(&duration)
     .bind(&user.sub)
     .bind(expires_at)
     .bind(server.id)
+    .bind(server.group_id)
+    .bind(payload.category_id)
+    .bind(ladder.as_ref().and_then(|c| c.step))
+    .bind(payload.reason_template_id)
+    .bind(payload.extend_to_linked.unwrap_or(ban_type == "ip"))
     .execute(&state.db)
     .await;
 
-    if let Err(e) = &db_result {
-        tracing::error!("Failed to insert ban into DB: {}", e);
-        // We continue to ban in game, but log error.
-        // Or should we return error? Usually we want to ensure game ban even if logging fails?
-        // But user wants logging.
-    } else {
-        tracing::info!("Ban inserted successfully");
+    match &db_result {
+        Err(e) => {
+            tracing::error!("Failed to insert ban into DB: {}", e);
+            // We continue to ban in game, but log error.
+            // Or should we return error? Usually we want to ensure game ban even if logging fails?
+            // But user wants logging.
+        }
+        Ok(res) => {
+            tracing::info!("Ban inserted successfully");
+            // Bans issued by scoped admins only apply within this server's group
+            if !user.is_global() {
+                let ban_id = res.last_insert_id() as i64;
+                if let Err(e) = crate::services::ban_scope::set_scope(&state.db, ban_id, &[server.group_id]).await {
+                    tracing::error!("Failed to scope ban {}: {}", ban_id, e);
+                }
+            }
+            crate::services::revision::track(&state.db, res.last_insert_id() as i64, None, "create", &user.sub).await;
+        }
     }
 
     // 3. Execute RCON Ban
     // Command: sm_ban #<userid> <minutes|0> [reason]
-    let command = format!("sm_ban #{} {} \"{}\"", payload.userid, payload.duration, reason);
+    let command = format!("sm_ban #{} {} \"{}\"", payload.userid, minutes, reason);
 
     match send_command(&address, &pwd, &command).await {
         Ok(_) => {
@@ -564,7 +849,10 @@ pub async fn ban_player(
                 &user.sub, 
                 "ban_player_rcon_db", 
                 &format!("Server: {}, UserID: {}", server.name, payload.userid), 
-                &format!("Duration: {}, Reason: {}, Player: {} ({})", payload.duration, reason, name, steam_id)
+                &match ladder.as_ref().and_then(|c| c.step) {
+                    Some(step) => format!("Duration: {} (ladder step {}), Reason: {}, Player: {} ({})", duration, step, reason, name, steam_id),
+                    None => format!("Duration: {}, Reason: {}, Player: {} ({})", duration, reason, name, steam_id),
+                }
             ).await;
             (StatusCode::OK, Json("Player banned and recorded")).into_response()
         },
@@ -1,4 +1,6 @@
 use axum::{
+    extract::DefaultBodyLimit,
+    handler::Handler,
     routing::{get, post},
     Router,
 };
@@ -18,23 +20,81 @@ mod utils;
 mod bg_task;
 mod services;
 
+use middleware::require;
+use models::api_key::{EventsPush, PlayersReport};
+use models::permission::*;
+
 #[derive(OpenApi)]
 #[openapi(
     paths(
         handlers::auth::login,
+        handlers::auth::login_2fa,
+        handlers::auth::steam_login,
+        handlers::auth::steam_verify,
+        handlers::auth::refresh,
         handlers::auth::logout,
         handlers::auth::me,
         handlers::auth::change_password,
+        handlers::auth::list_my_sessions,
+        handlers::auth::revoke_my_session,
+        handlers::auth::enroll_2fa,
+        handlers::auth::confirm_2fa,
         handlers::admin::list_admins,
         handlers::admin::create_admin,
         handlers::admin::update_admin,
         handlers::admin::delete_admin,
+        handlers::admin::get_admin_server_groups,
+        handlers::admin::set_admin_server_groups,
+        handlers::admin::list_admin_sessions,
+        handlers::admin::force_logout_admin,
+        handlers::admin::reset_admin_2fa,
+        handlers::admin::unlock_admin,
         handlers::ban::list_bans,
         handlers::ban::list_public_bans,
         handlers::ban::check_ban,
         handlers::ban::create_ban,
         handlers::ban::update_ban,
         handlers::ban::delete_ban,
+        handlers::ban::unban_ban,
+        handlers::escalation::list_categories,
+        handlers::escalation::create_category,
+        handlers::escalation::update_category,
+        handlers::escalation::delete_category,
+        handlers::escalation::propose_escalation,
+        handlers::reason::list_reasons,
+        handlers::reason::create_reason,
+        handlers::reason::update_reason,
+        handlers::reason::delete_reason,
+        handlers::evidence::upload_evidence,
+        handlers::evidence::list_evidence,
+        handlers::evidence::download_evidence,
+        handlers::evidence::download_public_evidence,
+        handlers::evidence::update_evidence,
+        handlers::evidence::delete_evidence,
+        handlers::revision::list_revisions,
+        handlers::revision::revert_ban,
+        handlers::identity::list_associations,
+        handlers::ip_match::get_policy,
+        handlers::ip_match::update_policy,
+        handlers::ip_match::list_exemptions,
+        handlers::ip_match::create_exemption,
+        handlers::ip_match::delete_exemption,
+        handlers::ip_match::list_reviews,
+        handlers::ip_match::decide_review,
+        handlers::import::import_sourcebans,
+        handlers::import::import_cfg,
+        handlers::export::export_bans,
+        handlers::comm::list_comms,
+        handlers::comm::check_comms,
+        handlers::comm::create_comm,
+        handlers::comm::update_comm,
+        handlers::comm::delete_comm,
+        handlers::appeal::submit_appeal,
+        handlers::appeal::get_public_appeal,
+        handlers::appeal::list_appeals,
+        handlers::appeal::get_appeal,
+        handlers::appeal::comment_appeal,
+        handlers::appeal::decide_appeal,
         handlers::whitelist::list_whitelist,
         handlers::whitelist::list_pending,
         handlers::whitelist::list_rejected,
@@ -52,6 +112,12 @@ mod services;
         handlers::server::update_server,
         handlers::server::delete_server,
         handlers::server::check_server_status,
+        handlers::server::list_api_keys,
+        handlers::server::create_api_key,
+        handlers::server::rotate_api_key,
+        handlers::server::revoke_api_key,
+        handlers::plugin::report_players,
+        handlers::plugin::push_event,
         handlers::server::get_server_players,
         handlers::server::kick_player,
         handlers::server::ban_player,
@@ -61,6 +127,11 @@ mod services;
         handlers::verification::create_verification,
         handlers::verification::update_verification,
         handlers::verification::delete_verification,
+        handlers::permission::list_permissions,
+        handlers::permission::set_role_permissions,
+        handlers::permission::set_role_2fa,
+        handlers::permission::get_admin_permissions,
+        handlers::permission::set_admin_permissions,
     ),
     components(
         schemas(
@@ -69,12 +140,57 @@ mod services;
             models::user::UpdateAdminRequest,
             models::user::LoginRequest,
             models::user::LoginResponse,
+            models::user::RefreshRequest,
+            models::user::TwoFactorLoginRequest,
+            models::user::TotpCodeRequest,
+            models::user::TotpEnrollment,
+            models::user::RecoveryCodes,
+            models::user::RoleTwoFactorRequest,
+            models::user::SteamVerifyRequest,
             models::user::ChangePasswordRequest,
             models::ban::Ban,
             models::ban::PublicBan,
+            models::ban::BanPage,
+            models::ban::PublicBanPage,
             models::ban::CreateBanRequest,
             models::ban::CreateBanRequest,
             models::ban::UpdateBanRequest,
+            models::ban::UnbanRequest,
+            models::escalation::BanCategory,
+            models::escalation::BanCategoryRequest,
+            models::escalation::EscalationProposal,
+            models::reason::ReasonTemplate,
+            models::reason::ReasonTemplateRequest,
+            models::evidence::Evidence,
+            models::evidence::PublicEvidence,
+            models::evidence::EvidenceUploadForm,
+            models::evidence::UpdateEvidenceRequest,
+            models::revision::BanRevision,
+            models::revision::BanSnapshot,
+            models::revision::FieldChange,
+            models::identity::LinkedAccount,
+            models::ip_match::IpMatchPolicy,
+            models::ip_match::UpdateIpMatchPolicyRequest,
+            models::ip_match::IpMatchExemption,
+            models::ip_match::CreateIpMatchExemptionRequest,
+            models::ip_match::IpMatchReview,
+            models::ip_match::DecideIpMatchRequest,
+            models::import::SourceBansImportRequest,
+            models::import::CfgImportForm,
+            models::import::ImportReport,
+            models::import::ImportCounts,
+            models::import::ImportIssue,
+            models::comm::CommBlock,
+            models::comm::CreateCommBlockRequest,
+            models::comm::UpdateCommBlockRequest,
+            models::comm::CommCheckResponse,
+            models::appeal::Appeal,
+            models::appeal::AppealComment,
+            models::appeal::AppealDetail,
+            models::appeal::PublicAppeal,
+            models::appeal::CreateAppealRequest,
+            models::appeal::AppealCommentRequest,
+            models::appeal::DecideAppealRequest,
             models::whitelist::Whitelist,
             models::whitelist::CreateWhitelistRequest,
             models::whitelist::ApplyWhitelistRequest,
@@ -86,6 +202,12 @@ mod services;
             models::server::CreateServerRequest,
             models::server::UpdateServerRequest,
             models::server::CheckServerRequest,
+            models::server::AdminServerGroupsRequest,
+            models::api_key::ServerApiKey,
+            models::api_key::CreateApiKeyRequest,
+            models::api_key::CreatedApiKey,
+            handlers::plugin::ReportPlayersRequest,
+            handlers::plugin::ServerEventRequest,
             handlers::server::Player,
             handlers::server::KickPlayerRequest,
             handlers::server::BanPlayerRequest,
@@ -94,6 +216,12 @@ mod services;
             handlers::verification::VerificationRecord,
             handlers::verification::CreateVerificationRequest,
             handlers::verification::UpdateVerificationRequest,
+            models::permission::PermissionInfo,
+            models::permission::PermissionOverview,
+            models::permission::AdminPermissions,
+            models::permission::SetPermissionsRequest,
+            models::session::Session,
+            models::session::RequiredAction,
         )
     ),
     tags(
@@ -115,7 +243,15 @@ impl utoipa::Modify for SecurityAddon {
                         utoipa::openapi::security::HttpAuthScheme::Bearer,
                     ),
                 ),
-            )
+            );
+            components.add_security_scheme(
+                "api_key",
+                utoipa::openapi::security::SecurityScheme::ApiKey(
+                    utoipa::openapi::security::ApiKey::Header(
+                        utoipa::openapi::security::ApiKeyValue::new("X-Api-Key"),
+                    ),
+                ),
+            );
         }
     }
 }
@@ -124,6 +260,7 @@ impl utoipa::Modify for SecurityAddon {
 pub struct AppState {
     pub db: sqlx::MySqlPool,
     pub client: reqwest::Client,
+    pub cache: services::cache::Cache,
 }
 
 #[tokio::main]
@@ -170,6 +307,7 @@ async fn main() {
     let state = Arc::new(AppState { 
         db: pool,
         client: reqwest::Client::new(),
+        cache: services::cache::Cache::connect().await,
     });
 
     // Spawn background task FIRST, cloning state
@@ -178,60 +316,159 @@ async fn main() {
         crate::bg_task::start_background_task(task_state).await;
     });
 
+    let expiry_state = state.clone();
+    tokio::spawn(async move {
+        crate::services::expiry_worker::start_expiry_worker(expiry_state).await;
+    });
+
     let verif_state = state.clone();
     tokio::spawn(async move {
         crate::services::verification_worker::start_verification_worker(verif_state.db.clone()).await;
     });
 
+    // Every protected route declares the permission it requires via `require::<P>()`.
     let protected_routes = Router::new()
         .route("/api/auth/me", get(handlers::auth::me))
         .route("/api/auth/logout", axum::routing::post(handlers::auth::logout))
+        .route("/api/auth/change-password", post(handlers::auth::change_password))
+        .route("/api/auth/sessions", get(handlers::auth::list_my_sessions))
+        .route("/api/auth/sessions/:sid", axum::routing::delete(handlers::auth::revoke_my_session))
+        .route("/api/auth/2fa/enroll", post(handlers::auth::enroll_2fa))
+        .route("/api/auth/2fa/confirm", post(handlers::auth::confirm_2fa))
         // Admins
-        .route("/api/admins", get(handlers::admin::list_admins).post(handlers::admin::create_admin))
-        .route("/api/admins/:id", axum::routing::put(handlers::admin::update_admin).delete(handlers::admin::delete_admin))
+        .route("/api/admins", get(handlers::admin::list_admins.layer(require::<AdminsManage>()))
+            .post(handlers::admin::create_admin.layer(require::<AdminsManage>())))
+        .route("/api/admins/:id", axum::routing::put(handlers::admin::update_admin.layer(require::<AdminsManage>()))
+            .delete(handlers::admin::delete_admin.layer(require::<AdminsManage>())))
+        .route("/api/admins/:id/permissions", get(handlers::permission::get_admin_permissions.layer(require::<AdminsManage>()))
+            .put(handlers::permission::set_admin_permissions.layer(require::<AdminsManage>())))
+        .route("/api/admins/:id/server-groups", get(handlers::admin::get_admin_server_groups.layer(require::<AdminsManage>()))
+            .put(handlers::admin::set_admin_server_groups.layer(require::<AdminsManage>())))
+        .route("/api/admins/:id/sessions", get(handlers::admin::list_admin_sessions.layer(require::<SessionsManage>())))
+        .route("/api/admins/:id/logout", post(handlers::admin::force_logout_admin.layer(require::<SessionsManage>())))
+        .route("/api/admins/:id/unlock", post(handlers::admin::unlock_admin.layer(require::<AdminsManage>())))
+        .route("/api/admins/:id/2fa/reset", post(handlers::admin::reset_admin_2fa.layer(require::<AdminsManage>())))
+        // Permissions
+        .route("/api/permissions", get(handlers::permission::list_permissions.layer(require::<AdminsManage>())))
+        .route("/api/roles/:role/permissions", axum::routing::put(handlers::permission::set_role_permissions.layer(require::<AdminsManage>())))
+        .route("/api/roles/:role/2fa", axum::routing::put(handlers::permission::set_role_2fa.layer(require::<AdminsManage>())))
         // Bans
-        .route("/api/bans", get(handlers::ban::list_bans).post(handlers::ban::create_ban))
-        .route("/api/bans/:id", axum::routing::put(handlers::ban::update_ban).delete(handlers::ban::delete_ban))
-        .route("/api/check_ban", get(handlers::ban::check_ban))
-        .route("/api/check_global_ban", get(handlers::ban::check_global_ban))
-        .route("/api/check_global_ban/bulk", post(handlers::ban::check_global_ban_bulk))
+        .route("/api/bans", get(handlers::ban::list_bans.layer(require::<BansView>()))
+            .post(handlers::ban::create_ban.layer(require::<BansCreate>())))
+        .route("/api/bans/:id", axum::routing::put(handlers::ban::update_ban.layer(require::<BansEdit>()))
+            .delete(handlers::ban::delete_ban.layer(require::<BansDelete>())))
+        .route("/api/bans/export", get(handlers::export::export_bans.layer(require::<BansExport>())))
+        .route("/api/bans/:id/unban", post(handlers::ban::unban_ban.layer(require::<BansEdit>())))
+        // Ban categories / escalation ladders
+        .route("/api/ban-categories", get(handlers::escalation::list_categories.layer(require::<BansView>()))
+            .post(handlers::escalation::create_category.layer(require::<BanCategoriesManage>())))
+        .route("/api/ban-categories/:id", axum::routing::put(handlers::escalation::update_category.layer(require::<BanCategoriesManage>()))
+            .delete(handlers::escalation::delete_category.layer(require::<BanCategoriesManage>())))
+        .route("/api/ban-categories/:id/propose", get(handlers::escalation::propose_escalation.layer(require::<BansCreate>())))
+        // Reason templates
+        .route("/api/reason-templates", get(handlers::reason::list_reasons.layer(require::<BansView>()))
+            .post(handlers::reason::create_reason.layer(require::<ReasonsManage>())))
+        .route("/api/reason-templates/:id", axum::routing::put(handlers::reason::update_reason.layer(require::<ReasonsManage>()))
+            .delete(handlers::reason::delete_reason.layer(require::<ReasonsManage>())))
+        // Ban revision history
+        .route("/api/bans/:id/revisions", get(handlers::revision::list_revisions.layer(require::<BansView>())))
+        .route("/api/bans/:id/revisions/:revision/revert", post(handlers::revision::revert_ban.layer(require::<BansEdit>())))
+        // Player identity graph
+        .route("/api/players/:steam_id/associations", get(handlers::identity::list_associations.layer(require::<BansView>())))
+        // IP-match policy and review queue
+        .route("/api/ip-match/policy", get(handlers::ip_match::get_policy.layer(require::<BansView>()))
+            .put(handlers::ip_match::update_policy.layer(require::<IpMatchManage>())))
+        .route("/api/ip-match/exemptions", get(handlers::ip_match::list_exemptions.layer(require::<IpMatchReview>()))
+            .post(handlers::ip_match::create_exemption.layer(require::<IpMatchManage>())))
+        .route("/api/ip-match/exemptions/:id", axum::routing::delete(handlers::ip_match::delete_exemption.layer(require::<IpMatchManage>())))
+        .route("/api/ip-match/reviews", get(handlers::ip_match::list_reviews.layer(require::<IpMatchReview>())))
+        // Import from SourceBans++ / SourceMod cfg files
+        .route("/api/import/sourcebans", post(handlers::import::import_sourcebans.layer(require::<BansImport>())))
+        .route("/api/import/cfg", post(handlers::import::import_cfg.layer(require::<BansImport>()))
+            .layer(DefaultBodyLimit::max(16 * 1024 * 1024)))
+        .route("/api/ip-match/reviews/:id/status", axum::routing::put(handlers::ip_match::decide_review.layer(require::<IpMatchReview>())))
+        // Ban evidence
+        .route("/api/bans/:id/evidence", get(handlers::evidence::list_evidence.layer(require::<EvidenceView>()))
+            .post(handlers::evidence::upload_evidence.layer(require::<EvidenceManage>()))
+            .layer(DefaultBodyLimit::max(services::evidence::max_bytes() as usize + 1024 * 1024)))
+        .route("/api/evidence/:id", axum::routing::put(handlers::evidence::update_evidence.layer(require::<EvidenceManage>()))
+            .delete(handlers::evidence::delete_evidence.layer(require::<EvidenceManage>())))
+        .route("/api/evidence/:id/download", get(handlers::evidence::download_evidence.layer(require::<EvidenceView>())))
+        // Comm blocks (mute / gag / silence)
+        .route("/api/comms", get(handlers::comm::list_comms.layer(require::<CommsView>()))
+            .post(handlers::comm::create_comm.layer(require::<CommsManage>())))
+        .route("/api/comms/:id", axum::routing::put(handlers::comm::update_comm.layer(require::<CommsManage>()))
+            .delete(handlers::comm::delete_comm.layer(require::<CommsManage>())))
+        // Appeals
+        .route("/api/appeals", get(handlers::appeal::list_appeals.layer(require::<AppealsView>())))
+        .route("/api/appeals/:id", get(handlers::appeal::get_appeal.layer(require::<AppealsView>())))
+        .route("/api/appeals/:id/comments", post(handlers::appeal::comment_appeal.layer(require::<AppealsReview>())))
+        .route("/api/appeals/:id/status", axum::routing::put(handlers::appeal::decide_appeal.layer(require::<AppealsReview>())))
         // Logs
-        .route("/api/logs", get(handlers::log::list_logs).post(handlers::log::create_log))
+        .route("/api/logs", get(handlers::log::list_logs.layer(require::<LogsView>()))
+            .post(handlers::log::create_log.layer(require::<LogsCreate>())))
 
         // Whitelist (管理员操作)
-        .route("/api/whitelist", get(handlers::whitelist::list_whitelist).post(handlers::whitelist::create_whitelist))
-        .route("/api/whitelist/pending", get(handlers::whitelist::list_pending))
-        .route("/api/whitelist/rejected", get(handlers::whitelist::list_rejected))
-        .route("/api/whitelist/:id", axum::routing::delete(handlers::whitelist::delete_whitelist))
-        .route("/api/whitelist/:id/approve", axum::routing::put(handlers::whitelist::approve_whitelist))
-        .route("/api/whitelist/:id/reject", axum::routing::put(handlers::whitelist::reject_whitelist))
+        .route("/api/whitelist", get(handlers::whitelist::list_whitelist.layer(require::<WhitelistView>()))
+            .post(handlers::whitelist::create_whitelist.layer(require::<WhitelistManage>())))
+        .route("/api/whitelist/pending", get(handlers::whitelist::list_pending.layer(require::<WhitelistView>())))
+        .route("/api/whitelist/rejected", get(handlers::whitelist::list_rejected.layer(require::<WhitelistView>())))
+        .route("/api/whitelist/:id", axum::routing::delete(handlers::whitelist::delete_whitelist.layer(require::<WhitelistManage>())))
+        .route("/api/whitelist/:id/approve", axum::routing::put(handlers::whitelist::approve_whitelist.layer(require::<WhitelistReview>())))
+        .route("/api/whitelist/:id/reject", axum::routing::put(handlers::whitelist::reject_whitelist.layer(require::<WhitelistReview>())))
 
         // Verifications (Manual)
-        .route("/api/verifications", get(handlers::verification::list_verifications).post(handlers::verification::create_verification))
-        .route("/api/verifications/:id", axum::routing::put(handlers::verification::update_verification).delete(handlers::verification::delete_verification))
+        .route("/api/verifications", get(handlers::verification::list_verifications.layer(require::<VerificationsManage>()))
+            .post(handlers::verification::create_verification.layer(require::<VerificationsManage>())))
+        .route("/api/verifications/:id", axum::routing::put(handlers::verification::update_verification.layer(require::<VerificationsManage>()))
+            .delete(handlers::verification::delete_verification.layer(require::<VerificationsManage>())))
 
         // Server Management
-        .route("/api/server-groups", get(handlers::server::list_server_groups).post(handlers::server::create_group))
-        .route("/api/server-groups/:id", axum::routing::delete(handlers::server::delete_group))
-        .route("/api/servers", axum::routing::post(handlers::server::create_server))
-        .route("/api/servers/:id", axum::routing::put(handlers::server::update_server).delete(handlers::server::delete_server))
-        .route("/api/servers/check", axum::routing::post(handlers::server::check_server_status))
+        .route("/api/server-groups", get(handlers::server::list_server_groups.layer(require::<ServersView>()))
+            .post(handlers::server::create_group.layer(require::<ServersManage>())))
+        .route("/api/server-groups/:id", axum::routing::delete(handlers::server::delete_group.layer(require::<ServersManage>())))
+        .route("/api/servers", axum::routing::post(handlers::server::create_server.layer(require::<ServersManage>())))
+        .route("/api/servers/:id", axum::routing::put(handlers::server::update_server.layer(require::<ServersManage>()))
+            .delete(handlers::server::delete_server.layer(require::<ServersManage>())))
+        .route("/api/servers/check", axum::routing::post(handlers::server::check_server_status.layer(require::<ServersManage>())))
+        .route("/api/servers/:id/api-keys", get(handlers::server::list_api_keys.layer(require::<ServersManage>()))
+            .post(handlers::server::create_api_key.layer(require::<ServersManage>())))
+        .route("/api/servers/:id/api-keys/:key_id", axum::routing::delete(handlers::server::revoke_api_key.layer(require::<ServersManage>())))
+        .route("/api/servers/:id/api-keys/:key_id/rotate", post(handlers::server::rotate_api_key.layer(require::<ServersManage>())))
         // Player Management
-        .route("/api/servers/:id/players", get(handlers::server::get_server_players))
-        .route("/api/servers/:id/kick", axum::routing::post(handlers::server::kick_player))
-        .route("/api/servers/:id/ban", axum::routing::post(handlers::server::ban_player))
-        .route_layer(axum::middleware::from_fn(middleware::auth_middleware));
+        .route("/api/servers/:id/players", get(handlers::server::get_server_players.layer(require::<ServersRcon>())))
+        .route("/api/servers/:id/kick", axum::routing::post(handlers::server::kick_player.layer(require::<ServersRcon>())))
+        .route("/api/servers/:id/ban", axum::routing::post(handlers::server::ban_player.layer(require::<ServersRcon>()).layer(require::<BansCreate>())))
+        .route_layer(axum::middleware::from_fn_with_state(state.clone(), middleware::auth_middleware));
+
+    // Routes the game server plugin calls with its `X-Api-Key`; admin tokens work too.
+    let plugin_routes = Router::new()
+        .route("/api/check_ban", get(handlers::ban::check_ban.layer(require::<BansCheck>())))
+        .route("/api/check_comms", get(handlers::comm::check_comms.layer(require::<BansCheck>())))
+        .route("/api/check_global_ban", get(handlers::ban::check_global_ban.layer(require::<BansCheck>())))
+        .route("/api/check_global_ban/bulk", post(handlers::ban::check_global_ban_bulk.layer(require::<BansCheck>())))
+        .route("/api/plugin/players", post(handlers::plugin::report_players.layer(require::<PlayersReport>())))
+        .route("/api/plugin/events", post(handlers::plugin::push_event.layer(require::<EventsPush>())))
+        .route_layer(axum::middleware::from_fn_with_state(state.clone(), middleware::server_auth_middleware));
 
     let app = Router::new()
         .route("/", get(root))
         .route("/api/auth/login", axum::routing::post(handlers::auth::login))
-        .route("/api/auth/change-password", axum::routing::post(handlers::auth::change_password))
+        .route("/api/auth/refresh", axum::routing::post(handlers::auth::refresh))
+        .route("/api/auth/login/2fa", axum::routing::post(handlers::auth::login_2fa))
+        .route("/api/auth/steam/login", get(handlers::auth::steam_login))
+        .route("/api/auth/steam/verify", axum::routing::post(handlers::auth::steam_verify))
         // 公开路由：白名单申请（无需认证）
         .route("/api/whitelist/apply", axum::routing::post(handlers::whitelist::apply_whitelist))
         .route("/api/whitelist/public-list", get(handlers::whitelist::list_public_whitelist))
         .route("/api/whitelist/player-info", get(handlers::whitelist::get_player_info))
         .route("/api/bans/public", get(handlers::ban::list_public_bans))
+        .route("/api/bans/public/evidence/:id", get(handlers::evidence::download_public_evidence))
+        // 公开路由：封禁申诉
+        .route("/api/appeals/submit", post(handlers::appeal::submit_appeal))
+        .route("/api/appeals/public/:id", get(handlers::appeal::get_public_appeal))
         .merge(protected_routes)
+        .merge(plugin_routes)
         .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
         .layer(TraceLayer::new_for_http())
         .layer(CorsLayer::permissive())
@@ -243,7 +480,7 @@ async fn main() {
 
     tracing::info!("listening on {}", addr);
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await.unwrap();
 }
 
 async fn root() -> &'static str {
@@ -257,22 +494,60 @@ async fn ensure_super_admin(pool: &sqlx::MySqlPool) {
         .unwrap_or(0);
 
     if count == 0 {
-        tracing::info!("No admins found. Creating default super_admin.");
-        let username = "admin";
-        let password = "123456"; 
-        let hashed = bcrypt::hash(password, bcrypt::DEFAULT_COST).expect("Failed to hash password");
+        tracing::info!("No admins found. Creating initial super_admin.");
+        // 初始账号可通过 INITIAL_ADMIN_USERNAME / INITIAL_ADMIN_PASSWORD 指定，
+        // 未指定密码时生成一次性随机密码，仅在此处打印一次
+        let username = std::env::var("INITIAL_ADMIN_USERNAME").unwrap_or_else(|_| "admin".to_string());
+        let (password, generated) = match std::env::var("INITIAL_ADMIN_PASSWORD") {
+            Ok(p) if !p.is_empty() => (p, false),
+            _ => {
+                use rand::{distributions::Alphanumeric, Rng};
+                let p: String = rand::thread_rng().sample_iter(&Alphanumeric).take(20).map(char::from).collect();
+                (p, true)
+            }
+        };
+        let hashed = bcrypt::hash(&password, bcrypt::DEFAULT_COST).expect("Failed to hash password");
         
         let _ = sqlx::query(
-            "INSERT INTO admins (username, password, role) VALUES (?, ?, 'super_admin')"
+            "INSERT INTO admins (username, password, role, must_change_password) VALUES (?, ?, 'super_admin', TRUE)"
         )
-        .bind(username)
+        .bind(&username)
         .bind(hashed)
         .execute(pool)
         .await
-        .expect("Failed to create default admin");
+        .expect("Failed to create initial admin");
         
-        tracing::info!("Default admin created: user='admin', pass='123456'");
+        if generated {
+            // 不写入日志，避免密码落盘
+            println!("==================================================");
+            println!(" Initial super admin created");
+            println!("   username: {}", username);
+            println!("   password: {}", password);
+            println!(" This password is shown only once and must be");
+            println!(" changed on first login.");
+            println!("==================================================");
+        }
+        tracing::info!("Initial admin '{}' created, password change required on first login", username);
     } else {
         tracing::info!("Super admin exists. Skipping creation.");
+        flag_default_password(pool).await;
+    }
+}
+
+/// 旧版本创建的 admin/123456 默认账号在升级后同样被要求改密
+async fn flag_default_password(pool: &sqlx::MySqlPool) {
+    let row: Option<(i64, String)> = sqlx::query_as("SELECT id, password FROM admins WHERE username = 'admin' AND must_change_password = FALSE")
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+
+    if let Some((id, hashed)) = row {
+        if bcrypt::verify("123456", &hashed).unwrap_or(false) {
+            tracing::warn!("Admin 'admin' still uses the default password, a password change will be required");
+            let _ = sqlx::query("UPDATE admins SET must_change_password = TRUE WHERE id = ?")
+                .bind(id)
+                .execute(pool)
+                .await;
+        }
     }
 }
@@ -1,13 +1,25 @@
 use axum::{
-    extract::Request,
-    http::{self, StatusCode},
-    middleware::Next,
-    response::Response,
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts, Request, State},
+    http::{self, request::Parts, StatusCode},
+    middleware::{FromExtractorLayer, Next},
+    response::{IntoResponse, Response},
+    Json,
 };
 use jsonwebtoken::{decode, DecodingKey, Validation};
+use serde_json::{json, Value};
+use std::marker::PhantomData;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use crate::AppState;
 use crate::handlers::auth::Claims;
+use crate::models::api_key::ServerIdentity;
+use crate::models::permission::Permission;
+use crate::models::session::RequiredAction;
+use crate::services::{api_key, session};
 
 pub async fn auth_middleware(
+    State(state): State<Arc<AppState>>,
     mut req: Request,
     next: Next,
 ) -> Result<Response, StatusCode> {
@@ -34,10 +46,200 @@ pub async fn auth_middleware(
         &Validation::default(),
     );
 
-    if let Ok(token_data) = token_data {
-        req.extensions_mut().insert(token_data.claims);
-        Ok(next.run(req).await)
-    } else {
-        Err(StatusCode::UNAUTHORIZED)
+    let claims = match token_data {
+        Ok(token_data) => token_data.claims,
+        Err(_) => return Err(StatusCode::UNAUTHORIZED),
+    };
+
+    // 令牌必须对应一个仍然有效的服务端会话，登出/强制下线后立即失效
+    let session = match session::get(&state.cache, &claims.sid).await {
+        Ok(Some(s)) => s,
+        Ok(None) => return Err(StatusCode::UNAUTHORIZED),
+        Err(e) => {
+            tracing::error!("Failed to look up session: {}", e);
+            return Err(StatusCode::SERVICE_UNAVAILABLE);
+        }
+    };
+
+    if let Some(action) = session.required_action {
+        if !allowed_while_pending(action, req.uri().path()) {
+            return Ok((
+                StatusCode::FORBIDDEN,
+                Json(json!({ "error": "Action required before continuing", "required_action": action })),
+            ).into_response());
+        }
+    }
+
+    req.extensions_mut().insert(claims);
+    req.extensions_mut().insert(session);
+    Ok(next.run(req).await)
+}
+
+/// For routes the game server plugin calls: accepts a server API key in `X-Api-Key`,
+/// otherwise falls back to the admin JWT.
+pub async fn server_auth_middleware(
+    State(state): State<Arc<AppState>>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let key = match req.headers().get("x-api-key").and_then(|v| v.to_str().ok()) {
+        Some(key) => key.to_string(),
+        None => return auth_middleware(State(state), req, next).await,
+    };
+
+    let ip = req.extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| crate::utils::client_ip(req.headers(), addr))
+        .unwrap_or_default();
+
+    match api_key::authenticate(&state.db, &key, &ip).await {
+        Ok(Some(identity)) => {
+            req.extensions_mut().insert(identity);
+            Ok(next.run(req).await)
+        }
+        Ok(None) => Err(StatusCode::UNAUTHORIZED),
+        Err(e) => {
+            tracing::error!("Failed to verify API key: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Routes a session can still reach while it has a pending required action
+fn allowed_while_pending(action: RequiredAction, path: &str) -> bool {
+    if matches!(path, "/api/auth/me" | "/api/auth/logout") {
+        return true;
+    }
+    match action {
+        RequiredAction::Enroll2fa => matches!(path, "/api/auth/2fa/enroll" | "/api/auth/2fa/confirm"),
+        RequiredAction::ChangePassword => path == "/api/auth/change-password",
+    }
+}
+
+/// Extractor that rejects the request unless the authenticated admin holds `P`.
+/// Must run behind `auth_middleware`, which puts the `Claims` into the extensions.
+/// Requests authenticated with a server API key need the matching scope instead.
+pub struct RequirePermission<P>(PhantomData<P>);
+
+#[async_trait]
+impl<P, S> FromRequestParts<S> for RequirePermission<P>
+where
+    P: Permission,
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, Json<Value>);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let (allowed, actor) = if let Some(claims) = parts.extensions.get::<Claims>() {
+            (claims.has_permission(P::KEY), claims.sub.clone())
+        } else if let Some(identity) = parts.extensions.get::<ServerIdentity>() {
+            (identity.has_scope(P::KEY), identity.actor())
+        } else {
+            return Err((StatusCode::UNAUTHORIZED, Json(json!({ "error": "Unauthorized" }))));
+        };
+
+        if allowed {
+            Ok(RequirePermission(PhantomData))
+        } else {
+            tracing::warn!("Permission '{}' denied for {}", P::KEY, actor);
+            Err((
+                StatusCode::FORBIDDEN,
+                Json(json!({ "error": "Permission denied", "required": P::KEY })),
+            ))
+        }
+    }
+}
+
+/// Plugin-only handlers take `ServerIdentity` directly; admin tokens are rejected.
+#[async_trait]
+impl<S> FromRequestParts<S> for ServerIdentity
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, Json<Value>);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts.extensions.get::<ServerIdentity>().cloned().ok_or((
+            StatusCode::FORBIDDEN,
+            Json(json!({ "error": "Server API key required" })),
+        ))
+    }
+}
+
+/// Route layer declaring the permission a handler requires, e.g.
+/// `get(handlers::ban::list_bans.layer(require::<BansView>()))`
+pub fn require<P: Permission>() -> FromExtractorLayer<RequirePermission<P>, ()> {
+    axum::middleware::from_extractor::<RequirePermission<P>>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::permission::{BansCreate, BansView};
+    use crate::models::server::Server;
+
+    fn parts_with<T: Clone + Send + Sync + 'static>(extension: Option<T>) -> Parts {
+        let (mut parts, _) = http::Request::new(()).into_parts();
+        if let Some(extension) = extension {
+            parts.extensions.insert(extension);
+        }
+        parts
+    }
+
+    async fn check<P: Permission>(parts: &mut Parts) -> Result<(), StatusCode> {
+        RequirePermission::<P>::from_request_parts(parts, &())
+            .await
+            .map(|_| ())
+            .map_err(|(status, _)| status)
+    }
+
+    fn claims(role: &str, permissions: &[&str]) -> Claims {
+        Claims {
+            sub: "tester".to_string(),
+            role: role.to_string(),
+            permissions: permissions.iter().map(|p| p.to_string()).collect(),
+            server_groups: None,
+            sid: String::new(),
+            exp: 0,
+        }
+    }
+
+    fn server_key(scopes: &[&str]) -> ServerIdentity {
+        ServerIdentity {
+            server: Server {
+                id: 1,
+                group_id: 1,
+                name: "test".to_string(),
+                ip: "127.0.0.1".to_string(),
+                port: 27015,
+                rcon_password: None,
+                created_at: None,
+                verification_enabled: false,
+            },
+            scopes: scopes.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn requires_the_permission_in_the_token() {
+        let mut parts = parts_with(Some(claims("admin", &["bans.view"])));
+        assert_eq!(check::<BansView>(&mut parts).await, Ok(()));
+        assert_eq!(check::<BansCreate>(&mut parts).await, Err(StatusCode::FORBIDDEN));
+
+        let mut parts = parts_with(Some(claims("super_admin", &[])));
+        assert_eq!(check::<BansCreate>(&mut parts).await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn server_keys_need_the_matching_scope() {
+        let mut parts = parts_with(Some(server_key(&["bans.view"])));
+        assert_eq!(check::<BansView>(&mut parts).await, Ok(()));
+        assert_eq!(check::<BansCreate>(&mut parts).await, Err(StatusCode::FORBIDDEN));
+    }
+
+    #[tokio::test]
+    async fn unauthenticated_requests_are_rejected() {
+        let mut parts = parts_with::<Claims>(None);
+        assert_eq!(check::<BansView>(&mut parts).await, Err(StatusCode::UNAUTHORIZED));
     }
 }
@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+use crate::models::permission::Permission;
+use crate::models::server::Server;
+
+/// Scopes a server API key can carry, as (key, description).
+/// `bans.check` shares its key with the admin permission so the same routes accept both.
+pub const PLUGIN_SCOPES: &[(&str, &str)] = &[
+    ("bans.check", "查询封禁状态"),
+    ("players.report", "上报在线玩家列表"),
+    ("events.push", "推送服务器事件到操作日志"),
+];
+
+pub fn is_known_scope(scope: &str) -> bool {
+    PLUGIN_SCOPES.iter().any(|(k, _)| *k == scope)
+}
+
+/// Plugin-only scopes; admins never hold these
+pub struct PlayersReport;
+
+impl Permission for PlayersReport {
+    const KEY: &'static str = "players.report";
+}
+
+pub struct EventsPush;
+
+impl Permission for EventsPush {
+    const KEY: &'static str = "events.push";
+}
+
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct ServerApiKey {
+    pub id: i64,
+    pub server_id: i64,
+    pub key_prefix: String,
+    /// Comma separated scope list
+    pub scopes: String,
+    pub created_by: String,
+    pub created_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub last_used_ip: Option<String>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateApiKeyRequest {
+    /// Defaults to every plugin scope
+    pub scopes: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreatedApiKey {
+    /// Plain key, shown only once
+    pub key: String,
+    pub info: ServerApiKey,
+}
+
+/// A request authenticated with a server API key instead of an admin JWT
+#[derive(Debug, Clone)]
+pub struct ServerIdentity {
+    pub server: Server,
+    pub scopes: Vec<String>,
+}
+
+impl ServerIdentity {
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+
+    /// Name used for audit log entries written on behalf of the server
+    /// (truncated to fit `audit_logs.admin_username`)
+    pub fn actor(&self) -> String {
+        format!("Server: {}", self.server.name).chars().take(64).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::permission::{is_known_permission, BansCheck};
+
+    fn identity(name: &str, scopes: &[&str]) -> ServerIdentity {
+        ServerIdentity {
+            server: Server {
+                id: 1,
+                group_id: 1,
+                name: name.to_string(),
+                ip: "127.0.0.1".to_string(),
+                port: 27015,
+                rcon_password: None,
+                created_at: None,
+                verification_enabled: false,
+            },
+            scopes: scopes.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn knows_every_plugin_scope() {
+        for key in [BansCheck::KEY, PlayersReport::KEY, EventsPush::KEY] {
+            assert!(is_known_scope(key), "{}", key);
+        }
+        assert!(!is_known_scope("bans.create"));
+        assert!(!is_known_scope(""));
+        // 只有 bans.check 与管理员权限共用
+        assert!(is_known_permission(BansCheck::KEY));
+        assert!(!is_known_permission(PlayersReport::KEY));
+        assert!(!is_known_permission(EventsPush::KEY));
+    }
+
+    #[test]
+    fn keys_only_hold_their_own_scopes() {
+        let key = identity("test", &["bans.check"]);
+        assert!(key.has_scope("bans.check"));
+        assert!(!key.has_scope("players.report"));
+        assert!(!key.has_scope("bans.create"));
+    }
+
+    #[test]
+    fn truncates_the_audit_actor() {
+        assert_eq!(identity("KZ #1", &[]).actor(), "Server: KZ #1");
+        assert_eq!(identity(&"x".repeat(100), &[]).actor().chars().count(), 64);
+    }
+}
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+pub const APPEAL_STATUSES: &[&str] = &["open", "under_review", "accepted", "denied"];
+
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Appeal {
+    pub id: i64,
+    pub ban_id: i64,
+    pub steam_id: String,
+    pub contact: Option<String>,
+    pub reason: String,
+    pub status: String, // 'open', 'under_review', 'accepted', 'denied'
+    pub decided_by: Option<String>,
+    pub decision_note: Option<String>,
+    pub decided_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl Appeal {
+    pub fn is_pending(&self) -> bool {
+        self.status == "open" || self.status == "under_review"
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct AppealComment {
+    pub id: i64,
+    pub appeal_id: i64,
+    pub author: String,
+    pub content: String,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AppealDetail {
+    #[serde(flatten)]
+    pub appeal: Appeal,
+    pub ban_name: String,
+    pub ban_reason: Option<String>,
+    pub ban_status: String,
+    pub comments: Vec<AppealComment>,
+}
+
+/// What the player sees when checking their appeal (no comments or contact info)
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct PublicAppeal {
+    pub id: i64,
+    pub ban_id: i64,
+    pub status: String,
+    pub decision_note: Option<String>,
+    pub decided_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateAppealRequest {
+    pub ban_id: i64,
+    /// Any SteamID format; must match the banned account
+    pub steam_id: String,
+    pub reason: String,
+    pub contact: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AppealCommentRequest {
+    pub content: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct DecideAppealRequest {
+    /// 'under_review', 'accepted' or 'denied'
+    pub status: String,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AppealListQuery {
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AppealLookupQuery {
+    pub steam_id: String,
+}
@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use chrono::{DateTime, Utc};
 use utoipa::ToSchema;
+use crate::models::evidence::PublicEvidence;
 
 #[derive(Debug, Serialize, Deserialize, FromRow, Clone, ToSchema)]
 pub struct Ban {
@@ -19,6 +20,41 @@ pub struct Ban {
     pub created_at: Option<DateTime<Utc>>,
     pub expires_at: Option<DateTime<Utc>>,
     pub server_id: Option<i64>,
+    pub group_id: Option<i64>,
+    /// Escalation ladder category and the step that was applied (None if the duration was overridden)
+    #[sqlx(default)]
+    pub category_id: Option<i64>,
+    #[sqlx(default)]
+    pub ladder_step: Option<i32>,
+    /// Reason template the ban was created from
+    #[sqlx(default)]
+    pub reason_template_id: Option<i64>,
+    /// Who lifted the ban, when and why (most recent unban)
+    #[sqlx(default)]
+    pub unbanned_by: Option<String>,
+    #[sqlx(default)]
+    pub unbanned_at: Option<DateTime<Utc>>,
+    #[sqlx(default)]
+    pub unban_reason: Option<String>,
+    /// Whether the ban extends to linked accounts (new accounts on a banned IP, alt accounts)
+    #[sqlx(default)]
+    pub extend_to_linked: bool,
+    /// The ban this one was derived from when it was created for a linked account
+    #[sqlx(default)]
+    pub linked_from: Option<i64>,
+    /// Origin of an imported ban, e.g. `sourcebans:<database>.<prefix>:<bid>`
+    #[sqlx(default)]
+    pub import_ref: Option<String>,
+    /// Server groups the ban applies to; empty means global
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub scope_group_ids: Vec<i64>,
+}
+
+impl Ban {
+    pub fn applies_to_group(&self, group_id: i64) -> bool {
+        self.scope_group_ids.is_empty() || self.scope_group_ids.contains(&group_id)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
@@ -34,17 +70,100 @@ pub struct PublicBan {
     pub admin_name: Option<String>,
     pub created_at: Option<DateTime<Utc>>,
     pub expires_at: Option<DateTime<Utc>>,
+    /// Evidence files marked as public
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub evidence: Vec<PublicEvidence>,
+}
+
+/// Filters, sort order and page for `GET /api/bans`. Timestamps accept RFC 3339 or
+/// `YYYY-MM-DD [HH:MM]` (BAN_TIMEZONE when no offset is given); upper bounds are exclusive.
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct BanSearchQuery {
+    /// 1-based page number (default 1)
+    pub page: Option<u32>,
+    /// Rows per page (default 50, at most 200)
+    pub page_size: Option<u32>,
+    pub status: Option<String>,
+    pub ban_type: Option<String>,
+    pub admin_name: Option<String>,
+    pub server_id: Option<i64>,
+    /// Text contained in the reason
+    pub reason: Option<String>,
+    /// SteamID in any format (ID64, STEAM_X:Y:Z, [U:1:N], profile URL or vanity name)
+    pub steam_id: Option<String>,
+    /// Player name fragment or SteamID in any format
+    pub player: Option<String>,
+    pub created_from: Option<String>,
+    pub created_to: Option<String>,
+    pub expires_from: Option<String>,
+    pub expires_to: Option<String>,
+    /// created_at (default), expires_at, id or name
+    pub sort: Option<String>,
+    /// desc (default) or asc
+    pub order: Option<String>,
+}
+
+/// Pagination and player search for `GET /api/bans/public`
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PublicBanQuery {
+    pub page: Option<u32>,
+    pub page_size: Option<u32>,
+    /// Player name fragment or SteamID in any format
+    pub player: Option<String>,
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BanPage {
+    pub items: Vec<Ban>,
+    /// Rows matching the filters across all pages
+    pub total: i64,
+    pub page: u32,
+    pub page_size: u32,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PublicBanPage {
+    pub items: Vec<PublicBan>,
+    pub total: i64,
+    pub page: u32,
+    pub page_size: u32,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CreateBanRequest {
     pub name: String,
     pub steam_id: String,
+    /// For IP bans: a single address, CIDR (`10.0.0.0/24`) or range (`10.0.0.1-10.0.0.50`)
     pub ip: String,
+    /// May be left empty when `reason_template_id` supplies a default
+    #[serde(default)]
     pub ban_type: String,
+    /// Custom reason; overrides the template text when both are given
     pub reason: Option<String>,
+    /// Reason template; also supplies the default duration and ban_type
+    pub reason_template_id: Option<i64>,
+    /// 'permanent', e.g. '30m' / '1d12h' / '2mo', ISO 8601 ('P1DT12H') or 'Until 2026-01-01 12:00 [+08:00]'
+    #[serde(default)]
     pub duration: String,
     pub admin_name: String,
+    /// Owning server group; required for admins scoped to several groups
+    pub group_id: Option<i64>,
+    /// Server groups the ban applies to; omitted or empty means global
+    pub server_group_ids: Option<Vec<i64>>,
+    /// Super admins must set this to ban an IP range wider than /16 (IPv4) or /32 (IPv6)
+    #[serde(default)]
+    pub confirm_wide_range: bool,
+    /// Reason category; with an empty duration the next step of its escalation ladder is used
+    pub category_id: Option<i64>,
+    /// Extend the ban to linked accounts; defaults to true for IP bans, false for account bans
+    pub extend_to_linked: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UnbanRequest {
+    pub reason: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -56,4 +175,9 @@ pub struct UpdateBanRequest {
     pub reason: Option<String>,
     pub duration: Option<String>,
     pub status: Option<String>,
+    /// Replaces the enforcement scope; empty list makes the ban global
+    pub server_group_ids: Option<Vec<i64>>,
+    #[serde(default)]
+    pub confirm_wide_range: bool,
+    pub extend_to_linked: Option<bool>,
 }
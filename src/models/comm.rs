@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+pub const COMM_TYPES: &[&str] = &["mute", "gag", "silence"];
+
+/// A voice mute, chat gag or both (silence)
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, ToSchema)]
+pub struct CommBlock {
+    pub id: i64,
+    pub name: String,
+    pub steam_id: String,
+    pub steam_id_3: Option<String>,
+    pub steam_id_64: Option<String>,
+    pub block_type: String, // 'mute', 'gag', 'silence'
+    pub reason: Option<String>,
+    pub duration: String,
+    pub status: String, // 'active', 'expired', 'removed'
+    pub admin_name: Option<String>,
+    pub server_id: Option<i64>,
+    pub group_id: Option<i64>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl CommBlock {
+    /// SourceMod basecomm command applying or lifting this block
+    pub fn rcon_command(&self, apply: bool) -> Option<&'static str> {
+        match (self.block_type.as_str(), apply) {
+            ("mute", true) => Some("sm_mute"),
+            ("mute", false) => Some("sm_unmute"),
+            ("gag", true) => Some("sm_gag"),
+            ("gag", false) => Some("sm_ungag"),
+            ("silence", true) => Some("sm_silence"),
+            ("silence", false) => Some("sm_unsilence"),
+            _ => None,
+        }
+    }
+
+    pub fn blocks_voice(&self) -> bool {
+        self.block_type == "mute" || self.block_type == "silence"
+    }
+
+    pub fn blocks_chat(&self) -> bool {
+        self.block_type == "gag" || self.block_type == "silence"
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateCommBlockRequest {
+    pub name: String,
+    pub steam_id: String,
+    /// 'mute', 'gag' or 'silence'
+    pub block_type: String,
+    pub reason: Option<String>,
+    /// Same format as ban durations, e.g. '30m', '1d' or 'permanent'
+    pub duration: String,
+    /// Owning server group; required for admins scoped to several groups
+    pub group_id: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateCommBlockRequest {
+    pub block_type: Option<String>,
+    pub reason: Option<String>,
+    /// Restarts the block from now with the new duration
+    pub duration: Option<String>,
+    /// 'active' or 'removed'
+    pub status: Option<String>,
+}
+
+/// Active comm blocks for a player, as seen by the game server plugin
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CommCheckResponse {
+    pub muted: bool,
+    pub gagged: bool,
+    pub blocks: Vec<CommBlock>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CommCheckQuery {
+    pub steam_id: String,
+    pub server_id: Option<i64>,
+}
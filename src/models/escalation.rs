@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+/// A ban reason category with its escalation ladder
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct BanCategory {
+    pub id: i64,
+    pub name: String,
+    pub description: Option<String>,
+    /// Only prior bans from the last N days count; None counts all of them
+    pub lookback_days: Option<i32>,
+    pub created_at: Option<DateTime<Utc>>,
+    /// Durations for the 1st, 2nd, ... offence; the last step repeats
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub steps: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BanCategoryRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub lookback_days: Option<i32>,
+    /// e.g. ["1d", "7d", "permanent"]
+    pub steps: Vec<String>,
+}
+
+/// The ladder step a new ban in this category would get
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EscalationProposal {
+    pub category_id: i64,
+    pub category_name: String,
+    /// Prior bans of the player in this category (within the lookback window)
+    pub prior_offences: i64,
+    /// 1-based ladder step
+    pub step: i32,
+    pub duration: String,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProposeQuery {
+    pub steam_id: String,
+}
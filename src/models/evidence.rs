@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Evidence {
+    pub id: i64,
+    pub ban_id: i64,
+    pub file_name: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    /// Hex SHA-256 of the file content
+    pub sha256: String,
+    pub description: Option<String>,
+    /// Whether the file is linked from the public ban list
+    pub is_public: bool,
+    pub uploaded_by: String,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// Evidence link shown on the public ban page
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct PublicEvidence {
+    pub id: i64,
+    #[serde(skip)]
+    pub ban_id: i64,
+    pub file_name: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub sha256: String,
+    pub description: Option<String>,
+    #[sqlx(skip)]
+    pub url: String,
+}
+
+/// Multipart form of the upload endpoint (documentation only)
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct EvidenceUploadForm {
+    #[schema(value_type = String, format = Binary)]
+    pub file: Vec<u8>,
+    pub description: Option<String>,
+    /// Show a download link on the public ban page
+    pub public: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateEvidenceRequest {
+    pub description: Option<String>,
+    pub is_public: Option<bool>,
+}
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+/// One (account, IP) pair another account shares an IP with
+#[derive(Debug, FromRow)]
+pub struct SharedIpRow {
+    pub steam_id_64: String,
+    pub ip: String,
+    /// Distinct accounts ever seen on this IP
+    pub accounts_on_ip: i64,
+    pub last_name: Option<String>,
+    pub first_seen: Option<DateTime<Utc>>,
+    pub last_seen: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LinkedAccount {
+    pub steam_id_64: String,
+    pub last_name: Option<String>,
+    pub shared_ips: Vec<String>,
+    /// 0..1; lower for IPs shared by many accounts (internet cafés, campus NAT)
+    pub confidence: f64,
+    pub first_seen: Option<DateTime<Utc>>,
+    pub last_seen: Option<DateTime<Utc>>,
+    /// Active ban on the linked account, if any
+    pub active_ban_id: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AssociationsQuery {
+    pub min_confidence: Option<f64>,
+}
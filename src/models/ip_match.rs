@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+pub const IP_MATCH_MODES: &[&str] = &["off", "flag", "auto"];
+pub const EXEMPTION_KINDS: &[&str] = &["steam_id", "ip_range"];
+
+/// What happens when a new account shows up on a banned IP (or is linked to a banned account)
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct IpMatchPolicy {
+    /// 'off', 'flag' (queue for review) or 'auto' (ban immediately)
+    pub mode: String,
+    /// Players with an approved whitelist entry are never matched
+    pub exempt_whitelisted: bool,
+    pub updated_by: Option<String>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateIpMatchPolicyRequest {
+    pub mode: String,
+    pub exempt_whitelisted: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct IpMatchExemption {
+    pub id: i64,
+    /// 'steam_id' or 'ip_range'
+    pub kind: String,
+    /// SteamID64, or the address / CIDR / range as entered
+    pub value: String,
+    pub note: Option<String>,
+    pub created_by: String,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateIpMatchExemptionRequest {
+    pub kind: String,
+    pub value: String,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct IpMatchReview {
+    pub id: i64,
+    /// The IP (or linked account) ban that was matched
+    pub parent_ban_id: i64,
+    pub steam_id: String,
+    pub steam_id_64: Option<String>,
+    pub name: Option<String>,
+    pub ip: String,
+    pub server_id: Option<i64>,
+    /// 'join', 'status' or 'alt_link'
+    pub source: String,
+    /// 'pending', 'confirmed' or 'dismissed'
+    pub status: String,
+    pub match_count: i32,
+    pub decided_by: Option<String>,
+    pub decision_note: Option<String>,
+    pub decided_at: Option<DateTime<Utc>>,
+    /// Ban created when the match was confirmed
+    pub created_ban_id: Option<i64>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub last_seen: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct IpMatchReviewQuery {
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct DecideIpMatchRequest {
+    /// 'confirmed' or 'dismissed'
+    pub status: String,
+    pub note: Option<String>,
+}
@@ -4,3 +4,15 @@ pub mod log;
 
 pub mod server;
 pub mod whitelist;
+pub mod permission;
+pub mod session;
+pub mod api_key;
+pub mod appeal;
+pub mod comm;
+pub mod escalation;
+pub mod reason;
+pub mod evidence;
+pub mod revision;
+pub mod identity;
+pub mod ip_match;
+pub mod import;
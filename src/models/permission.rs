@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// A permission key that can be required by a route.
+/// Each key gets a marker type so `main.rs` can declare `require::<BansDelete>()`.
+pub trait Permission: Send + Sync + 'static {
+    const KEY: &'static str;
+}
+
+macro_rules! permissions {
+    ($($ty:ident => $key:literal, $desc:literal;)*) => {
+        $(
+            pub struct $ty;
+
+            impl Permission for $ty {
+                const KEY: &'static str = $key;
+            }
+        )*
+
+        /// All known permissions as (key, description)
+        pub const ALL_PERMISSIONS: &[(&str, &str)] = &[$(($key, $desc)),*];
+    };
+}
+
+permissions! {
+    BansView => "bans.view", "查看封禁列表";
+    BansCreate => "bans.create", "创建封禁";
+    BansEdit => "bans.edit", "编辑封禁";
+    BansDelete => "bans.delete", "彻底删除封禁记录（另需超级管理员）";
+    BansCheck => "bans.check", "插件查询封禁状态";
+    BansExport => "bans.export", "导出封禁列表（SourceMod cfg / CSV / JSON）";
+    BansImport => "bans.import", "从 SourceBans++ 或 SourceMod cfg 导入封禁";
+    BanCategoriesManage => "bans.categories", "管理封禁分类与升级阶梯";
+    ReasonsManage => "reasons.manage", "管理封禁/踢出原因模板";
+    IpMatchManage => "ipmatch.manage", "配置同 IP 匹配策略与豁免名单";
+    IpMatchReview => "ipmatch.review", "审核同 IP 匹配队列";
+    EvidenceView => "evidence.view", "查看并下载封禁证据";
+    EvidenceManage => "evidence.manage", "上传/编辑/删除封禁证据";
+    CommsView => "comms.view", "查看禁言处罚";
+    CommsManage => "comms.manage", "创建/编辑/解除禁言处罚";
+    AppealsView => "appeals.view", "查看封禁申诉";
+    AppealsReview => "appeals.review", "评论并裁决封禁申诉";
+    AdminsManage => "admins.manage", "管理管理员及其权限";
+    SessionsManage => "sessions.manage", "查看并强制下线其他管理员的会话";
+    ServersView => "servers.view", "查看服务器列表";
+    ServersManage => "servers.manage", "管理服务器与服务器组";
+    ServersRcon => "servers.rcon", "通过 RCON 查看/踢出/封禁玩家";
+    WhitelistView => "whitelist.view", "查看白名单";
+    WhitelistManage => "whitelist.manage", "添加/删除白名单";
+    WhitelistReview => "whitelist.review", "审核白名单申请";
+    VerificationsManage => "verifications.manage", "管理玩家验证记录";
+    LogsView => "logs.view", "查看操作日志";
+    LogsCreate => "logs.create", "写入操作日志";
+}
+
+pub fn is_known_permission(key: &str) -> bool {
+    ALL_PERMISSIONS.iter().any(|(k, _)| *k == key)
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PermissionInfo {
+    pub key: String,
+    pub description: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PermissionOverview {
+    pub permissions: Vec<PermissionInfo>,
+    pub roles: std::collections::HashMap<String, Vec<String>>,
+    /// Roles whose admins must enroll TOTP 2FA
+    pub require_2fa: Vec<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AdminPermissions {
+    pub admin_id: i64,
+    pub role: String,
+    pub role_permissions: Vec<String>,
+    pub extra_permissions: Vec<String>,
+    pub effective: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SetPermissionsRequest {
+    pub permissions: Vec<String>,
+}
@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ReasonTemplate {
+    pub id: i64,
+    /// Stable short code, e.g. 'cheating'
+    pub code: String,
+    pub text_zh: String,
+    pub text_en: String,
+    pub default_duration: Option<String>,
+    /// 'account' or 'ip'
+    pub default_ban_type: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl ReasonTemplate {
+    /// Text stored on the ban and shown in game; a non-empty custom reason wins
+    pub fn reason(&self, custom: Option<&str>) -> String {
+        match custom.map(str::trim) {
+            Some(c) if !c.is_empty() => c.to_string(),
+            _ => self.text_zh.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ReasonTemplateRequest {
+    pub code: String,
+    pub text_zh: String,
+    pub text_en: String,
+    pub default_duration: Option<String>,
+    pub default_ban_type: Option<String>,
+}
@@ -0,0 +1,177 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+use crate::models::ban::Ban;
+
+/// The editable state of a ban at one point in its history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct BanSnapshot {
+    pub name: String,
+    pub steam_id: String,
+    /// None in revisions recorded before the SteamID3/64 were tracked
+    #[serde(default)]
+    pub steam_id_3: Option<String>,
+    #[serde(default)]
+    pub steam_id_64: Option<String>,
+    pub ip: String,
+    pub ban_type: String,
+    pub reason: Option<String>,
+    pub duration: String,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub status: String,
+    #[serde(default)]
+    pub server_group_ids: Vec<i64>,
+    /// None in revisions recorded before the flag existed
+    #[serde(default)]
+    pub extend_to_linked: Option<bool>,
+}
+
+impl BanSnapshot {
+    /// Expects `scope_group_ids` to be loaded
+    pub fn of(ban: &Ban) -> Self {
+        let mut server_group_ids = ban.scope_group_ids.clone();
+        server_group_ids.sort_unstable();
+        BanSnapshot {
+            name: ban.name.clone(),
+            steam_id: ban.steam_id.clone(),
+            steam_id_3: ban.steam_id_3.clone(),
+            steam_id_64: ban.steam_id_64.clone(),
+            ip: ban.ip.clone(),
+            ban_type: ban.ban_type.clone(),
+            reason: ban.reason.clone(),
+            duration: ban.duration.clone(),
+            expires_at: ban.expires_at,
+            status: ban.status.clone(),
+            server_group_ids,
+            extend_to_linked: Some(ban.extend_to_linked),
+        }
+    }
+
+    /// Fields that differ between `self` (before) and `after`
+    pub fn diff(&self, after: &BanSnapshot) -> Vec<FieldChange> {
+        let before = serde_json::to_value(self).unwrap_or_default();
+        let after = serde_json::to_value(after).unwrap_or_default();
+        SNAPSHOT_FIELDS.iter()
+            .filter(|f| before[**f] != after[**f])
+            .map(|f| FieldChange {
+                field: f.to_string(),
+                before: before[*f].clone(),
+                after: after[*f].clone(),
+            })
+            .collect()
+    }
+}
+
+const SNAPSHOT_FIELDS: &[&str] = &[
+    "name", "steam_id", "steam_id_3", "steam_id_64", "ip", "ban_type", "reason", "duration", "expires_at", "status", "server_group_ids",
+    "extend_to_linked",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FieldChange {
+    pub field: String,
+    #[schema(value_type = Object)]
+    pub before: serde_json::Value,
+    #[schema(value_type = Object)]
+    pub after: serde_json::Value,
+}
+
+#[derive(Debug, FromRow)]
+pub struct BanRevisionRow {
+    pub id: i64,
+    pub ban_id: i64,
+    pub revision: i32,
+    pub change_type: String,
+    pub changed_by: String,
+    pub note: Option<String>,
+    pub changes: String,
+    pub snapshot: String,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BanRevision {
+    pub id: i64,
+    pub ban_id: i64,
+    /// Sequence number within the ban, starting at 1
+    pub revision: i32,
+    /// 'create', 'update', 'expire', 'unban' or 'revert'
+    pub change_type: String,
+    pub changed_by: String,
+    pub note: Option<String>,
+    pub changes: Vec<FieldChange>,
+    /// State of the ban after this revision
+    pub snapshot: BanSnapshot,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<BanRevisionRow> for BanRevision {
+    type Error = serde_json::Error;
+
+    fn try_from(row: BanRevisionRow) -> Result<Self, Self::Error> {
+        Ok(BanRevision {
+            id: row.id,
+            ban_id: row.ban_id,
+            revision: row.revision,
+            change_type: row.change_type,
+            changed_by: row.changed_by,
+            note: row.note,
+            changes: serde_json::from_str(&row.changes)?,
+            snapshot: serde_json::from_str(&row.snapshot)?,
+            created_at: row.created_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot() -> BanSnapshot {
+        BanSnapshot {
+            name: "Player".to_string(),
+            steam_id: "STEAM_0:1:12345".to_string(),
+            steam_id_3: Some("[U:1:24691]".to_string()),
+            steam_id_64: Some("76561197960290419".to_string()),
+            ip: "10.0.0.1".to_string(),
+            ban_type: "account".to_string(),
+            reason: Some("cheating".to_string()),
+            duration: "1d".to_string(),
+            expires_at: None,
+            status: "active".to_string(),
+            server_group_ids: vec![1],
+            extend_to_linked: Some(false),
+        }
+    }
+
+    #[test]
+    fn diff_lists_changed_fields_only() {
+        let before = snapshot();
+        assert!(before.diff(&before.clone()).is_empty());
+
+        let after = BanSnapshot {
+            steam_id: "STEAM_0:0:50".to_string(),
+            steam_id_3: Some("[U:1:100]".to_string()),
+            steam_id_64: Some("76561197960265828".to_string()),
+            duration: "7d".to_string(),
+            ..before.clone()
+        };
+        let changes = before.diff(&after);
+        let fields: Vec<_> = changes.iter().map(|c| c.field.as_str()).collect();
+        assert_eq!(fields, vec!["steam_id", "steam_id_3", "steam_id_64", "duration"]);
+        assert_eq!(changes[3].before, serde_json::json!("1d"));
+        assert_eq!(changes[3].after, serde_json::json!("7d"));
+    }
+
+    #[test]
+    fn reads_snapshots_recorded_before_newer_fields() {
+        let old = r#"{"name":"Player","steam_id":"STEAM_0:1:12345","ip":"","ban_type":"account",
+            "reason":null,"duration":"permanent","expires_at":null,"status":"active"}"#;
+        let parsed: BanSnapshot = serde_json::from_str(old).unwrap();
+        assert_eq!(parsed.steam_id_64, None);
+        assert_eq!(parsed.steam_id_3, None);
+        assert!(parsed.server_group_ids.is_empty());
+        assert_eq!(parsed.extend_to_linked, None);
+    }
+}
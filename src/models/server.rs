@@ -10,7 +10,7 @@ pub struct ServerGroup {
     pub created_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct Server {
     pub id: i64,
     pub group_id: i64,
@@ -61,3 +61,8 @@ pub struct CheckServerRequest {
     pub port: u16,
     pub rcon_password: Option<String>,
 }
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AdminServerGroupsRequest {
+    pub group_ids: Vec<i64>,
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct Session {
+    pub id: String,
+    pub admin_id: i64,
+    pub username: String,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// While set, the session may only reach the endpoints needed to resolve it
+    #[serde(default)]
+    pub required_action: Option<RequiredAction>,
+    /// Set when listing, true for the session making the request
+    #[serde(default)]
+    pub current: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RequiredAction {
+    /// The admin's role requires 2FA but none is enrolled yet
+    Enroll2fa,
+    /// Bootstrap or reset password that has to be replaced first
+    ChangePassword,
+}
@@ -15,6 +15,10 @@ pub struct Admin {
     pub steam_id_64: Option<String>,
     pub created_at: Option<DateTime<Utc>>,
     pub remark: Option<String>,
+    #[serde(skip_serializing)]
+    pub totp_secret: Option<String>,
+    pub totp_enabled: bool,
+    pub must_change_password: bool,
 }
 
 
@@ -44,7 +48,17 @@ pub struct LoginRequest {
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct LoginResponse {
+    /// Short-lived access token
     pub token: String,
+    /// Single-use token for `/api/auth/refresh`, rotated on every use
+    pub refresh_token: String,
+    /// Access token lifetime in seconds
+    pub expires_in: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -52,3 +66,39 @@ pub struct ChangePasswordRequest {
     pub old_password: String,
     pub new_password: String,
 }
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TwoFactorLoginRequest {
+    /// Token returned by `/api/auth/login` when 2FA is required
+    pub mfa_token: String,
+    /// Current authenticator code or an unused recovery code
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TotpCodeRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TotpEnrollment {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RecoveryCodes {
+    /// Shown only once; only hashes are stored
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RoleTwoFactorRequest {
+    pub required: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SteamVerifyRequest {
+    /// All `openid.*` query parameters Steam appended to the return URL
+    pub params: std::collections::HashMap<String, String>,
+}
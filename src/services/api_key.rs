@@ -0,0 +1,98 @@
+use sha2::{Digest, Sha256};
+use sqlx::MySqlPool;
+use crate::models::api_key::{CreatedApiKey, ServerApiKey, ServerIdentity};
+use crate::models::server::Server;
+
+const KEY_PREFIX: &str = "zbk_";
+
+pub fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// Splits the stored comma separated scope list
+fn parse_scopes(raw: &str) -> Vec<String> {
+    raw.split(',').filter(|s| !s.is_empty()).map(|s| s.to_string()).collect()
+}
+
+/// Creates a key for the server; the plain key is only returned here
+pub async fn create(pool: &MySqlPool, server_id: i64, scopes: &[String], created_by: &str) -> Result<CreatedApiKey, sqlx::Error> {
+    let secret: [u8; 24] = rand::random();
+    let key = format!("{}{}", KEY_PREFIX, hex::encode(secret));
+    let prefix = key[..KEY_PREFIX.len() + 8].to_string();
+
+    let result = sqlx::query("INSERT INTO server_api_keys (server_id, key_prefix, key_hash, scopes, created_by) VALUES (?, ?, ?, ?, ?)")
+        .bind(server_id)
+        .bind(&prefix)
+        .bind(hash_key(&key))
+        .bind(scopes.join(","))
+        .bind(created_by)
+        .execute(pool)
+        .await?;
+
+    let info = sqlx::query_as::<_, ServerApiKey>("SELECT * FROM server_api_keys WHERE id = ?")
+        .bind(result.last_insert_id() as i64)
+        .fetch_one(pool)
+        .await?;
+
+    Ok(CreatedApiKey { key, info })
+}
+
+/// Resolves an API key to the server it belongs to and records its use
+pub async fn authenticate(pool: &MySqlPool, key: &str, ip: &str) -> Result<Option<ServerIdentity>, sqlx::Error> {
+    if !key.starts_with(KEY_PREFIX) {
+        return Ok(None);
+    }
+
+    let row = sqlx::query_as::<_, ServerApiKey>("SELECT * FROM server_api_keys WHERE key_hash = ? AND revoked_at IS NULL")
+        .bind(hash_key(key))
+        .fetch_optional(pool)
+        .await?;
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    let server = sqlx::query_as::<_, Server>("SELECT * FROM servers WHERE id = ?")
+        .bind(row.server_id)
+        .fetch_optional(pool)
+        .await?;
+    let Some(server) = server else {
+        return Ok(None);
+    };
+
+    // 最多每分钟写一次，避免插件高频请求时每次都更新
+    sqlx::query("UPDATE server_api_keys SET last_used_at = NOW(), last_used_ip = ? WHERE id = ? AND (last_used_at IS NULL OR last_used_at < NOW() - INTERVAL 1 MINUTE OR last_used_ip <> ?)")
+        .bind(ip)
+        .bind(row.id)
+        .bind(ip)
+        .execute(pool)
+        .await?;
+
+    Ok(Some(ServerIdentity {
+        server,
+        scopes: parse_scopes(&row.scopes),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::api_key::is_known_scope;
+
+    #[test]
+    fn reads_stored_scope_lists() {
+        assert_eq!(parse_scopes("bans.check,players.report"), vec!["bans.check", "players.report"]);
+        assert_eq!(parse_scopes("events.push"), vec!["events.push"]);
+        assert!(parse_scopes("").is_empty());
+        assert_eq!(parse_scopes("bans.check,,"), vec!["bans.check"]);
+        assert!(parse_scopes(&["bans.check", "players.report", "events.push"].join(",")).iter().all(|s| is_known_scope(s)));
+    }
+
+    #[test]
+    fn stores_only_a_hash_of_the_key() {
+        let key = "zbk_0123456789abcdef";
+        assert_eq!(hash_key(key), hash_key(key));
+        assert_ne!(hash_key(key), hash_key("zbk_0123456789abcdeg"));
+        assert_eq!(hash_key(key).len(), 64);
+        assert!(!hash_key(key).contains(key));
+    }
+}
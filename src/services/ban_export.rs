@@ -0,0 +1,244 @@
+use std::net::Ipv4Addr;
+use axum::body::Body;
+use chrono::{DateTime, Utc};
+use futures::channel::mpsc;
+use futures::{SinkExt, TryStreamExt};
+use serde_json::{Map, Value};
+use sqlx::{MySql, MySqlPool, QueryBuilder};
+use crate::models::ban::Ban;
+use crate::services::ban_search::BanFilters;
+
+/// Columns that can be selected for CSV / JSON exports
+pub const EXPORT_FIELDS: &[&str] = &[
+    "id", "name", "steam_id", "steam_id_3", "steam_id_64", "ip", "ban_type", "reason", "duration",
+    "status", "admin_name", "created_at", "expires_at", "server_id", "group_id",
+    "unbanned_by", "unbanned_at", "unban_reason",
+];
+
+/// Columns exported when none are selected
+pub const DEFAULT_FIELDS: &[&str] = &[
+    "id", "name", "steam_id", "steam_id_64", "ip", "ban_type", "reason", "duration",
+    "status", "admin_name", "created_at", "expires_at",
+];
+
+/// Rows are buffered into chunks of about this size before being sent
+const CHUNK_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// SourceMod `banned_user.cfg`: `banid <minutes> <steamid>`
+    BannedUser,
+    /// SourceMod `banned_ip.cfg`: `addip <minutes> <ip>`
+    BannedIp,
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn parse(input: &str) -> Option<Self> {
+        match input {
+            "banned_user" => Some(ExportFormat::BannedUser),
+            "banned_ip" => Some(ExportFormat::BannedIp),
+            "csv" => Some(ExportFormat::Csv),
+            "json" => Some(ExportFormat::Json),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::BannedUser | ExportFormat::BannedIp => "text/plain; charset=utf-8",
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Json => "application/json",
+        }
+    }
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            ExportFormat::BannedUser => "banned_user.cfg",
+            ExportFormat::BannedIp => "banned_ip.cfg",
+            ExportFormat::Csv => "bans.csv",
+            ExportFormat::Json => "bans.json",
+        }
+    }
+
+    fn is_cfg(self) -> bool {
+        matches!(self, ExportFormat::BannedUser | ExportFormat::BannedIp)
+    }
+}
+
+/// Parses a comma separated field list; empty means [`DEFAULT_FIELDS`]
+pub fn parse_fields(input: Option<&str>) -> Result<Vec<&'static str>, String> {
+    let Some(input) = input.map(str::trim).filter(|s| !s.is_empty()) else {
+        return Ok(DEFAULT_FIELDS.to_vec());
+    };
+    input.split(',')
+        .map(str::trim)
+        .map(|f| EXPORT_FIELDS.iter().find(|known| **known == f).copied()
+            .ok_or_else(|| format!("Unknown field '{}' (available: {})", f, EXPORT_FIELDS.join(", "))))
+        .collect()
+}
+
+/// Minutes left on a ban as SourceMod expects them; 0 means permanent
+fn remaining_minutes(expires_at: Option<DateTime<Utc>>, now: DateTime<Utc>) -> i64 {
+    match expires_at {
+        None => 0,
+        Some(t) => ((t - now).num_seconds() + 59).div_euclid(60).max(1),
+    }
+}
+
+fn csv_cell(value: &Value) -> String {
+    let text = match value {
+        Value::Null => return String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text
+    }
+}
+
+fn selected(ban: &Ban, fields: &[&str]) -> Map<String, Value> {
+    let mut all = match serde_json::to_value(ban) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    };
+    fields.iter()
+        .map(|f| (f.to_string(), all.remove(*f).unwrap_or(Value::Null)))
+        .collect()
+}
+
+/// Appends the lines for one ban to `out`
+fn write_row(out: &mut String, ban: &Ban, format: ExportFormat, fields: &[&str], first: bool, now: DateTime<Utc>) {
+    match format {
+        ExportFormat::BannedUser => {
+            if !ban.steam_id.is_empty() {
+                out.push_str(&format!("banid {} {}\n", remaining_minutes(ban.expires_at, now), ban.steam_id));
+            }
+        }
+        ExportFormat::BannedIp => {
+            // addip 只支持单个 IPv4 地址，IP 段与 IPv6 封禁只能留作注释
+            match ban.ip.trim().parse::<Ipv4Addr>() {
+                Ok(ip) => out.push_str(&format!("addip {} {}\n", remaining_minutes(ban.expires_at, now), ip)),
+                Err(_) => out.push_str(&format!("// ban #{}: {} cannot be expressed with addip\n", ban.id, ban.ip)),
+            }
+        }
+        ExportFormat::Csv => {
+            // serde_json 的 Map 按键名排序，按所选字段的顺序取值以对齐表头
+            let row = selected(ban, fields);
+            let cells: Vec<String> = fields.iter().map(|f| csv_cell(&row[*f])).collect();
+            out.push_str(&cells.join(","));
+            out.push_str("\r\n");
+        }
+        ExportFormat::Json => {
+            if !first {
+                out.push(',');
+            }
+            out.push_str(&Value::Object(selected(ban, fields)).to_string());
+        }
+    }
+}
+
+/// Reads matching bans row by row and sends them as chunks; stops quietly when the client is gone
+async fn produce(
+    pool: &MySqlPool,
+    filters: &BanFilters,
+    format: ExportFormat,
+    fields: &[&'static str],
+    tx: &mut mpsc::Sender<Result<String, std::io::Error>>,
+) -> Result<(), sqlx::Error> {
+    let now = Utc::now();
+    let mut out = match format {
+        ExportFormat::BannedUser | ExportFormat::BannedIp => format!("// Exported {} ({})\n", format.file_name(), now.to_rfc3339()),
+        ExportFormat::Csv => format!("{}\r\n", fields.join(",")),
+        ExportFormat::Json => "[".to_string(),
+    };
+
+    let mut qb = QueryBuilder::<MySql>::new("SELECT * FROM bans");
+    filters.push_where(&mut qb);
+    if format.is_cfg() {
+        // 配置文件给无法访问 API 的服务器执行，只导出仍然有效的封禁
+        qb.push(" AND status = 'active' AND (expires_at IS NULL OR expires_at > NOW()) AND ban_type = ")
+            .push_bind(if format == ExportFormat::BannedIp { "ip" } else { "account" });
+    }
+    filters.push_order(&mut qb);
+
+    let mut rows = qb.build_query_as::<Ban>().fetch(pool);
+    let mut first = true;
+    while let Some(ban) = rows.try_next().await? {
+        write_row(&mut out, &ban, format, fields, first, now);
+        first = false;
+        if out.len() >= CHUNK_BYTES && tx.send(Ok(std::mem::take(&mut out))).await.is_err() {
+            return Ok(());
+        }
+    }
+
+    if format == ExportFormat::Json {
+        out.push(']');
+    }
+    let _ = tx.send(Ok(out)).await;
+    Ok(())
+}
+
+/// A response body that streams the export while the query is still running
+pub fn stream(pool: MySqlPool, filters: BanFilters, format: ExportFormat, fields: Vec<&'static str>) -> Body {
+    let (mut tx, rx) = mpsc::channel(8);
+    tokio::spawn(async move {
+        if let Err(e) = produce(&pool, &filters, format, &fields, &mut tx).await {
+            // 响应头已发出，只能中断连接让客户端知道导出不完整
+            tracing::error!("Ban export failed: {}", e);
+            let _ = tx.send(Err(std::io::Error::other(e.to_string()))).await;
+        }
+    });
+    Body::from_stream(rx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn rounds_remaining_time_up_to_whole_minutes() {
+        let now = Utc::now();
+        assert_eq!(remaining_minutes(None, now), 0);
+        assert_eq!(remaining_minutes(Some(now + Duration::seconds(1)), now), 1);
+        assert_eq!(remaining_minutes(Some(now + Duration::seconds(60)), now), 1);
+        assert_eq!(remaining_minutes(Some(now + Duration::seconds(61)), now), 2);
+        assert_eq!(remaining_minutes(Some(now + Duration::hours(1)), now), 60);
+        // 已过期但尚未被标记的封禁不能变成 0（永久）
+        assert_eq!(remaining_minutes(Some(now), now), 1);
+        assert_eq!(remaining_minutes(Some(now - Duration::hours(1)), now), 1);
+    }
+
+    #[test]
+    fn quotes_csv_cells_only_when_needed() {
+        assert_eq!(csv_cell(&Value::Null), "");
+        assert_eq!(csv_cell(&Value::from(42)), "42");
+        assert_eq!(csv_cell(&Value::from(true)), "true");
+        assert_eq!(csv_cell(&Value::from("cheating")), "cheating");
+        assert_eq!(csv_cell(&Value::from("aim, wall")), "\"aim, wall\"");
+        assert_eq!(csv_cell(&Value::from("say \"hi\"")), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_cell(&Value::from("line\nbreak")), "\"line\nbreak\"");
+    }
+
+    #[test]
+    fn parses_selected_fields() {
+        assert_eq!(parse_fields(None), Ok(DEFAULT_FIELDS.to_vec()));
+        assert_eq!(parse_fields(Some("  ")), Ok(DEFAULT_FIELDS.to_vec()));
+        assert_eq!(parse_fields(Some("steam_id, reason,expires_at")), Ok(vec!["steam_id", "reason", "expires_at"]));
+        assert!(parse_fields(Some("steam_id,password")).unwrap_err().starts_with("Unknown field 'password'"));
+        assert!(parse_fields(Some("steam_id,")).is_err());
+    }
+
+    #[test]
+    fn default_fields_are_exportable() {
+        assert!(DEFAULT_FIELDS.iter().all(|f| EXPORT_FIELDS.contains(f)));
+        for format in ["banned_user", "banned_ip", "csv", "json"] {
+            assert!(ExportFormat::parse(format).is_some(), "{}", format);
+        }
+        assert_eq!(ExportFormat::parse("xml"), None);
+    }
+}
@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+use sqlx::{MySql, MySqlPool, QueryBuilder};
+use crate::models::ban::Ban;
+
+/// SQL condition restricting `bans b` to those that apply on server group `?`.
+/// Binding NULL (unknown server) matches every ban so unidentified callers fail closed.
+pub const SCOPE_CONDITION: &str = "(? IS NULL \
+    OR NOT EXISTS (SELECT 1 FROM ban_server_groups s WHERE s.ban_id = b.id) \
+    OR EXISTS (SELECT 1 FROM ban_server_groups s WHERE s.ban_id = b.id AND s.group_id = ?))";
+
+/// Keeps `IN (...)` lists well below MySQL's placeholder limit
+const SCOPE_BATCH: usize = 1000;
+
+/// Loads the scope of the given bans: ban_id -> group ids. Unscoped bans are absent.
+pub async fn load_scopes(pool: &MySqlPool, ban_ids: &[i64]) -> Result<HashMap<i64, Vec<i64>>, sqlx::Error> {
+    let mut scopes: HashMap<i64, Vec<i64>> = HashMap::new();
+    for chunk in ban_ids.chunks(SCOPE_BATCH) {
+        let mut query = QueryBuilder::<MySql>::new("SELECT ban_id, group_id FROM ban_server_groups WHERE ban_id IN (");
+        let mut ids = query.separated(", ");
+        for id in chunk {
+            ids.push_bind(*id);
+        }
+        query.push(") ORDER BY ban_id, group_id");
+
+        let rows = query.build_query_as::<(i64, i64)>().fetch_all(pool).await?;
+        for (ban_id, group_id) in rows {
+            scopes.entry(ban_id).or_default().push(group_id);
+        }
+    }
+    Ok(scopes)
+}
+
+/// Fills `scope_group_ids` on the given bans
+pub async fn attach_scopes(pool: &MySqlPool, bans: &mut [Ban]) -> Result<(), sqlx::Error> {
+    let ids: Vec<i64> = bans.iter().map(|b| b.id).collect();
+    let mut scopes = load_scopes(pool, &ids).await?;
+    for ban in bans.iter_mut() {
+        ban.scope_group_ids = scopes.remove(&ban.id).unwrap_or_default();
+    }
+    Ok(())
+}
+
+/// Replaces the scope of a ban. An empty list makes it global.
+pub async fn set_scope(pool: &MySqlPool, ban_id: i64, group_ids: &[i64]) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM ban_server_groups WHERE ban_id = ?")
+        .bind(ban_id)
+        .execute(&mut *tx)
+        .await?;
+    for group_id in group_ids {
+        sqlx::query("INSERT IGNORE INTO ban_server_groups (ban_id, group_id) VALUES (?, ?)")
+            .bind(ban_id)
+            .bind(group_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await
+}
+
+/// Gives ban `to` the same scope as ban `from` (used for IP-match auto-bans)
+pub async fn copy_scope(pool: &MySqlPool, from: i64, to: i64) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT IGNORE INTO ban_server_groups (ban_id, group_id) SELECT ?, group_id FROM ban_server_groups WHERE ban_id = ?")
+        .bind(to)
+        .bind(from)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Group id of a server, if the server exists
+pub async fn server_group(pool: &MySqlPool, server_id: i64) -> Result<Option<i64>, sqlx::Error> {
+    sqlx::query_scalar("SELECT group_id FROM servers WHERE id = ?")
+        .bind(server_id)
+        .fetch_optional(pool)
+        .await
+}
@@ -0,0 +1,167 @@
+use chrono::{DateTime, Utc};
+use sqlx::{MySql, QueryBuilder};
+use crate::models::ban::BanSearchQuery;
+use crate::services::steam_api::SteamService;
+use crate::utils::duration::parse_time;
+
+pub const DEFAULT_PAGE_SIZE: u32 = 50;
+pub const MAX_PAGE_SIZE: u32 = 200;
+
+/// A SteamID as entered plus the SteamID64 it resolved to, if any
+struct SteamMatch {
+    raw: String,
+    id64: Option<String>,
+}
+
+/// Validated filters of a ban search, ready to be appended to a query
+pub struct BanFilters {
+    status: Option<String>,
+    ban_type: Option<String>,
+    admin_name: Option<String>,
+    server_id: Option<i64>,
+    reason: Option<String>,
+    steam_id: Option<SteamMatch>,
+    player: Option<(String, Option<String>)>,
+    created_from: Option<DateTime<Utc>>,
+    created_to: Option<DateTime<Utc>>,
+    expires_from: Option<DateTime<Utc>>,
+    expires_to: Option<DateTime<Utc>>,
+    order_by: &'static str,
+}
+
+fn non_empty(value: &Option<String>) -> Option<String> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty()).map(str::to_string)
+}
+
+fn time_filter(name: &str, value: &Option<String>) -> Result<Option<DateTime<Utc>>, String> {
+    non_empty(value)
+        .map(|v| parse_time(&v).map_err(|e| format!("Invalid {}: {}", name, e)))
+        .transpose()
+}
+
+/// `LIKE` pattern matching `text` anywhere, with wildcards in `text` taken literally
+fn contains_pattern(text: &str) -> String {
+    let escaped = text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+impl BanFilters {
+    /// Validates the query and resolves SteamIDs (which may need the Steam Web API for vanity names)
+    pub async fn resolve(query: &BanSearchQuery) -> Result<Self, String> {
+        let steam = SteamService::new();
+
+        let steam_id = match non_empty(&query.steam_id) {
+            Some(raw) => {
+                let id64 = steam.resolve_steam_id(&raw).await;
+                Some(SteamMatch { raw, id64 })
+            }
+            None => None,
+        };
+        let player = match non_empty(&query.player) {
+            Some(text) => {
+                let id64 = steam.resolve_steam_id(&text).await;
+                Some((text, id64))
+            }
+            None => None,
+        };
+
+        let column = match query.sort.as_deref().unwrap_or("created_at") {
+            "created_at" => "created_at",
+            "expires_at" => "expires_at",
+            "id" => "id",
+            "name" => "name",
+            other => return Err(format!("Cannot sort by '{}' (use created_at, expires_at, id or name)", other)),
+        };
+        let order_by = match (column, query.order.as_deref().unwrap_or("desc")) {
+            ("created_at", "desc") => "created_at DESC, id DESC",
+            ("created_at", "asc") => "created_at ASC, id ASC",
+            // 永久封禁（expires_at 为 NULL）视为最晚到期
+            ("expires_at", "desc") => "expires_at IS NULL DESC, expires_at DESC, id DESC",
+            ("expires_at", "asc") => "expires_at IS NULL ASC, expires_at ASC, id ASC",
+            ("id", "desc") => "id DESC",
+            ("id", "asc") => "id ASC",
+            ("name", "desc") => "name DESC, id DESC",
+            ("name", "asc") => "name ASC, id ASC",
+            (_, other) => return Err(format!("Invalid order '{}' (use asc or desc)", other)),
+        };
+
+        Ok(BanFilters {
+            status: non_empty(&query.status),
+            ban_type: non_empty(&query.ban_type),
+            admin_name: non_empty(&query.admin_name),
+            server_id: query.server_id,
+            reason: non_empty(&query.reason),
+            steam_id,
+            player,
+            created_from: time_filter("created_from", &query.created_from)?,
+            created_to: time_filter("created_to", &query.created_to)?,
+            expires_from: time_filter("expires_from", &query.expires_from)?,
+            expires_to: time_filter("expires_to", &query.expires_to)?,
+            order_by,
+        })
+    }
+
+    /// Appends ` WHERE ...` (always present, `1=1` without filters)
+    pub fn push_where(&self, qb: &mut QueryBuilder<'_, MySql>) {
+        qb.push(" WHERE 1=1");
+        if let Some(status) = &self.status {
+            qb.push(" AND status = ").push_bind(status.clone());
+        }
+        if let Some(ban_type) = &self.ban_type {
+            qb.push(" AND ban_type = ").push_bind(ban_type.clone());
+        }
+        if let Some(admin_name) = &self.admin_name {
+            qb.push(" AND admin_name = ").push_bind(admin_name.clone());
+        }
+        if let Some(server_id) = self.server_id {
+            qb.push(" AND server_id = ").push_bind(server_id);
+        }
+        if let Some(reason) = &self.reason {
+            qb.push(" AND reason LIKE ").push_bind(contains_pattern(reason));
+        }
+        if let Some(steam) = &self.steam_id {
+            qb.push(" AND (steam_id = ").push_bind(steam.raw.clone());
+            if let Some(id64) = &steam.id64 {
+                qb.push(" OR steam_id_64 = ").push_bind(id64.clone());
+            }
+            qb.push(")");
+        }
+        if let Some((text, id64)) = &self.player {
+            qb.push(" AND (name LIKE ").push_bind(contains_pattern(text));
+            qb.push(" OR steam_id = ").push_bind(text.clone());
+            if let Some(id64) = id64 {
+                qb.push(" OR steam_id_64 = ").push_bind(id64.clone());
+            }
+            qb.push(")");
+        }
+        if let Some(t) = self.created_from {
+            qb.push(" AND created_at >= ").push_bind(t);
+        }
+        if let Some(t) = self.created_to {
+            qb.push(" AND created_at < ").push_bind(t);
+        }
+        if let Some(t) = self.expires_from {
+            qb.push(" AND expires_at >= ").push_bind(t);
+        }
+        if let Some(t) = self.expires_to {
+            qb.push(" AND expires_at < ").push_bind(t);
+        }
+    }
+
+    pub fn push_order(&self, qb: &mut QueryBuilder<'_, MySql>) {
+        qb.push(" ORDER BY ").push(self.order_by);
+    }
+}
+
+/// Page number and size from a query, clamped to sane values
+pub fn page(page: Option<u32>, page_size: Option<u32>) -> (u32, u32) {
+    let page = page.unwrap_or(1).max(1);
+    let page_size = page_size.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    (page, page_size)
+}
+
+/// Appends `LIMIT ? OFFSET ?` for a page from [`page`]
+pub fn push_page(qb: &mut QueryBuilder<'_, MySql>, page: u32, page_size: u32) {
+    let offset = u64::from(page - 1) * u64::from(page_size);
+    qb.push(" LIMIT ").push_bind(page_size).push(" OFFSET ").push_bind(offset);
+}
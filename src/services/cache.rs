@@ -0,0 +1,219 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Small key/value store used for sessions and other short-lived data.
+/// Backed by Redis when `REDIS_URL` is reachable, otherwise by process memory
+/// (state is then lost on restart and not shared between instances).
+pub enum Cache {
+    Redis(redis::aio::MultiplexedConnection),
+    Memory(Mutex<HashMap<String, Entry>>),
+}
+
+pub struct Entry {
+    value: Value,
+    expires_at: Option<Instant>,
+}
+
+enum Value {
+    Str(String),
+    Set(HashSet<String>),
+}
+
+impl Entry {
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|t| t <= now)
+    }
+}
+
+impl Cache {
+    pub async fn connect() -> Self {
+        let url = match std::env::var("REDIS_URL") {
+            Ok(url) if !url.is_empty() => url,
+            _ => {
+                tracing::warn!("REDIS_URL not set, using in-memory cache");
+                return Cache::memory();
+            }
+        };
+
+        let conn = match redis::Client::open(url.as_str()) {
+            Ok(client) => client.get_multiplexed_async_connection().await,
+            Err(e) => Err(e),
+        };
+
+        match conn {
+            Ok(conn) => {
+                tracing::info!("Connected to Redis");
+                Cache::Redis(conn)
+            }
+            Err(e) => {
+                tracing::warn!("Failed to connect to Redis ({}), using in-memory cache", e);
+                Cache::memory()
+            }
+        }
+    }
+
+    pub fn memory() -> Self {
+        Cache::Memory(Mutex::new(HashMap::new()))
+    }
+
+    pub async fn set(&self, key: &str, value: &str, ttl: Duration) -> anyhow::Result<()> {
+        match self {
+            Cache::Redis(conn) => {
+                redis::cmd("SET").arg(key).arg(value).arg("EX").arg(ttl.as_secs().max(1))
+                    .query_async::<()>(&mut conn.clone())
+                    .await?;
+            }
+            Cache::Memory(map) => {
+                let mut map = map.lock().unwrap();
+                let now = Instant::now();
+                map.retain(|_, e| !e.is_expired(now));
+                map.insert(key.to_string(), Entry {
+                    value: Value::Str(value.to_string()),
+                    expires_at: Some(now + ttl),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn get(&self, key: &str) -> anyhow::Result<Option<String>> {
+        match self {
+            Cache::Redis(conn) => {
+                Ok(redis::cmd("GET").arg(key).query_async::<Option<String>>(&mut conn.clone()).await?)
+            }
+            Cache::Memory(map) => {
+                let map = map.lock().unwrap();
+                Ok(match map.get(key) {
+                    Some(e) if !e.is_expired(Instant::now()) => match &e.value {
+                        Value::Str(s) => Some(s.clone()),
+                        Value::Set(_) => None,
+                    },
+                    _ => None,
+                })
+            }
+        }
+    }
+
+    /// Increments a counter; the TTL starts with the first increment
+    pub async fn incr(&self, key: &str, ttl: Duration) -> anyhow::Result<i64> {
+        match self {
+            Cache::Redis(conn) => {
+                let mut conn = conn.clone();
+                let count = redis::cmd("INCR").arg(key).query_async::<i64>(&mut conn).await?;
+                if count == 1 {
+                    redis::cmd("EXPIRE").arg(key).arg(ttl.as_secs().max(1)).query_async::<()>(&mut conn).await?;
+                }
+                Ok(count)
+            }
+            Cache::Memory(map) => {
+                let mut map = map.lock().unwrap();
+                let now = Instant::now();
+                let entry = map.entry(key.to_string()).or_insert_with(|| Entry {
+                    value: Value::Str("0".to_string()),
+                    expires_at: Some(now + ttl),
+                });
+                if entry.is_expired(now) || !matches!(entry.value, Value::Str(_)) {
+                    entry.value = Value::Str("0".to_string());
+                    entry.expires_at = Some(now + ttl);
+                }
+                let count = match &entry.value {
+                    Value::Str(s) => s.parse::<i64>().unwrap_or(0) + 1,
+                    Value::Set(_) => 1,
+                };
+                entry.value = Value::Str(count.to_string());
+                Ok(count)
+            }
+        }
+    }
+
+    /// Atomically reads and removes a value
+    pub async fn take(&self, key: &str) -> anyhow::Result<Option<String>> {
+        match self {
+            Cache::Redis(conn) => {
+                Ok(redis::cmd("GETDEL").arg(key).query_async::<Option<String>>(&mut conn.clone()).await?)
+            }
+            Cache::Memory(map) => {
+                let mut map = map.lock().unwrap();
+                Ok(match map.remove(key) {
+                    Some(e) if !e.is_expired(Instant::now()) => match e.value {
+                        Value::Str(s) => Some(s),
+                        Value::Set(_) => None,
+                    },
+                    _ => None,
+                })
+            }
+        }
+    }
+
+    pub async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        match self {
+            Cache::Redis(conn) => {
+                redis::cmd("DEL").arg(key).query_async::<()>(&mut conn.clone()).await?;
+            }
+            Cache::Memory(map) => {
+                map.lock().unwrap().remove(key);
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds a member to a set and (re)sets the TTL of the whole set
+    pub async fn set_add(&self, key: &str, member: &str, ttl: Duration) -> anyhow::Result<()> {
+        match self {
+            Cache::Redis(conn) => {
+                let mut conn = conn.clone();
+                redis::cmd("SADD").arg(key).arg(member).query_async::<()>(&mut conn).await?;
+                redis::cmd("EXPIRE").arg(key).arg(ttl.as_secs().max(1)).query_async::<()>(&mut conn).await?;
+            }
+            Cache::Memory(map) => {
+                let mut map = map.lock().unwrap();
+                let now = Instant::now();
+                let entry = map.entry(key.to_string()).or_insert_with(|| Entry {
+                    value: Value::Set(HashSet::new()),
+                    expires_at: None,
+                });
+                if entry.is_expired(now) || !matches!(entry.value, Value::Set(_)) {
+                    entry.value = Value::Set(HashSet::new());
+                }
+                if let Value::Set(set) = &mut entry.value {
+                    set.insert(member.to_string());
+                }
+                entry.expires_at = Some(now + ttl);
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn set_remove(&self, key: &str, member: &str) -> anyhow::Result<()> {
+        match self {
+            Cache::Redis(conn) => {
+                redis::cmd("SREM").arg(key).arg(member).query_async::<()>(&mut conn.clone()).await?;
+            }
+            Cache::Memory(map) => {
+                if let Some(Entry { value: Value::Set(set), .. }) = map.lock().unwrap().get_mut(key) {
+                    set.remove(member);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn set_members(&self, key: &str) -> anyhow::Result<Vec<String>> {
+        match self {
+            Cache::Redis(conn) => {
+                Ok(redis::cmd("SMEMBERS").arg(key).query_async::<Vec<String>>(&mut conn.clone()).await?)
+            }
+            Cache::Memory(map) => {
+                let map = map.lock().unwrap();
+                Ok(match map.get(key) {
+                    Some(e) if !e.is_expired(Instant::now()) => match &e.value {
+                        Value::Set(set) => set.iter().cloned().collect(),
+                        Value::Str(_) => Vec::new(),
+                    },
+                    _ => Vec::new(),
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use std::sync::Arc;
+use futures::future::join_all;
+use regex::Regex;
+use crate::AppState;
+use crate::handlers::server::Player;
+use crate::models::ban::Ban;
+use crate::models::comm::CommBlock;
+use crate::models::server::Server;
+use crate::services::steam_api::SteamService;
+use crate::services::{ban_scope, revision};
+use crate::utils::rcon::send_command;
+
+/// Sends `sm_unban` for the ban's SteamID and IP to every server (fire-and-forget)
+pub fn queue_rcon_unban(state: &Arc<AppState>, ban: &Ban) {
+    let state = state.clone();
+    let steam_id = ban.steam_id.clone();
+    let ip = ban.ip.clone();
+    let ban_name = ban.name.clone();
+
+    tokio::spawn(async move {
+        let servers = match sqlx::query_as::<_, Server>("SELECT * FROM servers").fetch_all(&state.db).await {
+            Ok(s) => s,
+            Err(e) => {
+                tracing::error!("Failed to load servers for unban of {}: {}", ban_name, e);
+                return;
+            }
+        };
+
+        tracing::debug!("Background task: Sending unban commands to {} servers for {}", servers.len(), ban_name);
+
+        let tasks: Vec<_> = servers.into_iter().map(|server| {
+            let steam_id = steam_id.clone();
+            let ip = ip.clone();
+            async move {
+                let address = format!("{}:{}", server.ip, server.port);
+                let pwd = server.rcon_password.unwrap_or_default();
+
+                // Unban SteamID
+                if !steam_id.is_empty() {
+                    let cmd = format!("sm_unban \"{}\"", steam_id);
+                    let _ = send_command(&address, &pwd, &cmd).await;
+                }
+
+                // Unban IP
+                if !ip.is_empty() {
+                    let cmd = format!("sm_unban \"{}\"", ip);
+                    let _ = send_command(&address, &pwd, &cmd).await;
+                }
+            }
+        }).collect();
+
+        join_all(tasks).await;
+    });
+}
+
+/// Marks an active ban as unbanned, recording who lifted it and why, and lifts it on the servers.
+/// Returns false if the ban was not active.
+pub async fn unban(state: &Arc<AppState>, ban: &Ban, actor: &str, reason: &str) -> Result<bool, sqlx::Error> {
+    let before = revision::snapshot(&state.db, ban.id).await?;
+    let result = sqlx::query(
+        "UPDATE bans SET status = 'unbanned', unbanned_by = ?, unbanned_at = NOW(), unban_reason = ? WHERE id = ? AND status = 'active'"
+    )
+    .bind(actor)
+    .bind(reason)
+    .bind(ban.id)
+    .execute(&state.db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+    if let Err(e) = revision::record(&state.db, ban.id, before.as_ref(), "unban", actor, Some(reason)).await {
+        tracing::error!("Failed to record unban of ban {}: {}", ban.id, e);
+    }
+    queue_rcon_unban(state, ban);
+    Ok(true)
+}
+
+/// Account a linked-account ban is created for
+pub struct LinkedTarget<'a> {
+    pub name: &'a str,
+    pub steam_id: &'a str,
+    pub steam_id_64: Option<&'a str>,
+    pub ip: &'a str,
+    pub server_id: Option<i64>,
+}
+
+/// Creates an account ban for an account linked to `parent` (same IP or identity graph).
+/// It inherits the parent's duration, expiry and scope, and does not extend further itself.
+pub async fn ban_linked_account(state: &AppState, parent: &Ban, target: &LinkedTarget<'_>, reason: &str, actor: &str) -> Result<Ban, sqlx::Error> {
+    let res = sqlx::query(
+        "INSERT INTO bans (name, steam_id, steam_id_64, ip, ban_type, reason, duration, admin_name, expires_at, created_at, status, server_id, group_id, linked_from) VALUES (?, ?, ?, ?, 'account', ?, ?, ?, ?, NOW(), 'active', ?, ?, ?)"
+    )
+    .bind(target.name)
+    .bind(target.steam_id)
+    .bind(target.steam_id_64)
+    .bind(target.ip)
+    .bind(reason)
+    .bind(&parent.duration)
+    .bind(actor)
+    .bind(parent.expires_at)
+    .bind(target.server_id)
+    .bind(parent.group_id)
+    .bind(parent.id)
+    .execute(&state.db)
+    .await?;
+
+    let id = res.last_insert_id() as i64;
+    if let Err(e) = ban_scope::copy_scope(&state.db, parent.id, id).await {
+        tracing::error!("Failed to copy scope from ban {} to linked ban {}: {}", parent.id, id, e);
+    }
+    revision::track(&state.db, id, None, "create", actor).await;
+
+    let mut ban = sqlx::query_as::<_, Ban>("SELECT * FROM bans WHERE id = ?")
+        .bind(id)
+        .fetch_one(&state.db)
+        .await?;
+    ban.scope_group_ids = sqlx::query_scalar("SELECT group_id FROM ban_server_groups WHERE ban_id = ?")
+        .bind(id)
+        .fetch_all(&state.db)
+        .await?;
+    Ok(ban)
+}
+
+/// Players currently on a server: the plugin's last report if fresh, otherwise RCON `status`
+pub async fn online_players(state: &AppState, server: &Server) -> Result<Vec<Player>, String> {
+    // 插件最近上报过玩家列表时直接使用，不再走 RCON
+    if let Ok(Some(raw)) = state.cache.get(&crate::handlers::plugin::player_report_key(server.id)).await {
+        if let Ok(players) = serde_json::from_str::<Vec<Player>>(&raw) {
+            return Ok(players);
+        }
+    }
+
+    let address = format!("{}:{}", server.ip, server.port);
+    let pwd = server.rcon_password.clone().unwrap_or_default();
+    let output = send_command(&address, &pwd, "status").await?;
+    tracing::info!("RCON 'status' output: \n{}", output); // Debug log
+
+    // Output format: # userid slot "name" steamid time ping ...
+    let re = Regex::new(r#"#\s+(\d+)\s+\d+\s+"(.+?)"\s+(\S+)\s+(\S+)\s+(\d+)"#).unwrap();
+    Ok(re.captures_iter(&output).map(|cap| Player {
+        userid: cap[1].parse::<i32>().unwrap_or(-1),
+        name: cap[2].to_string(),
+        steam_id: cap[3].to_string(),
+        time: cap[4].to_string(),
+        ping: cap[5].parse::<i32>().unwrap_or(0),
+    }).collect())
+}
+
+/// Finds the server a player is connected to, searching only `servers`
+async fn locate_player(state: &AppState, servers: Vec<Server>, steam_id_64: &str) -> Option<(Server, Player)> {
+    let steam_service = SteamService::new();
+    for server in servers {
+        let players = match online_players(state, &server).await {
+            Ok(p) => p,
+            Err(e) => {
+                tracing::debug!("Skipping server {} while locating {}: {}", server.name, steam_id_64, e);
+                continue;
+            }
+        };
+        for player in players {
+            // 只转换 SteamID 格式，避免把 BOT 等名称当作自定义 URL 去请求 Steam API
+            let id = player.steam_id.as_str();
+            if !(id.starts_with("STEAM_") || id.starts_with("[U:") || id.starts_with("7656")) {
+                continue;
+            }
+            if steam_service.resolve_steam_id(id).await.as_deref() == Some(steam_id_64) {
+                return Some((server, player));
+            }
+        }
+    }
+    None
+}
+
+/// Applies (`sm_mute`/`sm_gag`/`sm_silence`) or lifts a comm block on the server the
+/// player is currently connected to. Offline players pick it up from the plugin check.
+pub fn queue_rcon_comm(state: &Arc<AppState>, block: &CommBlock, apply: bool) {
+    let Some(command) = block.rcon_command(apply) else {
+        return;
+    };
+    let state = state.clone();
+    let steam_id_64 = block.steam_id_64.clone().unwrap_or_default();
+    let group_id = block.group_id;
+
+    tokio::spawn(async move {
+        let servers = sqlx::query_as::<_, Server>("SELECT * FROM servers WHERE (? IS NULL OR group_id = ?)")
+            .bind(group_id)
+            .bind(group_id)
+            .fetch_all(&state.db)
+            .await;
+        let servers = match servers {
+            Ok(s) => s,
+            Err(e) => {
+                tracing::error!("Failed to load servers for {}: {}", command, e);
+                return;
+            }
+        };
+
+        match locate_player(&state, servers, &steam_id_64).await {
+            Some((server, player)) => {
+                let address = format!("{}:{}", server.ip, server.port);
+                let pwd = server.rcon_password.unwrap_or_default();
+                let cmd = format!("{} #{}", command, player.userid);
+                if let Err(e) = send_command(&address, &pwd, &cmd).await {
+                    tracing::warn!("Failed to run '{}' on {}: {}", cmd, server.name, e);
+                }
+            }
+            None => tracing::debug!("{} not online, skipping {}", steam_id_64, command),
+        }
+    });
+}
@@ -0,0 +1,107 @@
+use sqlx::MySqlPool;
+use crate::models::escalation::{BanCategory, EscalationProposal};
+use crate::utils::calculate_expires_at;
+
+/// Fills `steps` on the given categories
+pub async fn attach_steps(pool: &MySqlPool, categories: &mut [BanCategory]) -> Result<(), sqlx::Error> {
+    let rows = sqlx::query_as::<_, (i64, String)>("SELECT category_id, duration FROM ban_category_steps ORDER BY category_id, step")
+        .fetch_all(pool)
+        .await?;
+    for category in categories.iter_mut() {
+        category.steps = rows.iter()
+            .filter(|(id, _)| *id == category.id)
+            .map(|(_, duration)| duration.clone())
+            .collect();
+    }
+    Ok(())
+}
+
+pub async fn load_category(pool: &MySqlPool, id: i64) -> Result<Option<BanCategory>, sqlx::Error> {
+    let Some(category) = sqlx::query_as::<_, BanCategory>("SELECT * FROM ban_categories WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+    else {
+        return Ok(None);
+    };
+    let mut categories = [category];
+    attach_steps(pool, &mut categories).await?;
+    let [category] = categories;
+    Ok(Some(category))
+}
+
+/// Replaces the ladder of a category inside the caller's transaction
+pub async fn set_steps(tx: &mut sqlx::Transaction<'_, sqlx::MySql>, category_id: i64, steps: &[String]) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM ban_category_steps WHERE category_id = ?")
+        .bind(category_id)
+        .execute(&mut **tx)
+        .await?;
+    for (i, duration) in steps.iter().enumerate() {
+        sqlx::query("INSERT INTO ban_category_steps (category_id, step, duration) VALUES (?, ?, ?)")
+            .bind(category_id)
+            .bind(i as i32 + 1)
+            .bind(duration)
+            .execute(&mut **tx)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Counts the player's earlier bans in the category. Bans that were lifted
+/// (e.g. after an accepted appeal) do not count as offences.
+pub async fn prior_offences(pool: &MySqlPool, category: &BanCategory, steam_id_64: &str) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT COUNT(*) FROM bans WHERE category_id = ? AND steam_id_64 = ? AND status <> 'unbanned' \
+         AND (? IS NULL OR created_at >= NOW() - INTERVAL ? DAY)"
+    )
+    .bind(category.id)
+    .bind(steam_id_64)
+    .bind(category.lookback_days)
+    .bind(category.lookback_days)
+    .fetch_one(pool)
+    .await
+}
+
+/// Proposes the next ladder step for the player
+pub async fn propose(pool: &MySqlPool, category: &BanCategory, steam_id_64: &str) -> Result<Option<EscalationProposal>, sqlx::Error> {
+    if category.steps.is_empty() {
+        return Ok(None);
+    }
+    let prior = prior_offences(pool, category, steam_id_64).await?;
+    let index = (prior as usize).min(category.steps.len() - 1);
+    let duration = category.steps[index].clone();
+
+    Ok(Some(EscalationProposal {
+        category_id: category.id,
+        category_name: category.name.clone(),
+        prior_offences: prior,
+        step: index as i32 + 1,
+        // 阶梯时长在保存时已校验，这里失败只可能是 Until 时间已过
+        expires_at: calculate_expires_at(&duration).ok().flatten(),
+        duration,
+    }))
+}
+
+/// Duration chosen for a new ban in a category
+pub struct LadderChoice {
+    pub category_name: String,
+    pub duration: String,
+    /// Ladder step applied; None when the admin overrode the ladder with another duration
+    pub step: Option<i32>,
+}
+
+/// An empty `requested` duration takes the proposed ladder step. An explicit duration is
+/// kept and only recorded as the step if it matches. `Ok(None)` means unknown category.
+pub async fn choose(pool: &MySqlPool, category_id: i64, steam_id_64: &str, requested: &str) -> Result<Option<LadderChoice>, sqlx::Error> {
+    let Some(category) = load_category(pool, category_id).await? else {
+        return Ok(None);
+    };
+    let proposal = propose(pool, &category, steam_id_64).await?;
+    let requested = requested.trim();
+
+    let (duration, step) = match proposal {
+        Some(p) if requested.is_empty() || requested == p.duration => (p.duration, Some(p.step)),
+        _ => (requested.to_string(), None),
+    };
+    Ok(Some(LadderChoice { category_name: category.name, duration, step }))
+}
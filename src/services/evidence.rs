@@ -0,0 +1,176 @@
+use axum::extract::multipart::Field;
+use sha2::{Digest, Sha256};
+use sqlx::MySqlPool;
+use std::fmt;
+use std::path::PathBuf;
+use tokio::io::AsyncWriteExt;
+
+/// Allowed evidence files by extension. The client's content type is ignored.
+const ALLOWED_TYPES: &[(&str, &str)] = &[
+    ("dem", "application/octet-stream"),
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("txt", "text/plain; charset=utf-8"),
+    ("log", "text/plain; charset=utf-8"),
+];
+
+#[derive(Debug)]
+pub enum EvidenceError {
+    TooLarge(u64),
+    Empty,
+    /// Extension not allowed or content does not match it
+    Rejected(String),
+    Io(std::io::Error),
+}
+
+impl fmt::Display for EvidenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvidenceError::TooLarge(max) => write!(f, "File exceeds the {} MB limit", max / 1024 / 1024),
+            EvidenceError::Empty => write!(f, "File is empty"),
+            EvidenceError::Rejected(msg) => write!(f, "{}", msg),
+            EvidenceError::Io(e) => write!(f, "Failed to store file: {}", e),
+        }
+    }
+}
+
+impl From<std::io::Error> for EvidenceError {
+    fn from(e: std::io::Error) -> Self {
+        EvidenceError::Io(e)
+    }
+}
+
+pub struct StoredFile {
+    pub sha256: String,
+    pub size: u64,
+}
+
+/// Storage root, configured with EVIDENCE_DIR (default `data/evidence`)
+pub fn evidence_dir() -> PathBuf {
+    std::env::var("EVIDENCE_DIR")
+        .ok()
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("data/evidence"))
+}
+
+/// Per-file size limit, configured with EVIDENCE_MAX_MB (default 100 MB, demos are large)
+pub fn max_bytes() -> u64 {
+    std::env::var("EVIDENCE_MAX_MB")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|mb| *mb > 0)
+        .unwrap_or(100)
+        * 1024
+        * 1024
+}
+
+/// Files are content-addressed: identical uploads share one file on disk
+pub fn path_for(sha256: &str) -> PathBuf {
+    evidence_dir().join(&sha256[..2]).join(sha256)
+}
+
+pub fn content_type_for(file_name: &str) -> Option<&'static str> {
+    let ext = file_name.rsplit_once('.')?.1.to_ascii_lowercase();
+    ALLOWED_TYPES.iter().find(|(e, _)| *e == ext).map(|(_, t)| *t)
+}
+
+/// Checks the first bytes against the file type so e.g. an HTML page can't be served as a PNG
+fn matches_signature(content_type: &str, head: &[u8]) -> bool {
+    match content_type {
+        "image/png" => head.starts_with(b"\x89PNG\r\n\x1a\n"),
+        "image/jpeg" => head.starts_with(b"\xff\xd8\xff"),
+        "image/gif" => head.starts_with(b"GIF87a") || head.starts_with(b"GIF89a"),
+        "image/webp" => head.len() >= 12 && head.starts_with(b"RIFF") && &head[8..12] == b"WEBP",
+        // Source 引擎 demo 文件头
+        "application/octet-stream" => head.starts_with(b"HL2DEMO\0"),
+        _ => !head.contains(&0),
+    }
+}
+
+/// Keeps only the base name and drops characters that would break headers or paths
+pub fn sanitize_file_name(name: &str) -> String {
+    let base = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    let cleaned: String = base.chars()
+        .filter(|c| !c.is_control() && *c != '"')
+        .take(255)
+        .collect();
+    cleaned.trim().to_string()
+}
+
+/// Streams an uploaded file to disk while hashing it and enforcing the size limit
+pub async fn store(mut field: Field<'_>, content_type: &str) -> Result<StoredFile, EvidenceError> {
+    let max = max_bytes();
+    let tmp_dir = evidence_dir().join("tmp");
+    tokio::fs::create_dir_all(&tmp_dir).await?;
+    let tmp_path = tmp_dir.join(format!("{}.part", uuid::Uuid::new_v4()));
+
+    let result: Result<StoredFile, EvidenceError> = async {
+        let mut file = tokio::fs::File::create(&tmp_path).await?;
+        let mut hasher = Sha256::new();
+        let mut size: u64 = 0;
+
+        while let Some(chunk) = field.chunk().await.map_err(|e| EvidenceError::Rejected(e.body_text()))? {
+            if size == 0 && !chunk.is_empty() && !matches_signature(content_type, &chunk) {
+                return Err(EvidenceError::Rejected("File content does not match its extension".to_string()));
+            }
+            size += chunk.len() as u64;
+            if size > max {
+                return Err(EvidenceError::TooLarge(max));
+            }
+            hasher.update(&chunk);
+            file.write_all(&chunk).await?;
+        }
+        file.flush().await?;
+
+        if size == 0 {
+            return Err(EvidenceError::Empty);
+        }
+        Ok(StoredFile { sha256: hex::encode(hasher.finalize()), size })
+    }.await;
+
+    let stored = match result {
+        Ok(s) => s,
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&tmp_path).await;
+            return Err(e);
+        }
+    };
+
+    let final_path = path_for(&stored.sha256);
+    if tokio::fs::try_exists(&final_path).await.unwrap_or(false) {
+        let _ = tokio::fs::remove_file(&tmp_path).await;
+    } else {
+        if let Some(parent) = final_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::rename(&tmp_path, &final_path).await?;
+    }
+    Ok(stored)
+}
+
+/// Deletes files no evidence row refers to any more
+pub async fn remove_unused(pool: &MySqlPool, hashes: &[String]) {
+    for sha256 in hashes {
+        let in_use: Result<i64, sqlx::Error> = sqlx::query_scalar("SELECT COUNT(*) FROM ban_evidence WHERE sha256 = ?")
+            .bind(sha256)
+            .fetch_one(pool)
+            .await;
+        if let Ok(0) = in_use {
+            if let Err(e) = tokio::fs::remove_file(path_for(sha256)).await {
+                tracing::warn!("Failed to remove evidence file {}: {}", sha256, e);
+            }
+        }
+    }
+}
+
+pub async fn hashes_for_ban(pool: &MySqlPool, ban_id: i64) -> Vec<String> {
+    sqlx::query_scalar("SELECT DISTINCT sha256 FROM ban_evidence WHERE ban_id = ?")
+        .bind(ban_id)
+        .fetch_all(pool)
+        .await
+        .unwrap_or_default()
+}
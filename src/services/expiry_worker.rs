@@ -0,0 +1,162 @@
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::Utc;
+use crate::AppState;
+use crate::models::ban::Ban;
+use crate::models::comm::CommBlock;
+use crate::services::{enforcement, revision};
+use crate::utils::log_admin_action;
+
+/// Upper bound on how long the worker sleeps, so bans created meanwhile are picked up
+const MAX_SLEEP: Duration = Duration::from_secs(30);
+/// Lower bound, so a row that keeps failing to expire cannot make the loop spin
+const MIN_SLEEP: Duration = Duration::from_secs(1);
+const ACTOR: &str = "System (Expiry)";
+
+/// Expires bans and comm blocks when their `expires_at` passes.
+/// Set EXPIRY_RCON_UNBAN=true to also send `sm_unban` to every server for expired bans.
+pub async fn start_expiry_worker(state: Arc<AppState>) {
+    tracing::info!("Expiry Worker started.");
+    let rcon_unban = std::env::var("EXPIRY_RCON_UNBAN").map(|v| v == "true").unwrap_or(false);
+
+    let mut failures = 0u32;
+    loop {
+        let mut failed = false;
+        if let Err(e) = expire_bans(&state, rcon_unban).await {
+            tracing::error!("Expiry Worker: failed to expire bans: {}", e);
+            failed = true;
+        }
+        if let Err(e) = expire_comm_blocks(&state).await {
+            tracing::error!("Expiry Worker: failed to expire comm blocks: {}", e);
+            failed = true;
+        }
+        failures = if failed { failures.saturating_add(1) } else { 0 };
+
+        tokio::time::sleep(sleep_for(next_due(&state).await, Utc::now(), failures)).await;
+    }
+}
+
+/// Sleeps until the next ban or comm block is due, within MIN_SLEEP..=MAX_SLEEP.
+/// After consecutive failures it backs off exponentially (2s, 4s, ... up to MAX_SLEEP).
+fn sleep_for(next_due: Option<chrono::DateTime<Utc>>, now: chrono::DateTime<Utc>, failures: u32) -> Duration {
+    let until_due = match next_due {
+        Some(t) => (t - now).to_std().unwrap_or(Duration::ZERO).min(MAX_SLEEP),
+        None => MAX_SLEEP,
+    };
+    let backoff = MIN_SLEEP.saturating_mul(2u32.saturating_pow(failures)).min(MAX_SLEEP);
+    until_due.max(backoff)
+}
+
+/// When the next active ban or comm block expires
+async fn next_due(state: &AppState) -> Option<chrono::DateTime<Utc>> {
+    sqlx::query_scalar(
+        "SELECT MIN(t) FROM ( \
+            SELECT MIN(expires_at) AS t FROM bans WHERE status = 'active' AND expires_at IS NOT NULL \
+            UNION ALL \
+            SELECT MIN(expires_at) AS t FROM comm_blocks WHERE status = 'active' AND expires_at IS NOT NULL \
+         ) due"
+    )
+    .fetch_one(&state.db)
+    .await
+    .unwrap_or(None)
+}
+
+async fn expire_bans(state: &Arc<AppState>, rcon_unban: bool) -> Result<(), sqlx::Error> {
+    let due = sqlx::query_as::<_, Ban>("SELECT * FROM bans WHERE status = 'active' AND expires_at <= NOW()")
+        .fetch_all(&state.db)
+        .await?;
+
+    for ban in due {
+        let before = revision::snapshot(&state.db, ban.id).await?;
+        // 仅处理本次真正由 active 变为 expired 的封禁，避免多实例重复触发
+        let result = sqlx::query("UPDATE bans SET status = 'expired' WHERE id = ? AND status = 'active'")
+            .bind(ban.id)
+            .execute(&state.db)
+            .await?;
+        if result.rows_affected() == 0 {
+            continue;
+        }
+        revision::track(&state.db, ban.id, before.as_ref(), "expire", ACTOR).await;
+        on_ban_expired(state, &ban, rcon_unban).await;
+    }
+    Ok(())
+}
+
+/// The "ban expired" event: recorded in the audit log and optionally lifted on the servers
+async fn on_ban_expired(state: &Arc<AppState>, ban: &Ban, rcon_unban: bool) {
+    tracing::info!("Ban {} for {} ({}) expired", ban.id, ban.name, ban.steam_id);
+    if rcon_unban {
+        enforcement::queue_rcon_unban(state, ban);
+    }
+    let _ = log_admin_action(
+        &state.db,
+        ACTOR,
+        "ban_expired",
+        &format!("BanID: {}, Target: {} ({})", ban.id, ban.name, ban.steam_id),
+        &format!(
+            "Expired at {}{}",
+            ban.expires_at.map(|t| t.to_rfc3339()).unwrap_or_default(),
+            if rcon_unban { " (Unban commands queued)" } else { "" }
+        )
+    ).await;
+}
+
+async fn expire_comm_blocks(state: &Arc<AppState>) -> Result<(), sqlx::Error> {
+    let due = sqlx::query_as::<_, CommBlock>("SELECT * FROM comm_blocks WHERE status = 'active' AND expires_at <= NOW()")
+        .fetch_all(&state.db)
+        .await?;
+
+    for block in due {
+        let result = sqlx::query("UPDATE comm_blocks SET status = 'expired' WHERE id = ? AND status = 'active'")
+            .bind(block.id)
+            .execute(&state.db)
+            .await?;
+        if result.rows_affected() == 0 {
+            continue;
+        }
+        // 玩家仍在线时解除本局的禁言
+        enforcement::queue_rcon_comm(state, &block, false);
+        let _ = log_admin_action(
+            &state.db,
+            ACTOR,
+            "comm_expired",
+            &format!("CommID: {}, Target: {} ({})", block.id, block.name, block.steam_id),
+            &format!("{} block expired", block.block_type)
+        ).await;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sleeps_until_due_within_bounds() {
+        let now = Utc::now();
+        let secs = |n: i64| Some(now + chrono::Duration::seconds(n));
+        let cases = [
+            (None, 0, MAX_SLEEP),
+            (secs(10), 0, Duration::from_secs(10)),
+            (secs(3600), 0, MAX_SLEEP),
+            (secs(0), 0, MIN_SLEEP),
+            (secs(-60), 0, MIN_SLEEP),
+        ];
+        for (due, failures, expected) in cases {
+            assert_eq!(sleep_for(due, now, failures), expected, "{:?}", due);
+        }
+    }
+
+    #[test]
+    fn backs_off_after_failures() {
+        let now = Utc::now();
+        let overdue = Some(now - chrono::Duration::seconds(5));
+        let expected = [1, 2, 4, 8, 16, 30, 30];
+        for (failures, secs) in expected.into_iter().enumerate() {
+            assert_eq!(sleep_for(overdue, now, failures as u32), Duration::from_secs(secs), "{} failures", failures);
+        }
+        assert_eq!(sleep_for(overdue, now, u32::MAX), MAX_SLEEP);
+        // A later due time still wins over a short backoff
+        assert_eq!(sleep_for(Some(now + chrono::Duration::seconds(20)), now, 1), Duration::from_secs(20));
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use sqlx::MySqlPool;
+use crate::models::ban::Ban;
+use crate::models::identity::{LinkedAccount, SharedIpRow};
+use crate::services::ban_scope;
+use crate::utils::ip_range;
+
+/// Weight of one shared IP when only two accounts were ever seen on it
+const SHARED_IP_WEIGHT: f64 = 0.7;
+
+/// Minimum confidence for a linked account to inherit a ban, configured with
+/// ALT_LINK_MIN_CONFIDENCE (default 0.7, i.e. one IP shared by exactly two accounts)
+pub fn min_link_confidence() -> f64 {
+    std::env::var("ALT_LINK_MIN_CONFIDENCE")
+        .ok()
+        .and_then(|v| v.parse::<f64>().ok())
+        .filter(|c| (0.0..=1.0).contains(c))
+        .unwrap_or(SHARED_IP_WEIGHT)
+}
+
+/// Records that `steam_id_64` was seen on `ip`. Failures are only logged.
+pub async fn observe(pool: &MySqlPool, steam_id_64: &str, ip: &str, name: Option<&str>, source: &str) {
+    let Some(addr) = ip_range::parse_player_ip(ip) else {
+        return;
+    };
+    if steam_id_64.is_empty() || addr.is_unspecified() || addr.is_loopback() {
+        return;
+    }
+
+    // seen_count 必须在 last_seen 之前更新，MySQL 按顺序求值
+    let result = sqlx::query(
+        "INSERT INTO player_identities (steam_id_64, ip, last_name, source) VALUES (?, ?, ?, ?) \
+         ON DUPLICATE KEY UPDATE \
+            seen_count = seen_count + IF(last_seen < NOW() - INTERVAL 1 HOUR, 1, 0), \
+            last_seen = NOW(), \
+            last_name = COALESCE(VALUES(last_name), last_name), \
+            source = VALUES(source)"
+    )
+    .bind(steam_id_64)
+    .bind(addr.to_string())
+    .bind(name)
+    .bind(source)
+    .execute(pool)
+    .await;
+
+    if let Err(e) = result {
+        tracing::warn!("Failed to record identity {} @ {}: {}", steam_id_64, addr, e);
+    }
+}
+
+/// Most recent in-game name seen for `steam_id_64`
+pub async fn last_name(pool: &MySqlPool, steam_id_64: &str) -> Option<String> {
+    sqlx::query_scalar("SELECT last_name FROM player_identities WHERE steam_id_64 = ? AND last_name IS NOT NULL ORDER BY last_seen DESC LIMIT 1")
+        .bind(steam_id_64)
+        .fetch_optional(pool)
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Accounts that share at least one IP with `steam_id_64`, most likely first.
+/// Each shared IP counts for less the more accounts were seen on it.
+pub async fn linked_accounts(pool: &MySqlPool, steam_id_64: &str) -> Result<Vec<LinkedAccount>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, SharedIpRow>(
+        "SELECT o.steam_id_64, o.ip, \
+            (SELECT COUNT(*) FROM player_identities x WHERE x.ip = o.ip) AS accounts_on_ip, \
+            o.last_name, o.first_seen, o.last_seen \
+         FROM player_identities p \
+         JOIN player_identities o ON o.ip = p.ip AND o.steam_id_64 <> p.steam_id_64 \
+         WHERE p.steam_id_64 = ?"
+    )
+    .bind(steam_id_64)
+    .fetch_all(pool)
+    .await?;
+
+    let mut accounts: HashMap<String, (LinkedAccount, f64)> = HashMap::new();
+    for row in rows {
+        let weight = SHARED_IP_WEIGHT / (row.accounts_on_ip.max(2) - 1) as f64;
+        let (account, unlinked) = accounts.entry(row.steam_id_64.clone()).or_insert_with(|| (
+            LinkedAccount {
+                steam_id_64: row.steam_id_64.clone(),
+                last_name: None,
+                shared_ips: Vec::new(),
+                confidence: 0.0,
+                first_seen: row.first_seen,
+                last_seen: row.last_seen,
+                active_ban_id: None,
+            },
+            1.0,
+        ));
+        // 多个共享 IP 视为相互独立的证据：1 - Π(1 - w)
+        *unlinked *= 1.0 - weight;
+        account.shared_ips.push(row.ip);
+        account.first_seen = match (account.first_seen, row.first_seen) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        if row.last_seen >= account.last_seen {
+            account.last_seen = row.last_seen;
+            account.last_name = row.last_name.or(account.last_name.take());
+        }
+    }
+
+    let mut linked: Vec<LinkedAccount> = accounts.into_values()
+        .map(|(mut account, unlinked)| {
+            account.confidence = ((1.0 - unlinked) * 100.0).round() / 100.0;
+            account
+        })
+        .collect();
+    linked.sort_by(|a, b| b.confidence.total_cmp(&a.confidence).then(b.last_seen.cmp(&a.last_seen)));
+
+    if !linked.is_empty() {
+        let placeholders = vec!["?"; linked.len()].join(", ");
+        let sql = format!(
+            "SELECT steam_id_64, id FROM bans WHERE status = 'active' AND (expires_at IS NULL OR expires_at > NOW()) AND steam_id_64 IN ({})",
+            placeholders
+        );
+        let mut query = sqlx::query_as::<_, (String, i64)>(&sql);
+        for account in &linked {
+            query = query.bind(&account.steam_id_64);
+        }
+        let bans: HashMap<String, i64> = query.fetch_all(pool).await?.into_iter().collect();
+        for account in &mut linked {
+            account.active_ban_id = bans.get(&account.steam_id_64).copied();
+        }
+    }
+
+    Ok(linked)
+}
+
+/// Finds an active, extendable ban on an account linked to `steam_id_64` with enough
+/// confidence that applies on server group `group_id`
+pub async fn banned_link(pool: &MySqlPool, steam_id_64: &str, group_id: Option<i64>) -> Result<Option<(Ban, LinkedAccount)>, sqlx::Error> {
+    let min_confidence = min_link_confidence();
+    for account in linked_accounts(pool, steam_id_64).await? {
+        if account.confidence < min_confidence {
+            break;
+        }
+        if account.active_ban_id.is_none() {
+            continue;
+        }
+        let ban = sqlx::query_as::<_, Ban>(&format!(
+            "SELECT * FROM bans b WHERE status = 'active' AND (expires_at IS NULL OR expires_at > NOW()) AND extend_to_linked = TRUE AND steam_id_64 = ? AND {} LIMIT 1",
+            ban_scope::SCOPE_CONDITION
+        ))
+        .bind(&account.steam_id_64)
+        .bind(group_id)
+        .bind(group_id)
+        .fetch_optional(pool)
+        .await?;
+        if let Some(ban) = ban {
+            return Ok(Some((ban, account)));
+        }
+    }
+    Ok(None)
+}
@@ -0,0 +1,805 @@
+use std::collections::HashSet;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use sqlx::mysql::MySqlPoolOptions;
+use sqlx::{FromRow, MySqlPool};
+use crate::models::import::{ImportCounts, ImportIssue, ImportReport};
+use crate::services::revision;
+use crate::services::steam_api::SteamService;
+use crate::utils::ip_range::IpRange;
+
+/// Only the first problems are reported; the counts cover everything
+const MAX_ISSUES: usize = 200;
+
+struct NewBan {
+    import_ref: Option<String>,
+    name: String,
+    steam_id: String,
+    steam_id_3: Option<String>,
+    steam_id_64: Option<String>,
+    ip: String,
+    ip_range: Option<IpRange>,
+    ban_type: &'static str,
+    reason: String,
+    duration: String,
+    admin_name: Option<String>,
+    created_at: DateTime<Utc>,
+    expires_at: Option<DateTime<Utc>>,
+    status: &'static str,
+    unbanned_by: Option<String>,
+    unbanned_at: Option<DateTime<Utc>>,
+    unban_reason: Option<String>,
+}
+
+impl NewBan {
+    /// Keys under which an active ban on the same player already blocks them
+    fn identities(&self) -> Vec<String> {
+        if self.ban_type == "ip" {
+            return vec![format!("ip:{}", self.ip)];
+        }
+        std::iter::once(&self.steam_id).chain(self.steam_id_64.as_ref())
+            .map(|id| format!("account:{}", id))
+            .collect()
+    }
+}
+
+struct NewComm {
+    import_ref: Option<String>,
+    name: String,
+    steam_id: String,
+    steam_id_3: Option<String>,
+    steam_id_64: String,
+    block_type: &'static str,
+    reason: String,
+    duration: String,
+    admin_name: Option<String>,
+    created_at: DateTime<Utc>,
+    expires_at: Option<DateTime<Utc>>,
+    status: &'static str,
+}
+
+struct NewAdmin {
+    username: String,
+    /// bcrypt hash carried over from the source; None means a random password is set
+    password_hash: Option<String>,
+    steam_id: Option<String>,
+    steam_id_3: Option<String>,
+    steam_id_64: Option<String>,
+    remark: String,
+}
+
+#[derive(FromRow)]
+struct SbBan {
+    bid: i64,
+    ip: String,
+    authid: String,
+    name: String,
+    created: i64,
+    ends: i64,
+    length: i64,
+    reason: String,
+    admin_name: Option<String>,
+    removed_by: Option<String>,
+    remove_type: Option<String>,
+    removed_on: Option<i64>,
+    ban_type: i64,
+    unban_reason: Option<String>,
+}
+
+#[derive(FromRow)]
+struct SbComm {
+    bid: i64,
+    authid: String,
+    name: String,
+    created: i64,
+    ends: i64,
+    length: i64,
+    reason: String,
+    admin_name: Option<String>,
+    remove_type: Option<String>,
+    block_type: i64,
+}
+
+#[derive(FromRow)]
+struct SbAdmin {
+    aid: i64,
+    user: String,
+    authid: String,
+    password: String,
+}
+
+/// `30m`, `1d`, `permanent` for a length in seconds
+pub(crate) fn duration_label(seconds: i64) -> String {
+    match seconds {
+        s if s <= 0 => "permanent".to_string(),
+        s if s % 86400 == 0 => format!("{}d", s / 86400),
+        s if s % 3600 == 0 => format!("{}h", s / 3600),
+        s if s % 60 == 0 => format!("{}m", s / 60),
+        s => format!("{}s", s),
+    }
+}
+
+fn from_unix(secs: i64) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(secs, 0)
+}
+
+/// Database name of a MySQL URL, used to tell imports from different SourceBans installs apart
+fn database_name(url: &str) -> &str {
+    let path = url.rsplit('/').next().unwrap_or_default();
+    path.split('?').next().unwrap_or_default()
+}
+
+fn non_empty(s: String) -> Option<String> {
+    Some(s.trim().to_string()).filter(|s| !s.is_empty())
+}
+
+/// Collects records mapped from another ban system, deduplicating them against
+/// existing rows and against each other, then writes them in one transaction
+pub struct Importer {
+    report: ImportReport,
+    steam: SteamService,
+    ban_refs: HashSet<String>,
+    active_bans: HashSet<String>,
+    ban_history: HashSet<(String, i64)>,
+    comm_refs: HashSet<String>,
+    active_comms: HashSet<String>,
+    comm_history: HashSet<(String, i64)>,
+    admin_names: HashSet<String>,
+    admin_steam_ids: HashSet<String>,
+    bans: Vec<NewBan>,
+    comms: Vec<NewComm>,
+    admins: Vec<NewAdmin>,
+}
+
+impl Importer {
+    fn empty(dry_run: bool) -> Self {
+        Importer {
+            report: ImportReport { dry_run, ..Default::default() },
+            steam: SteamService::new(),
+            ban_refs: HashSet::new(),
+            active_bans: HashSet::new(),
+            ban_history: HashSet::new(),
+            comm_refs: HashSet::new(),
+            active_comms: HashSet::new(),
+            comm_history: HashSet::new(),
+            admin_names: HashSet::new(),
+            admin_steam_ids: HashSet::new(),
+            bans: Vec::new(),
+            comms: Vec::new(),
+            admins: Vec::new(),
+        }
+    }
+
+    /// Loads the keys of existing bans, comm blocks and admins for deduplication
+    pub async fn new(pool: &MySqlPool, dry_run: bool) -> Result<Self, sqlx::Error> {
+        let mut importer = Importer::empty(dry_run);
+
+        let bans = sqlx::query_as::<_, (String, String, Option<String>, String, i64, Option<DateTime<Utc>>, Option<String>)>(
+            "SELECT ban_type, steam_id, steam_id_64, ip, \
+                CAST(status = 'active' AND (expires_at IS NULL OR expires_at > NOW()) AS SIGNED) AS is_active, \
+                created_at, import_ref FROM bans"
+        )
+        .fetch_all(pool)
+        .await?;
+        for (ban_type, steam_id, steam_id_64, ip, is_active, created_at, import_ref) in bans {
+            let identities: Vec<String> = if ban_type == "ip" {
+                vec![format!("ip:{}", ip)]
+            } else {
+                std::iter::once(steam_id).chain(steam_id_64)
+                    .filter(|id| !id.is_empty())
+                    .map(|id| format!("account:{}", id))
+                    .collect()
+            };
+            for identity in identities {
+                if let Some(t) = created_at {
+                    importer.ban_history.insert((identity.clone(), t.timestamp()));
+                }
+                if is_active > 0 {
+                    importer.active_bans.insert(identity);
+                }
+            }
+            importer.ban_refs.extend(import_ref);
+        }
+
+        let comms = sqlx::query_as::<_, (Option<String>, String, i64, Option<DateTime<Utc>>, Option<String>)>(
+            "SELECT steam_id_64, block_type, \
+                CAST(status = 'active' AND (expires_at IS NULL OR expires_at > NOW()) AS SIGNED) AS is_active, \
+                created_at, import_ref FROM comm_blocks"
+        )
+        .fetch_all(pool)
+        .await?;
+        for (steam_id_64, block_type, is_active, created_at, import_ref) in comms {
+            if let Some(id64) = steam_id_64 {
+                let identity = format!("{}:{}", id64, block_type);
+                if let Some(t) = created_at {
+                    importer.comm_history.insert((identity.clone(), t.timestamp()));
+                }
+                if is_active > 0 {
+                    importer.active_comms.insert(identity);
+                }
+            }
+            importer.comm_refs.extend(import_ref);
+        }
+
+        let admins = sqlx::query_as::<_, (String, Option<String>)>("SELECT username, steam_id_64 FROM admins")
+            .fetch_all(pool)
+            .await?;
+        for (username, steam_id_64) in admins {
+            importer.admin_names.insert(username.to_lowercase());
+            importer.admin_steam_ids.extend(steam_id_64);
+        }
+
+        Ok(importer)
+    }
+
+    fn issue(&mut self, source: String, message: impl Into<String>) {
+        if self.report.issues.len() < MAX_ISSUES {
+            self.report.issues.push(ImportIssue { source, message: message.into() });
+        }
+    }
+
+    fn skip(&mut self, counts: fn(&mut ImportReport) -> &mut ImportCounts, source: String, message: impl Into<String>) {
+        counts(&mut self.report).skipped += 1;
+        self.issue(source, message);
+    }
+
+    /// (SteamID2, SteamID3, SteamID64) for a SteamID2/3/64; other input is rejected
+    /// without asking the Steam Web API
+    async fn steam_ids(&self, input: &str) -> Option<(String, Option<String>, String)> {
+        let input = input.trim();
+        let looks_like_id = input.starts_with("STEAM_") || input.starts_with("[U:")
+            || (!input.is_empty() && input.chars().all(|c| c.is_ascii_digit()));
+        if !looks_like_id {
+            return None;
+        }
+        let id64 = self.steam.resolve_steam_id(input).await?;
+        let id2 = self.steam.id64_to_id2(&id64)?;
+        let id3 = self.steam.id64_to_id3(&id64);
+        Some((id2, id3, id64))
+    }
+
+    fn add_ban(&mut self, ban: NewBan) {
+        let identities = ban.identities();
+        let created = ban.created_at.timestamp();
+        let duplicate = ban.import_ref.as_ref().is_some_and(|r| self.ban_refs.contains(r))
+            || identities.iter().any(|i| self.ban_history.contains(&(i.clone(), created)))
+            || (ban.status == "active" && identities.iter().any(|i| self.active_bans.contains(i)));
+        if duplicate {
+            self.report.bans.duplicates += 1;
+            return;
+        }
+
+        for identity in identities {
+            self.ban_history.insert((identity.clone(), created));
+            if ban.status == "active" {
+                self.active_bans.insert(identity);
+            }
+        }
+        self.ban_refs.extend(ban.import_ref.clone());
+        self.report.bans.imported += 1;
+        self.bans.push(ban);
+    }
+
+    fn add_comm(&mut self, comm: NewComm) {
+        let identity = format!("{}:{}", comm.steam_id_64, comm.block_type);
+        let key = (identity.clone(), comm.created_at.timestamp());
+        let duplicate = comm.import_ref.as_ref().is_some_and(|r| self.comm_refs.contains(r))
+            || self.comm_history.contains(&key)
+            || (comm.status == "active" && self.active_comms.contains(&identity));
+        if duplicate {
+            self.report.comms.duplicates += 1;
+            return;
+        }
+
+        self.comm_history.insert(key);
+        if comm.status == "active" {
+            self.active_comms.insert(identity);
+        }
+        self.comm_refs.extend(comm.import_ref.clone());
+        self.report.comms.imported += 1;
+        self.comms.push(comm);
+    }
+
+    fn add_admin(&mut self, admin: NewAdmin) {
+        let duplicate = self.admin_names.contains(&admin.username.to_lowercase())
+            || admin.steam_id_64.as_ref().is_some_and(|id| self.admin_steam_ids.contains(id));
+        if duplicate {
+            self.report.admins.duplicates += 1;
+            return;
+        }
+
+        self.admin_names.insert(admin.username.to_lowercase());
+        self.admin_steam_ids.extend(admin.steam_id_64.clone());
+        if admin.password_hash.is_none() {
+            self.report.admins_needing_password.push(admin.username.clone());
+        }
+        self.report.admins.imported += 1;
+        self.admins.push(admin);
+    }
+
+    /// Reads `<prefix>_bans` and optionally `<prefix>_comms` / `<prefix>_admins` from a SourceBans++ database
+    pub async fn read_sourcebans(&mut self, database_url: &str, prefix: &str, include_comms: bool, include_admins: bool) -> Result<(), sqlx::Error> {
+        let source = MySqlPoolOptions::new()
+            .max_connections(1)
+            .acquire_timeout(Duration::from_secs(10))
+            .connect(database_url)
+            .await?;
+        let origin = format!("sourcebans:{}.{}", database_name(database_url), prefix);
+        let now = Utc::now().timestamp();
+
+        // SourceBans++ 的整数列有的是无符号、有的可为 NULL，统一 CAST 成 BIGINT 读取
+        let bans = sqlx::query_as::<_, SbBan>(&format!(
+            "SELECT CAST(b.bid AS SIGNED) AS bid, COALESCE(b.ip, '') AS ip, COALESCE(b.authid, '') AS authid, \
+                COALESCE(b.name, '') AS name, CAST(b.created AS SIGNED) AS created, CAST(b.ends AS SIGNED) AS ends, \
+                CAST(b.length AS SIGNED) AS length, COALESCE(b.reason, '') AS reason, a.user AS admin_name, \
+                r.user AS removed_by, b.RemoveType AS remove_type, CAST(b.RemovedOn AS SIGNED) AS removed_on, \
+                CAST(b.`type` AS SIGNED) AS ban_type, b.ureason AS unban_reason \
+             FROM {p}_bans b LEFT JOIN {p}_admins a ON a.aid = b.aid LEFT JOIN {p}_admins r ON r.aid = b.RemovedBy \
+             ORDER BY b.bid",
+            p = prefix
+        ))
+        .fetch_all(&source)
+        .await?;
+
+        for row in bans {
+            self.report.bans.found += 1;
+            let at = format!("{}_bans#{}", prefix, row.bid);
+            let removed = row.remove_type.as_deref().map(str::trim).unwrap_or_default().to_ascii_uppercase();
+            if removed == "D" {
+                self.skip(|r| &mut r.bans, at, "Deleted in SourceBans");
+                continue;
+            }
+            let Some(created_at) = from_unix(row.created) else {
+                self.skip(|r| &mut r.bans, at, format!("Invalid creation time {}", row.created));
+                continue;
+            };
+            let expires_at = if row.length > 0 { from_unix(row.ends) } else { None };
+            let status = match removed.as_str() {
+                "U" => "unbanned",
+                "E" => "expired",
+                _ if row.length > 0 && row.ends <= now => "expired",
+                _ => "active",
+            };
+
+            let ip = row.ip.trim().to_string();
+            let (ban_type, ip_range, ids) = if row.ban_type == 1 {
+                match IpRange::parse(&ip) {
+                    Ok(range) => ("ip", Some(range), self.steam_ids(&row.authid).await),
+                    Err(e) => {
+                        self.skip(|r| &mut r.bans, at, e);
+                        continue;
+                    }
+                }
+            } else {
+                match self.steam_ids(&row.authid).await {
+                    Some(ids) => ("account", None, Some(ids)),
+                    None => {
+                        self.skip(|r| &mut r.bans, at, format!("Unrecognised SteamID '{}'", row.authid));
+                        continue;
+                    }
+                }
+            };
+            let (steam_id, steam_id_3, steam_id_64) = match ids {
+                Some((id2, id3, id64)) => (id2, id3, Some(id64)),
+                None => (String::new(), None, None),
+            };
+
+            self.add_ban(NewBan {
+                import_ref: Some(format!("{}:{}", origin, row.bid)),
+                name: non_empty(row.name).unwrap_or_else(|| "Unknown".to_string()),
+                steam_id,
+                steam_id_3,
+                steam_id_64,
+                ip,
+                ip_range,
+                ban_type,
+                reason: row.reason,
+                duration: duration_label(row.length),
+                admin_name: Some(row.admin_name.unwrap_or_else(|| "CONSOLE".to_string())),
+                created_at,
+                expires_at,
+                status,
+                unbanned_by: if status == "unbanned" { row.removed_by } else { None },
+                unbanned_at: if status == "unbanned" { row.removed_on.and_then(from_unix) } else { None },
+                unban_reason: if status == "unbanned" { row.unban_reason.and_then(non_empty) } else { None },
+            });
+        }
+
+        if include_comms {
+            let comms = sqlx::query_as::<_, SbComm>(&format!(
+                "SELECT CAST(c.bid AS SIGNED) AS bid, COALESCE(c.authid, '') AS authid, COALESCE(c.name, '') AS name, \
+                    CAST(c.created AS SIGNED) AS created, CAST(c.ends AS SIGNED) AS ends, CAST(c.length AS SIGNED) AS length, \
+                    COALESCE(c.reason, '') AS reason, a.user AS admin_name, c.RemoveType AS remove_type, \
+                    CAST(c.`type` AS SIGNED) AS block_type \
+                 FROM {p}_comms c LEFT JOIN {p}_admins a ON a.aid = c.aid ORDER BY c.bid",
+                p = prefix
+            ))
+            .fetch_all(&source)
+            .await?;
+
+            for row in comms {
+                self.report.comms.found += 1;
+                let at = format!("{}_comms#{}", prefix, row.bid);
+                let removed = row.remove_type.as_deref().map(str::trim).unwrap_or_default().to_ascii_uppercase();
+                if removed == "D" {
+                    self.skip(|r| &mut r.comms, at, "Deleted in SourceBans");
+                    continue;
+                }
+                let block_type = match row.block_type {
+                    1 => "mute",
+                    2 => "gag",
+                    3 => "silence",
+                    other => {
+                        self.skip(|r| &mut r.comms, at, format!("Unknown block type {}", other));
+                        continue;
+                    }
+                };
+                let Some((steam_id, steam_id_3, steam_id_64)) = self.steam_ids(&row.authid).await else {
+                    self.skip(|r| &mut r.comms, at, format!("Unrecognised SteamID '{}'", row.authid));
+                    continue;
+                };
+                let Some(created_at) = from_unix(row.created) else {
+                    self.skip(|r| &mut r.comms, at, format!("Invalid creation time {}", row.created));
+                    continue;
+                };
+                let status = match removed.as_str() {
+                    "U" => "removed",
+                    "E" => "expired",
+                    _ if row.length > 0 && row.ends <= now => "expired",
+                    _ => "active",
+                };
+
+                self.add_comm(NewComm {
+                    import_ref: Some(format!("{}:{}", origin, row.bid)),
+                    name: non_empty(row.name).unwrap_or_else(|| "Unknown".to_string()),
+                    steam_id,
+                    steam_id_3,
+                    steam_id_64,
+                    block_type,
+                    reason: row.reason,
+                    duration: duration_label(row.length),
+                    admin_name: Some(row.admin_name.unwrap_or_else(|| "CONSOLE".to_string())),
+                    created_at,
+                    expires_at: if row.length > 0 { from_unix(row.ends) } else { None },
+                    status,
+                });
+            }
+        }
+
+        if include_admins {
+            let admins = sqlx::query_as::<_, SbAdmin>(&format!(
+                "SELECT CAST(aid AS SIGNED) AS aid, COALESCE(user, '') AS user, COALESCE(authid, '') AS authid, \
+                    COALESCE(password, '') AS password \
+                 FROM {p}_admins WHERE aid > 0 AND user <> 'CONSOLE' ORDER BY aid",
+                p = prefix
+            ))
+            .fetch_all(&source)
+            .await?;
+
+            for row in admins {
+                self.report.admins.found += 1;
+                let at = format!("{}_admins#{}", prefix, row.aid);
+                let Some(username) = non_empty(row.user) else {
+                    self.skip(|r| &mut r.admins, at, "Empty username");
+                    continue;
+                };
+                let ids = self.steam_ids(&row.authid).await;
+                if ids.is_none() && !row.authid.trim().is_empty() {
+                    self.issue(at, format!("Unrecognised SteamID '{}', imported without one", row.authid));
+                }
+                let (steam_id, steam_id_3, steam_id_64) = match ids {
+                    Some((id2, id3, id64)) => (Some(id2), id3, Some(id64)),
+                    None => (None, None, None),
+                };
+
+                // SourceBans++ 1.7+ 使用 bcrypt，可直接沿用；更早的 SHA1 密码无法迁移
+                let password_hash = Some(row.password).filter(|p| p.starts_with("$2"));
+                self.add_admin(NewAdmin {
+                    username,
+                    password_hash,
+                    steam_id,
+                    steam_id_3,
+                    steam_id_64,
+                    remark: format!("Imported from SourceBans ({} #{})", database_name(database_url), row.aid),
+                });
+            }
+        }
+
+        source.close().await;
+        Ok(())
+    }
+
+    /// Reads a SourceMod `banned_user.cfg` (`banid <minutes> <steamid>`) or
+    /// `banned_ip.cfg` (`addip <minutes> <ip>`)
+    pub async fn read_cfg(&mut self, file_name: &str, text: &str, actor: &str) {
+        let now = Utc::now();
+        for (line_no, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+            self.report.bans.found += 1;
+            let at = format!("{}:{}", file_name, line_no + 1);
+
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let (command, minutes, target) = match parts.as_slice() {
+                [command, minutes, target, ..] => (*command, *minutes, target.trim_matches('"')),
+                _ => {
+                    self.skip(|r| &mut r.bans, at, format!("Unrecognised line '{}'", line));
+                    continue;
+                }
+            };
+            let Ok(minutes) = minutes.parse::<f64>() else {
+                self.skip(|r| &mut r.bans, at, format!("Invalid duration '{}'", minutes));
+                continue;
+            };
+            let seconds = (minutes * 60.0).round() as i64;
+            let expires_at = if seconds > 0 { Some(now + chrono::Duration::seconds(seconds)) } else { None };
+
+            let (ban_type, steam_ids, ip, ip_range) = match command {
+                "banid" => match self.steam_ids(target).await {
+                    Some(ids) => ("account", Some(ids), String::new(), None),
+                    None => {
+                        self.skip(|r| &mut r.bans, at, format!("Unrecognised SteamID '{}'", target));
+                        continue;
+                    }
+                },
+                "addip" => match IpRange::parse(target) {
+                    Ok(range) => ("ip", None, target.to_string(), Some(range)),
+                    Err(e) => {
+                        self.skip(|r| &mut r.bans, at, e);
+                        continue;
+                    }
+                },
+                other => {
+                    self.skip(|r| &mut r.bans, at, format!("Unknown command '{}'", other));
+                    continue;
+                }
+            };
+            let (steam_id, steam_id_3, steam_id_64) = match steam_ids {
+                Some((id2, id3, id64)) => (id2, id3, Some(id64)),
+                None => (String::new(), None, None),
+            };
+
+            self.add_ban(NewBan {
+                import_ref: None,
+                name: "Unknown".to_string(),
+                steam_id,
+                steam_id_3,
+                steam_id_64,
+                ip,
+                ip_range,
+                ban_type,
+                reason: format!("Imported from {}", file_name),
+                duration: duration_label(seconds),
+                admin_name: Some(actor.to_string()),
+                created_at: now,
+                expires_at,
+                status: "active",
+                unbanned_by: None,
+                unbanned_at: None,
+                unban_reason: None,
+            });
+        }
+    }
+
+    /// The report without writing anything
+    pub fn into_report(self) -> ImportReport {
+        self.report
+    }
+
+    /// Writes all collected records in one transaction and returns the report
+    pub async fn commit(self, pool: &MySqlPool, actor: &str) -> Result<ImportReport, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        let mut ban_ids = Vec::with_capacity(self.bans.len());
+
+        for ban in &self.bans {
+            let res = sqlx::query(
+                "INSERT INTO bans (name, steam_id, steam_id_3, steam_id_64, ip, ip_start, ip_end, ban_type, reason, duration, admin_name, created_at, expires_at, status, unbanned_by, unbanned_at, unban_reason, extend_to_linked, import_ref) \
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            )
+            .bind(&ban.name)
+            .bind(&ban.steam_id)
+            .bind(&ban.steam_id_3)
+            .bind(&ban.steam_id_64)
+            .bind(&ban.ip)
+            .bind(ban.ip_range.map(|r| r.start_key()))
+            .bind(ban.ip_range.map(|r| r.end_key()))
+            .bind(ban.ban_type)
+            .bind(&ban.reason)
+            .bind(&ban.duration)
+            .bind(&ban.admin_name)
+            .bind(ban.created_at)
+            .bind(ban.expires_at)
+            .bind(ban.status)
+            .bind(&ban.unbanned_by)
+            .bind(ban.unbanned_at)
+            .bind(&ban.unban_reason)
+            .bind(ban.ban_type == "ip")
+            .bind(&ban.import_ref)
+            .execute(&mut *tx)
+            .await?;
+            ban_ids.push(res.last_insert_id() as i64);
+        }
+
+        for comm in &self.comms {
+            sqlx::query(
+                "INSERT INTO comm_blocks (name, steam_id, steam_id_3, steam_id_64, block_type, reason, duration, status, admin_name, created_at, expires_at, import_ref) \
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            )
+            .bind(&comm.name)
+            .bind(&comm.steam_id)
+            .bind(&comm.steam_id_3)
+            .bind(&comm.steam_id_64)
+            .bind(comm.block_type)
+            .bind(&comm.reason)
+            .bind(&comm.duration)
+            .bind(comm.status)
+            .bind(&comm.admin_name)
+            .bind(comm.created_at)
+            .bind(comm.expires_at)
+            .bind(&comm.import_ref)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        for admin in &self.admins {
+            // 无法沿用原密码的管理员设置随机密码，需由超级管理员重置
+            let password = match &admin.password_hash {
+                Some(hash) => hash.clone(),
+                None => bcrypt::hash(uuid::Uuid::new_v4().to_string(), bcrypt::DEFAULT_COST)
+                    .map_err(|e| sqlx::Error::Protocol(e.to_string()))?,
+            };
+            sqlx::query(
+                "INSERT INTO admins (username, password, role, steam_id, steam_id_3, steam_id_64, remark, must_change_password) VALUES (?, ?, 'admin', ?, ?, ?, ?, TRUE)"
+            )
+            .bind(&admin.username)
+            .bind(password)
+            .bind(&admin.steam_id)
+            .bind(&admin.steam_id_3)
+            .bind(&admin.steam_id_64)
+            .bind(&admin.remark)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        for id in ban_ids {
+            revision::track(pool, id, None, "import", actor).await;
+        }
+        Ok(self.report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn importer() -> Importer {
+        // SteamID2/3/64 conversion is local; the key is only used for vanity lookups
+        std::env::set_var("STEAM_API_KEY", "test");
+        Importer::empty(true)
+    }
+
+    #[test]
+    fn labels_durations() {
+        let cases = [
+            (0, "permanent"),
+            (-60, "permanent"),
+            (45, "45s"),
+            (30 * 60, "30m"),
+            (90 * 60, "90m"),
+            (2 * 3600, "2h"),
+            (86400, "1d"),
+            (36 * 3600, "36h"),
+        ];
+        for (seconds, label) in cases {
+            assert_eq!(duration_label(seconds), label, "{}", seconds);
+        }
+    }
+
+    #[test]
+    fn reads_database_names() {
+        assert_eq!(database_name("mysql://u:p@host:3306/sourcebans"), "sourcebans");
+        assert_eq!(database_name("mysql://u:p@host/sb?ssl-mode=disabled"), "sb");
+        assert_eq!(database_name(""), "");
+    }
+
+    #[tokio::test]
+    async fn converts_steam_ids() {
+        let importer = importer();
+        let cases = [
+            ("STEAM_0:1:12345", "STEAM_0:1:12345", "[U:1:24691]", "76561197960290419"),
+            ("STEAM_1:0:7", "STEAM_0:0:7", "[U:1:14]", "76561197960265742"),
+            ("[U:1:100]", "STEAM_0:0:50", "[U:1:100]", "76561197960265828"),
+            (" 76561197960265829 ", "STEAM_0:1:50", "[U:1:101]", "76561197960265829"),
+        ];
+        for (input, id2, id3, id64) in cases {
+            let ids = importer.steam_ids(input).await;
+            assert_eq!(ids, Some((id2.to_string(), Some(id3.to_string()), id64.to_string())), "{:?}", input);
+        }
+        for input in ["", "player!", "STEAM_0:2:1", "STEAM_0:1:abc", "[U:1:12", "[G:1:5]", "not a steamid"] {
+            assert_eq!(importer.steam_ids(input).await, None, "{:?}", input);
+        }
+    }
+
+    #[tokio::test]
+    async fn reads_cfg_bans() {
+        let mut importer = importer();
+        let cfg = "// banned_user.cfg\n\
+            \n\
+            banid 0 STEAM_0:1:12345\n\
+            banid 30 [U:1:100]\n\
+            banid 1440 \"STEAM_1:0:7\"\n\
+            banid 90 76561197960265829\n\
+            addip 0 10.0.0.1\n\
+            addip 60 10.0.0.0/24\n";
+        importer.read_cfg("banned.cfg", cfg, "importer").await;
+
+        let report = &importer.report;
+        assert_eq!((report.bans.found, report.bans.imported, report.bans.skipped), (6, 6, 0));
+        assert!(report.issues.is_empty());
+
+        let summary: Vec<_> = importer.bans.iter()
+            .map(|b| (b.ban_type, b.steam_id.as_str(), b.ip.as_str(), b.duration.as_str(), b.expires_at.is_none()))
+            .collect();
+        assert_eq!(summary, vec![
+            ("account", "STEAM_0:1:12345", "", "permanent", true),
+            ("account", "STEAM_0:0:50", "", "30m", false),
+            ("account", "STEAM_0:0:7", "", "1d", false),
+            ("account", "STEAM_0:1:50", "", "90m", false),
+            ("ip", "", "10.0.0.1", "permanent", true),
+            ("ip", "", "10.0.0.0/24", "1h", false),
+        ]);
+        assert_eq!(importer.bans[1].steam_id_64.as_deref(), Some("76561197960265828"));
+        assert!(importer.bans[5].ip_range.is_some());
+        assert!(importer.bans.iter().all(|b| b.admin_name.as_deref() == Some("importer") && b.status == "active"));
+    }
+
+    #[tokio::test]
+    async fn skips_malformed_cfg_lines() {
+        let mut importer = importer();
+        let cfg = "banid 30\n\
+            banid\n\
+            banid abc STEAM_0:1:1\n\
+            banid 30 player!\n\
+            banid 30 STEAM_0:1:abc\n\
+            banid 30 [U:1:12\n\
+            addip 30 10.0.0.300\n\
+            addip 30 10.0.0.9-10.0.0.1\n\
+            addip 30 10.0.0.0/40\n\
+            kick 30 STEAM_0:1:1\n";
+        importer.read_cfg("bad.cfg", cfg, "importer").await;
+
+        let report = &importer.report;
+        assert_eq!((report.bans.found, report.bans.imported, report.bans.skipped), (10, 0, 10));
+        let issues: Vec<_> = report.issues.iter().map(|i| (i.source.as_str(), i.message.as_str())).collect();
+        assert_eq!(issues, vec![
+            ("bad.cfg:1", "Unrecognised line 'banid 30'"),
+            ("bad.cfg:2", "Unrecognised line 'banid'"),
+            ("bad.cfg:3", "Invalid duration 'abc'"),
+            ("bad.cfg:4", "Unrecognised SteamID 'player!'"),
+            ("bad.cfg:5", "Unrecognised SteamID 'STEAM_0:1:abc'"),
+            ("bad.cfg:6", "Unrecognised SteamID '[U:1:12'"),
+            ("bad.cfg:7", "Invalid IP address '10.0.0.300'"),
+            ("bad.cfg:8", "Range start must not be after its end"),
+            ("bad.cfg:9", "Invalid prefix length in '10.0.0.0/40'"),
+            ("bad.cfg:10", "Unknown command 'kick'"),
+        ]);
+        assert!(importer.bans.is_empty());
+    }
+
+    #[tokio::test]
+    async fn deduplicates_repeated_cfg_bans() {
+        let mut importer = importer();
+        let cfg = "banid 0 STEAM_0:1:12345\n\
+            banid 0 [U:1:24691]\n\
+            addip 0 10.0.0.1\n\
+            addip 0 10.0.0.1\n";
+        importer.read_cfg("banned.cfg", cfg, "importer").await;
+
+        let report = &importer.report;
+        assert_eq!((report.bans.found, report.bans.imported, report.bans.duplicates), (4, 2, 2));
+    }
+}
@@ -0,0 +1,189 @@
+use sqlx::MySqlPool;
+use crate::AppState;
+use crate::models::ban::Ban;
+use crate::models::ip_match::IpMatchPolicy;
+use crate::services::enforcement::{self, LinkedTarget};
+use crate::utils::ip_range;
+
+/// What became of an account that matched an extendable ban on another account
+pub enum MatchOutcome {
+    /// Policy `auto`: a linked ban was created
+    Banned(Box<Ban>),
+    /// Policy `flag`: the match waits in the review queue
+    Flagged,
+    /// Nothing was created for the account (policy `off`, the parent ban does not
+    /// extend, or the match was already confirmed); an IP ban still blocks the address
+    Ignored,
+    /// Exempt SteamID / trusted IP range, or an admin dismissed this match: let through
+    Exempt,
+}
+
+pub async fn policy(pool: &MySqlPool) -> Result<IpMatchPolicy, sqlx::Error> {
+    let policy = sqlx::query_as::<_, IpMatchPolicy>(
+        "SELECT mode, exempt_whitelisted, updated_by, updated_at FROM ip_match_policy WHERE id = 1"
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    // 缺少配置行时保持原有的自动封禁行为
+    Ok(policy.unwrap_or(IpMatchPolicy {
+        mode: "auto".to_string(),
+        exempt_whitelisted: true,
+        updated_by: None,
+        updated_at: None,
+    }))
+}
+
+/// Whether the account or its address is on the exemption list (or the approved whitelist)
+pub async fn is_exempt(pool: &MySqlPool, policy: &IpMatchPolicy, target: &LinkedTarget<'_>) -> Result<bool, sqlx::Error> {
+    let steam_id_64 = target.steam_id_64.unwrap_or(target.steam_id);
+    let ip_key = ip_range::parse_player_ip(target.ip).map(ip_range::key);
+
+    let exempt: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM ip_match_exemptions WHERE (kind = 'steam_id' AND value IN (?, ?)) \
+         OR (kind = 'ip_range' AND ip_start <= ? AND ip_end >= ?)"
+    )
+    .bind(target.steam_id)
+    .bind(steam_id_64)
+    .bind(&ip_key)
+    .bind(&ip_key)
+    .fetch_one(pool)
+    .await?;
+    if exempt > 0 || !policy.exempt_whitelisted {
+        return Ok(exempt > 0);
+    }
+
+    let whitelisted: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM whitelist WHERE status = 'approved' AND (steam_id = ? OR steam_id_64 = ?)"
+    )
+    .bind(target.steam_id)
+    .bind(steam_id_64)
+    .fetch_one(pool)
+    .await?;
+    Ok(whitelisted > 0)
+}
+
+/// Applies the IP-match policy to `target`, which shares an IP (`source` 'join' / 'status')
+/// or a strong identity link ('alt_link') with the account banned by `parent`
+pub async fn handle(
+    state: &AppState,
+    parent: &Ban,
+    target: &LinkedTarget<'_>,
+    source: &str,
+    reason: &str,
+    actor: &str,
+) -> Result<MatchOutcome, sqlx::Error> {
+    let policy = policy(&state.db).await?;
+    if is_exempt(&state.db, &policy, target).await? {
+        return Ok(MatchOutcome::Exempt);
+    }
+
+    let decided: Option<String> = sqlx::query_scalar(
+        "SELECT status FROM ip_match_reviews WHERE parent_ban_id = ? AND steam_id = ?"
+    )
+    .bind(parent.id)
+    .bind(target.steam_id)
+    .fetch_optional(&state.db)
+    .await?;
+    match decide(&policy.mode, decided.as_deref(), parent.extend_to_linked) {
+        Action::Ban => enforcement::ban_linked_account(state, parent, target, reason, actor).await.map(|ban| MatchOutcome::Banned(Box::new(ban))),
+        Action::Flag => {
+            flag(&state.db, parent, target, source).await?;
+            Ok(MatchOutcome::Flagged)
+        }
+        Action::Exempt => Ok(MatchOutcome::Exempt),
+        Action::Ignore => Ok(MatchOutcome::Ignored),
+    }
+}
+
+/// What `handle` does for a non-exempt account
+#[derive(Debug, PartialEq, Eq)]
+enum Action {
+    Ban,
+    Flag,
+    Exempt,
+    Ignore,
+}
+
+/// Decides from the policy mode, an earlier review decision and whether the parent ban extends
+fn decide(mode: &str, review: Option<&str>, extends: bool) -> Action {
+    match review {
+        // 管理员已驳回：该账号不再因这条封禁被拦截
+        Some("dismissed") => return Action::Exempt,
+        // 已确认过：之后被解封的关联封禁不再自动重建
+        Some("confirmed") => return Action::Ignore,
+        _ => {}
+    }
+
+    if !extends {
+        return Action::Ignore;
+    }
+
+    match mode {
+        "auto" => Action::Ban,
+        "flag" => Action::Flag,
+        _ => Action::Ignore,
+    }
+}
+
+/// Queues (or bumps) a pending review for the match
+async fn flag(pool: &MySqlPool, parent: &Ban, target: &LinkedTarget<'_>, source: &str) -> Result<(), sqlx::Error> {
+    let name = Some(target.name).filter(|n| !n.is_empty() && *n != "Auto-Banned");
+    let res = sqlx::query(
+        "INSERT INTO ip_match_reviews (parent_ban_id, steam_id, steam_id_64, name, ip, server_id, source) VALUES (?, ?, ?, ?, ?, ?, ?) \
+         ON DUPLICATE KEY UPDATE \
+            match_count = match_count + 1, \
+            last_seen = NOW(), \
+            ip = VALUES(ip), \
+            name = COALESCE(VALUES(name), name), \
+            steam_id_64 = COALESCE(VALUES(steam_id_64), steam_id_64)"
+    )
+    .bind(parent.id)
+    .bind(target.steam_id)
+    .bind(target.steam_id_64)
+    .bind(name)
+    .bind(target.ip)
+    .bind(target.server_id)
+    .bind(source)
+    .execute(pool)
+    .await?;
+
+    // MySQL 对新插入的行返回 1，对更新的行返回 2
+    if res.rows_affected() == 1 {
+        tracing::info!("IP match flagged for review: {} @ {} (ban #{})", target.steam_id, target.ip, parent.id);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ip_match::IP_MATCH_MODES;
+
+    #[test]
+    fn follows_the_policy_mode() {
+        assert_eq!(decide("auto", None, true), Action::Ban);
+        assert_eq!(decide("flag", None, true), Action::Flag);
+        assert_eq!(decide("off", None, true), Action::Ignore);
+        // 未知模式不自动封禁
+        assert_eq!(decide("bogus", None, true), Action::Ignore);
+        assert!(IP_MATCH_MODES.iter().all(|m| ["auto", "flag", "off"].contains(m)));
+    }
+
+    #[test]
+    fn only_extending_bans_reach_linked_accounts() {
+        for mode in ["auto", "flag", "off"] {
+            assert_eq!(decide(mode, None, false), Action::Ignore, "{}", mode);
+        }
+    }
+
+    #[test]
+    fn earlier_reviews_win_over_the_mode() {
+        for mode in ["auto", "flag", "off"] {
+            assert_eq!(decide(mode, Some("dismissed"), true), Action::Exempt, "{}", mode);
+            assert_eq!(decide(mode, Some("confirmed"), true), Action::Ignore, "{}", mode);
+            // 待审核的记录按当前模式处理
+            assert_eq!(decide(mode, Some("pending"), true), decide(mode, None, true), "{}", mode);
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use std::time::Duration;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use crate::services::cache::Cache;
+
+/// Longest delay enforced between two failed attempts before the lockout kicks in
+const MAX_BACKOFF_SECS: i64 = 60;
+/// An IP may fail this many times more often than a single account before it is locked
+const IP_FACTOR: i64 = 4;
+
+/// Who a failed login is counted against
+#[derive(Clone, Copy)]
+pub enum Subject<'a> {
+    User(&'a str),
+    Ip(&'a str),
+}
+
+impl Subject<'_> {
+    fn id(&self) -> String {
+        match self {
+            Subject::User(name) => format!("user:{}", name.to_lowercase()),
+            Subject::Ip(ip) => format!("ip:{}", ip),
+        }
+    }
+
+    fn max_failures(&self) -> i64 {
+        let max = max_failures();
+        match self {
+            Subject::User(_) => max,
+            Subject::Ip(_) => max * IP_FACTOR,
+        }
+    }
+}
+
+impl std::fmt::Display for Subject<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Subject::User(name) => write!(f, "User: {}", name),
+            Subject::Ip(ip) => write!(f, "IP: {}", ip),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Block {
+    until: i64,
+    locked: bool,
+}
+
+/// Returned when a login must be refused before the password is even checked
+pub struct Blocked {
+    pub retry_after: i64,
+    pub locked: bool,
+}
+
+fn max_failures() -> i64 {
+    std::env::var("LOGIN_MAX_FAILURES").ok().and_then(|v| v.parse().ok()).unwrap_or(5)
+}
+
+fn lockout() -> Duration {
+    let secs = std::env::var("LOGIN_LOCKOUT_SECS").ok().and_then(|v| v.parse().ok()).unwrap_or(900);
+    Duration::from_secs(secs)
+}
+
+fn failures_key(subject: &Subject) -> String {
+    format!("login_failures:{}", subject.id())
+}
+
+fn block_key(subject: &Subject) -> String {
+    format!("login_block:{}", subject.id())
+}
+
+/// Checks whether the username or the client IP is currently backing off or locked
+pub async fn check(cache: &Cache, username: &str, ip: &str) -> anyhow::Result<Option<Blocked>> {
+    let now = Utc::now().timestamp();
+    let mut blocked: Option<Blocked> = None;
+    for subject in [Subject::User(username), Subject::Ip(ip)] {
+        let Some(raw) = cache.get(&block_key(&subject)).await? else {
+            continue;
+        };
+        let Ok(block) = serde_json::from_str::<Block>(&raw) else {
+            continue;
+        };
+        if block.until > now && blocked.as_ref().is_none_or(|b| block.until - now > b.retry_after) {
+            blocked = Some(Blocked { retry_after: block.until - now, locked: block.locked });
+        }
+    }
+    Ok(blocked)
+}
+
+/// Counts a failed attempt against both subjects and applies backoff or lockout.
+/// Returns the subjects that were locked by this attempt so the caller can audit them.
+pub async fn record_failure<'a>(cache: &Cache, username: &'a str, ip: &'a str) -> anyhow::Result<Vec<Subject<'a>>> {
+    let lockout = lockout();
+    let mut locked = Vec::new();
+    for subject in [Subject::User(username), Subject::Ip(ip)] {
+        let failures = cache.incr(&failures_key(&subject), lockout).await?;
+        let (secs, is_lock) = if failures >= subject.max_failures() {
+            (lockout.as_secs() as i64, true)
+        } else {
+            // 1s, 2s, 4s ... 指数退避
+            (2i64.saturating_pow(failures.saturating_sub(1) as u32).min(MAX_BACKOFF_SECS), false)
+        };
+
+        let block = Block { until: Utc::now().timestamp() + secs, locked: is_lock };
+        cache.set(&block_key(&subject), &serde_json::to_string(&block)?, Duration::from_secs(secs as u64)).await?;
+        if is_lock {
+            // 锁定后重新计数
+            cache.delete(&failures_key(&subject)).await?;
+            locked.push(subject);
+        }
+    }
+    Ok(locked)
+}
+
+/// A successful login clears the account's counters; the IP counter keeps running
+pub async fn record_success(cache: &Cache, username: &str) -> anyhow::Result<()> {
+    unlock(cache, username).await
+}
+
+pub async fn unlock(cache: &Cache, username: &str) -> anyhow::Result<()> {
+    let subject = Subject::User(username);
+    cache.delete(&failures_key(&subject)).await?;
+    cache.delete(&block_key(&subject)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 使用默认配置：账号 5 次失败锁定 900 秒，IP 为 20 次
+    async fn retry_after(cache: &Cache, username: &str, ip: &str) -> Option<(i64, bool)> {
+        check(cache, username, ip).await.unwrap().map(|b| (b.retry_after, b.locked))
+    }
+
+    #[tokio::test]
+    async fn backs_off_exponentially_before_locking() {
+        let cache = Cache::memory();
+        assert!(retry_after(&cache, "alice", "10.0.0.1").await.is_none());
+
+        for expected in [1, 2, 4, 8] {
+            let locked = record_failure(&cache, "alice", "10.0.0.1").await.unwrap();
+            assert!(locked.is_empty());
+            let (secs, locked) = retry_after(&cache, "alice", "10.0.0.1").await.unwrap();
+            assert!((expected - 1..=expected).contains(&secs), "expected {}s, got {}s", expected, secs);
+            assert!(!locked);
+        }
+
+        let locked = record_failure(&cache, "alice", "10.0.0.1").await.unwrap();
+        assert!(matches!(locked[..], [Subject::User("alice")]));
+        let (secs, locked) = retry_after(&cache, "alice", "10.0.0.1").await.unwrap();
+        assert!(secs > 890);
+        assert!(locked);
+    }
+
+    #[tokio::test]
+    async fn usernames_are_counted_case_insensitively() {
+        let cache = Cache::memory();
+        for name in ["Bob", "bob", "BOB", "bOb"] {
+            record_failure(&cache, name, "10.0.0.2").await.unwrap();
+        }
+        let locked = record_failure(&cache, "bob", "10.0.0.2").await.unwrap();
+        assert!(matches!(locked[..], [Subject::User(_)]));
+        assert!(retry_after(&cache, "BOB", "10.0.0.3").await.unwrap().1);
+    }
+
+    #[tokio::test]
+    async fn locks_an_ip_that_tries_many_accounts() {
+        let cache = Cache::memory();
+        let mut ip_locked = false;
+        for i in 0..max_failures() * IP_FACTOR {
+            let username = format!("user{}", i);
+            let locked = record_failure(&cache, &username, "10.0.0.4").await.unwrap();
+            ip_locked = matches!(locked[..], [Subject::Ip("10.0.0.4")]);
+        }
+        assert!(ip_locked);
+        assert!(retry_after(&cache, "someone-else", "10.0.0.4").await.unwrap().1);
+        assert!(retry_after(&cache, "someone-else", "10.0.0.5").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn success_clears_the_account_but_not_the_ip() {
+        let cache = Cache::memory();
+        for _ in 0..max_failures() {
+            record_failure(&cache, "carol", "10.0.0.6").await.unwrap();
+        }
+        record_success(&cache, "carol").await.unwrap();
+        assert!(retry_after(&cache, "carol", "10.0.0.7").await.is_none());
+        // IP 仍处于退避中
+        assert!(retry_after(&cache, "carol", "10.0.0.6").await.is_some());
+    }
+}
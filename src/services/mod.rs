@@ -1,2 +1,19 @@
 pub mod steam_api;
 pub mod verification_worker;
+pub mod ban_scope;
+pub mod cache;
+pub mod session;
+pub mod totp;
+pub mod login_guard;
+pub mod api_key;
+pub mod steam_openid;
+pub mod enforcement;
+pub mod expiry_worker;
+pub mod escalation;
+pub mod evidence;
+pub mod revision;
+pub mod identity;
+pub mod ip_match;
+pub mod ban_search;
+pub mod import;
+pub mod ban_export;
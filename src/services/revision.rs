@@ -0,0 +1,97 @@
+use sqlx::MySqlPool;
+use crate::models::ban::Ban;
+use crate::models::revision::{BanRevision, BanRevisionRow, BanSnapshot, FieldChange};
+
+/// Current state of a ban, including its scope
+pub async fn snapshot(pool: &MySqlPool, ban_id: i64) -> Result<Option<BanSnapshot>, sqlx::Error> {
+    let Some(mut ban) = sqlx::query_as::<_, Ban>("SELECT * FROM bans WHERE id = ?")
+        .bind(ban_id)
+        .fetch_optional(pool)
+        .await?
+    else {
+        return Ok(None);
+    };
+    ban.scope_group_ids = sqlx::query_scalar("SELECT group_id FROM ban_server_groups WHERE ban_id = ?")
+        .bind(ban_id)
+        .fetch_all(pool)
+        .await?;
+    Ok(Some(BanSnapshot::of(&ban)))
+}
+
+/// Records the ban's current state as a new revision. `before` is the state prior to the
+/// change (None for newly created bans); nothing is written when no field changed.
+pub async fn record(
+    pool: &MySqlPool,
+    ban_id: i64,
+    before: Option<&BanSnapshot>,
+    change_type: &str,
+    changed_by: &str,
+    note: Option<&str>,
+) -> Result<Vec<FieldChange>, sqlx::Error> {
+    let Some(after) = snapshot(pool, ban_id).await? else {
+        return Ok(Vec::new());
+    };
+    let changes = before.map(|b| b.diff(&after)).unwrap_or_default();
+    if before.is_some() && changes.is_empty() {
+        return Ok(changes);
+    }
+
+    // 版本号在插入时按该封禁已有的最大版本号递增，(ban_id, revision) 唯一约束兜底并发
+    sqlx::query(
+        "INSERT INTO ban_revisions (ban_id, revision, change_type, changed_by, note, changes, snapshot) \
+         SELECT ?, COALESCE(MAX(revision), 0) + 1, ?, ?, ?, ?, ? FROM ban_revisions WHERE ban_id = ?"
+    )
+    .bind(ban_id)
+    .bind(change_type)
+    .bind(changed_by)
+    .bind(note.map(|n| n.chars().take(255).collect::<String>()))
+    .bind(serde_json::to_string(&changes).unwrap_or_else(|_| "[]".to_string()))
+    .bind(serde_json::to_string(&after).unwrap_or_else(|_| "{}".to_string()))
+    .bind(ban_id)
+    .execute(pool)
+    .await?;
+
+    Ok(changes)
+}
+
+/// Like [`record`] but only logs failures; history must never block the change itself
+pub async fn track(pool: &MySqlPool, ban_id: i64, before: Option<&BanSnapshot>, change_type: &str, changed_by: &str) -> Vec<FieldChange> {
+    match record(pool, ban_id, before, change_type, changed_by, None).await {
+        Ok(changes) => changes,
+        Err(e) => {
+            tracing::error!("Failed to record revision for ban {}: {}", ban_id, e);
+            Vec::new()
+        }
+    }
+}
+
+pub async fn list(pool: &MySqlPool, ban_id: i64) -> Result<Vec<BanRevision>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, BanRevisionRow>("SELECT * FROM ban_revisions WHERE ban_id = ? ORDER BY revision DESC")
+        .bind(ban_id)
+        .fetch_all(pool)
+        .await?;
+    rows.into_iter()
+        .map(|r| BanRevision::try_from(r).map_err(|e| sqlx::Error::Decode(Box::new(e))))
+        .collect()
+}
+
+pub async fn find(pool: &MySqlPool, ban_id: i64, revision: i32) -> Result<Option<BanRevision>, sqlx::Error> {
+    let row = sqlx::query_as::<_, BanRevisionRow>("SELECT * FROM ban_revisions WHERE ban_id = ? AND revision = ?")
+        .bind(ban_id)
+        .bind(revision)
+        .fetch_optional(pool)
+        .await?;
+    row.map(|r| BanRevision::try_from(r).map_err(|e| sqlx::Error::Decode(Box::new(e))))
+        .transpose()
+}
+
+/// One-line summary for the audit log, e.g. `duration: "permanent" -> "1d"`
+pub fn summarize(changes: &[FieldChange]) -> String {
+    if changes.is_empty() {
+        return "No changes".to_string();
+    }
+    changes.iter()
+        .map(|c| format!("{}: {} -> {}", c.field, c.before, c.after))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
@@ -0,0 +1,272 @@
+use std::time::Duration;
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+use crate::models::session::{RequiredAction, Session};
+use crate::services::cache::Cache;
+
+fn session_key(id: &str) -> String {
+    format!("session:{}", id)
+}
+
+fn admin_key(admin_id: i64) -> String {
+    format!("admin_sessions:{}", admin_id)
+}
+
+fn refresh_key(id: &str) -> String {
+    format!("session_refresh:{}", id)
+}
+
+fn used_refresh_key(id: &str) -> String {
+    format!("session_refresh_used:{}", id)
+}
+
+/// How many already-rotated refresh token hashes are remembered per session for reuse detection
+const USED_REFRESH_TOKENS_KEPT: usize = 16;
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+fn remaining_ttl(session: &Session) -> Duration {
+    (session.expires_at - Utc::now()).to_std().unwrap_or(Duration::from_secs(1))
+}
+
+pub enum RefreshOutcome {
+    /// Token was valid; carries the session and its new refresh token
+    Rotated(Session, String),
+    /// Token was an earlier, already rotated token of a live session, so the session was revoked
+    Reused(Session),
+    Invalid,
+}
+
+/// Creates a server-side session; its id goes into the JWT `sid` claim.
+pub async fn create(
+    cache: &Cache,
+    admin_id: i64,
+    username: &str,
+    ip: Option<String>,
+    user_agent: Option<String>,
+    required_action: Option<RequiredAction>,
+    ttl: Duration,
+) -> anyhow::Result<Session> {
+    let now = Utc::now();
+    let session = Session {
+        id: Uuid::new_v4().simple().to_string(),
+        admin_id,
+        username: username.to_string(),
+        ip,
+        user_agent,
+        created_at: now,
+        expires_at: now + chrono::Duration::from_std(ttl)?,
+        required_action,
+        current: false,
+    };
+
+    cache.set(&session_key(&session.id), &serde_json::to_string(&session)?, ttl).await?;
+    cache.set_add(&admin_key(admin_id), &session.id, ttl).await?;
+    Ok(session)
+}
+
+/// Returns the session if it exists and has not been revoked
+pub async fn get(cache: &Cache, id: &str) -> anyhow::Result<Option<Session>> {
+    if id.is_empty() {
+        return Ok(None);
+    }
+    match cache.get(&session_key(id)).await? {
+        Some(raw) => Ok(serde_json::from_str(&raw).ok()),
+        None => Ok(None),
+    }
+}
+
+/// Issues a new refresh token for the session, replacing the previous one.
+/// Only its hash is stored.
+pub async fn issue_refresh_token(cache: &Cache, session: &Session) -> anyhow::Result<String> {
+    let secret: [u8; 32] = rand::random();
+    let token = format!("{}.{}", session.id, hex::encode(secret));
+    cache.set(&refresh_key(&session.id), &hash_token(&token), remaining_ttl(session)).await?;
+    Ok(token)
+}
+
+/// Consumes a refresh token and issues the next one.
+/// Presenting a token that was already rotated revokes the whole session (reuse detection).
+/// Any other unknown token is just rejected: the session id is visible in access tokens,
+/// so a guessed secret must not be able to log the owner out.
+pub async fn rotate_refresh_token(cache: &Cache, token: &str) -> anyhow::Result<RefreshOutcome> {
+    let Some((sid, _)) = token.split_once('.') else {
+        return Ok(RefreshOutcome::Invalid);
+    };
+    let Some(session) = get(cache, sid).await? else {
+        return Ok(RefreshOutcome::Invalid);
+    };
+    let presented = hash_token(token);
+
+    if cache.get(&refresh_key(sid)).await?.as_deref() == Some(presented.as_str()) {
+        // take() makes sure two concurrent refreshes cannot both rotate the same token
+        if cache.take(&refresh_key(sid)).await?.as_deref() == Some(presented.as_str()) {
+            remember_used(cache, &session, &presented).await?;
+            let next = issue_refresh_token(cache, &session).await?;
+            return Ok(RefreshOutcome::Rotated(session, next));
+        }
+    }
+
+    let used = cache.get(&used_refresh_key(sid)).await?.unwrap_or_default();
+    if used.split_whitespace().any(|h| h == presented) {
+        revoke(cache, &session).await?;
+        return Ok(RefreshOutcome::Reused(session));
+    }
+    Ok(RefreshOutcome::Invalid)
+}
+
+/// Keeps the last USED_REFRESH_TOKENS_KEPT rotated token hashes of a session
+async fn remember_used(cache: &Cache, session: &Session, hash: &str) -> anyhow::Result<()> {
+    let key = used_refresh_key(&session.id);
+    let previous = cache.get(&key).await?.unwrap_or_default();
+    let mut hashes: Vec<&str> = previous.split_whitespace().collect();
+    hashes.push(hash);
+    let keep = &hashes[hashes.len().saturating_sub(USED_REFRESH_TOKENS_KEPT)..];
+    cache.set(&key, &keep.join(" "), remaining_ttl(session)).await
+}
+
+/// Stores changes to a session without extending its lifetime
+pub async fn update(cache: &Cache, session: &Session) -> anyhow::Result<()> {
+    cache.set(&session_key(&session.id), &serde_json::to_string(session)?, remaining_ttl(session)).await
+}
+
+pub async fn revoke(cache: &Cache, session: &Session) -> anyhow::Result<()> {
+    cache.delete(&session_key(&session.id)).await?;
+    cache.delete(&refresh_key(&session.id)).await?;
+    cache.delete(&used_refresh_key(&session.id)).await?;
+    cache.set_remove(&admin_key(session.admin_id), &session.id).await?;
+    Ok(())
+}
+
+/// Active sessions of an admin; ids whose session already expired are pruned
+pub async fn list_for_admin(cache: &Cache, admin_id: i64) -> anyhow::Result<Vec<Session>> {
+    let mut sessions = Vec::new();
+    for id in cache.set_members(&admin_key(admin_id)).await? {
+        match get(cache, &id).await? {
+            Some(s) => sessions.push(s),
+            None => cache.set_remove(&admin_key(admin_id), &id).await?,
+        }
+    }
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+    Ok(sessions)
+}
+
+/// Force-logout: revokes every session of an admin, returns how many were active
+pub async fn revoke_all_for_admin(cache: &Cache, admin_id: i64) -> anyhow::Result<usize> {
+    let sessions = list_for_admin(cache, admin_id).await?;
+    for s in &sessions {
+        revoke(cache, s).await?;
+    }
+    cache.delete(&admin_key(admin_id)).await?;
+    Ok(sessions.len())
+}
+
+/// Called after an admin's account, role or grants change so stale tokens stop working.
+/// Failures are only logged; the triggering change has already been committed.
+pub async fn invalidate_admin(cache: &Cache, admin_id: i64) {
+    match revoke_all_for_admin(cache, admin_id).await {
+        Ok(n) if n > 0 => tracing::info!("Revoked {} session(s) of admin {}", n, admin_id),
+        Ok(_) => {}
+        Err(e) => tracing::error!("Failed to revoke sessions of admin {}: {}", admin_id, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TTL: Duration = Duration::from_secs(3600);
+
+    async fn login(cache: &Cache, admin_id: i64) -> (Session, String) {
+        let session = create(cache, admin_id, "admin", None, None, None, TTL).await.unwrap();
+        let token = issue_refresh_token(cache, &session).await.unwrap();
+        (session, token)
+    }
+
+    #[tokio::test]
+    async fn rotates_refresh_tokens() {
+        let cache = Cache::memory();
+        let (session, first) = login(&cache, 1).await;
+
+        let second = match rotate_refresh_token(&cache, &first).await.unwrap() {
+            RefreshOutcome::Rotated(s, next) => {
+                assert_eq!(s.id, session.id);
+                next
+            }
+            _ => panic!("expected rotation"),
+        };
+        assert_ne!(first, second);
+        assert!(second.starts_with(&format!("{}.", session.id)));
+        assert!(matches!(rotate_refresh_token(&cache, &second).await.unwrap(), RefreshOutcome::Rotated(..)));
+    }
+
+    #[tokio::test]
+    async fn reusing_a_rotated_token_revokes_the_session() {
+        let cache = Cache::memory();
+        let (session, first) = login(&cache, 1).await;
+        let RefreshOutcome::Rotated(_, second) = rotate_refresh_token(&cache, &first).await.unwrap() else {
+            panic!("expected rotation");
+        };
+
+        assert!(matches!(rotate_refresh_token(&cache, &first).await.unwrap(), RefreshOutcome::Reused(_)));
+        assert!(get(&cache, &session.id).await.unwrap().is_none());
+        assert!(matches!(rotate_refresh_token(&cache, &second).await.unwrap(), RefreshOutcome::Invalid));
+    }
+
+    #[tokio::test]
+    async fn forged_tokens_leave_the_session_alone() {
+        let cache = Cache::memory();
+        let (session, token) = login(&cache, 1).await;
+
+        for forged in [format!("{}.garbage", session.id), format!("{}.", session.id), "no-dot".to_string(), "unknown.secret".to_string()] {
+            assert!(matches!(rotate_refresh_token(&cache, &forged).await.unwrap(), RefreshOutcome::Invalid), "{}", forged);
+        }
+        assert!(get(&cache, &session.id).await.unwrap().is_some());
+        assert!(matches!(rotate_refresh_token(&cache, &token).await.unwrap(), RefreshOutcome::Rotated(..)));
+    }
+
+    #[tokio::test]
+    async fn remembers_only_recent_rotated_tokens() {
+        let cache = Cache::memory();
+        let (session, mut token) = login(&cache, 1).await;
+        let oldest = token.clone();
+        for _ in 0..=USED_REFRESH_TOKENS_KEPT {
+            let RefreshOutcome::Rotated(_, next) = rotate_refresh_token(&cache, &token).await.unwrap() else {
+                panic!("expected rotation");
+            };
+            token = next;
+        }
+
+        // Forgotten after USED_REFRESH_TOKENS_KEPT rotations: rejected, but no longer revokes
+        assert!(matches!(rotate_refresh_token(&cache, &oldest).await.unwrap(), RefreshOutcome::Invalid));
+        assert!(get(&cache, &session.id).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn revokes_every_session_of_an_admin() {
+        let cache = Cache::memory();
+        let (a, token) = login(&cache, 1).await;
+        let (b, _) = login(&cache, 1).await;
+        let (other, _) = login(&cache, 2).await;
+
+        let listed: Vec<String> = list_for_admin(&cache, 1).await.unwrap().into_iter().map(|s| s.id).collect();
+        assert_eq!(listed.len(), 2);
+        assert!(listed.contains(&a.id) && listed.contains(&b.id));
+
+        assert_eq!(revoke_all_for_admin(&cache, 1).await.unwrap(), 2);
+        assert!(get(&cache, &a.id).await.unwrap().is_none());
+        assert!(get(&cache, &b.id).await.unwrap().is_none());
+        assert!(get(&cache, &other.id).await.unwrap().is_some());
+        assert!(matches!(rotate_refresh_token(&cache, &token).await.unwrap(), RefreshOutcome::Invalid));
+        assert!(list_for_admin(&cache, 1).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn empty_session_id_is_never_found() {
+        let cache = Cache::memory();
+        assert!(get(&cache, "").await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use regex::Regex;
+use reqwest::Url;
+use uuid::Uuid;
+use crate::services::cache::Cache;
+
+const DEFAULT_ENDPOINT: &str = "https://steamcommunity.com/openid/login";
+const IDENTIFIER_SELECT: &str = "http://specs.openid.net/auth/2.0/identifier_select";
+const OPENID_NS: &str = "http://specs.openid.net/auth/2.0";
+const NONCE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Steam OpenID 配置。STEAM_OPENID_ENDPOINT 可指向本地桩服务用于离线测试。
+pub struct SteamOpenId {
+    pub endpoint: String,
+    /// Panel page Steam redirects back to; it forwards the query to `/api/auth/steam/verify`
+    pub return_url: String,
+    pub realm: String,
+}
+
+impl SteamOpenId {
+    /// None when `STEAM_OPENID_RETURN_URL` is not configured
+    pub fn from_env() -> Option<Self> {
+        let return_url = std::env::var("STEAM_OPENID_RETURN_URL").ok().filter(|v| !v.is_empty())?;
+        let endpoint = std::env::var("STEAM_OPENID_ENDPOINT").unwrap_or_else(|_| DEFAULT_ENDPOINT.to_string());
+        let realm = match std::env::var("STEAM_OPENID_REALM") {
+            Ok(realm) if !realm.is_empty() => realm,
+            _ => Url::parse(&return_url).ok()?.origin().ascii_serialization(),
+        };
+        Some(SteamOpenId { endpoint, return_url, realm })
+    }
+
+    /// Builds the Steam sign-in URL; the nonce in `return_to` is single-use
+    pub async fn login_url(&self, cache: &Cache) -> anyhow::Result<String> {
+        let nonce = Uuid::new_v4().simple().to_string();
+        cache.set(&nonce_key(&nonce), "1", NONCE_TTL).await?;
+
+        let mut return_to = Url::parse(&self.return_url)?;
+        return_to.query_pairs_mut().append_pair("nonce", &nonce);
+
+        let url = Url::parse_with_params(&self.endpoint, &[
+            ("openid.ns", OPENID_NS),
+            ("openid.mode", "checkid_setup"),
+            ("openid.return_to", return_to.as_str()),
+            ("openid.realm", self.realm.as_str()),
+            ("openid.identity", IDENTIFIER_SELECT),
+            ("openid.claimed_id", IDENTIFIER_SELECT),
+        ])?;
+        Ok(url.into())
+    }
+
+    /// Verifies a positive assertion with the provider and returns the SteamID64.
+    /// The error string is safe to show to the client.
+    pub async fn verify(&self, client: &reqwest::Client, cache: &Cache, params: &HashMap<String, String>) -> Result<String, String> {
+        let get = |key: &str| params.get(key).map(String::as_str);
+
+        if get("openid.mode") != Some("id_res") {
+            return Err("Not a positive assertion".to_string());
+        }
+        if get("openid.op_endpoint") != Some(self.endpoint.as_str()) {
+            return Err("Unexpected OpenID provider".to_string());
+        }
+
+        let signed: Vec<&str> = get("openid.signed").unwrap_or_default().split(',').collect();
+        if !["claimed_id", "return_to", "op_endpoint"].iter().all(|f| signed.contains(f)) {
+            return Err("Assertion does not sign the required fields".to_string());
+        }
+
+        let return_to = get("openid.return_to").ok_or("Missing return_to")?;
+        if !return_to.starts_with(&self.return_url) {
+            return Err("return_to mismatch".to_string());
+        }
+        let nonce = Url::parse(return_to).ok()
+            .and_then(|u| u.query_pairs().find(|(k, _)| k == "nonce").map(|(_, v)| v.into_owned()))
+            .ok_or("Missing nonce")?;
+
+        let claimed_id = get("openid.claimed_id").ok_or("Missing claimed_id")?;
+        let re = Regex::new(r"/openid/id/(\d{17})$").unwrap();
+        let steam_id = re.captures(claimed_id)
+            .map(|c| c[1].to_string())
+            .ok_or("Invalid claimed_id")?;
+
+        // 只能使用一次，防止回放
+        match cache.take(&nonce_key(&nonce)).await {
+            Ok(Some(_)) => {}
+            Ok(None) => return Err("Login request expired or already used".to_string()),
+            Err(e) => return Err(e.to_string()),
+        }
+
+        let mut form: Vec<(&str, &str)> = params.iter()
+            .filter(|(k, _)| k.starts_with("openid.") && k.as_str() != "openid.mode")
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+        form.push(("openid.mode", "check_authentication"));
+
+        let body = client.post(&self.endpoint)
+            .form(&form)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to contact OpenID provider: {}", e))?
+            .text()
+            .await
+            .map_err(|e| format!("Failed to read OpenID provider response: {}", e))?;
+
+        if body.lines().any(|l| l.trim() == "is_valid:true") {
+            Ok(steam_id)
+        } else {
+            Err("Assertion rejected by OpenID provider".to_string())
+        }
+    }
+}
+
+fn nonce_key(nonce: &str) -> String {
+    format!("steam_openid:{}", nonce)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::post, Form, Router};
+
+    const STEAM_ID: &str = "76561197960287930";
+
+    /// In-process provider like `bin/steam_openid_stub.rs`; only assertions signed `good` are valid
+    async fn provider() -> String {
+        async fn check_authentication(Form(params): Form<HashMap<String, String>>) -> String {
+            let valid = params.get("openid.mode").map(String::as_str) == Some("check_authentication")
+                && params.get("openid.sig").map(String::as_str) == Some("good");
+            format!("ns:{}\nis_valid:{}\n", OPENID_NS, valid)
+        }
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}/openid/login", listener.local_addr().unwrap());
+        let app = Router::new().route("/openid/login", post(check_authentication));
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        endpoint
+    }
+
+    fn openid(endpoint: &str) -> SteamOpenId {
+        SteamOpenId {
+            endpoint: endpoint.to_string(),
+            return_url: "https://panel.example.com/steam-callback".to_string(),
+            realm: "https://panel.example.com".to_string(),
+        }
+    }
+
+    /// Starts a login and answers it the way Steam would
+    async fn assertion(openid: &SteamOpenId, cache: &Cache, sig: &str) -> HashMap<String, String> {
+        let login = Url::parse(&openid.login_url(cache).await.unwrap()).unwrap();
+        let query: HashMap<String, String> = login.query_pairs().into_owned().collect();
+        let claimed_id = format!("https://steamcommunity.com/openid/id/{}", STEAM_ID);
+        [
+            ("openid.ns", OPENID_NS),
+            ("openid.mode", "id_res"),
+            ("openid.op_endpoint", openid.endpoint.as_str()),
+            ("openid.claimed_id", claimed_id.as_str()),
+            ("openid.identity", claimed_id.as_str()),
+            ("openid.return_to", query["openid.return_to"].as_str()),
+            ("openid.response_nonce", "2026-10-16T00:00:00Zstub"),
+            ("openid.assoc_handle", "stub"),
+            ("openid.signed", "signed,op_endpoint,claimed_id,identity,return_to,response_nonce,assoc_handle"),
+            ("openid.sig", sig),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+    }
+
+    #[tokio::test]
+    async fn accepts_a_valid_assertion_once() {
+        let (cache, client) = (Cache::memory(), reqwest::Client::new());
+        let openid = openid(&provider().await);
+        let params = assertion(&openid, &cache, "good").await;
+
+        assert_eq!(openid.verify(&client, &cache, &params).await, Ok(STEAM_ID.to_string()));
+        assert_eq!(
+            openid.verify(&client, &cache, &params).await,
+            Err("Login request expired or already used".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_assertions_the_provider_does_not_confirm() {
+        let (cache, client) = (Cache::memory(), reqwest::Client::new());
+        let openid = openid(&provider().await);
+        let params = assertion(&openid, &cache, "forged").await;
+
+        assert_eq!(
+            openid.verify(&client, &cache, &params).await,
+            Err("Assertion rejected by OpenID provider".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_tampered_assertions_before_asking_the_provider() {
+        let (cache, client) = (Cache::memory(), reqwest::Client::new());
+        // 端点不可达：这些断言必须在联系提供方之前就被拒绝
+        let openid = openid("http://127.0.0.1:9/openid/login");
+        let params = assertion(&openid, &cache, "good").await;
+
+        let cases = [
+            ("openid.mode", "cancel", "Not a positive assertion"),
+            ("openid.op_endpoint", "https://evil.example.com/openid/login", "Unexpected OpenID provider"),
+            ("openid.signed", "signed,claimed_id,identity", "Assertion does not sign the required fields"),
+            ("openid.return_to", "https://evil.example.com/steam-callback?nonce=x", "return_to mismatch"),
+            ("openid.return_to", "https://panel.example.com/steam-callback", "Missing nonce"),
+            ("openid.claimed_id", "https://steamcommunity.com/openid/id/123", "Invalid claimed_id"),
+        ];
+        for (key, value, error) in cases {
+            let mut tampered = params.clone();
+            tampered.insert(key.to_string(), value.to_string());
+            assert_eq!(openid.verify(&client, &cache, &tampered).await, Err(error.to_string()), "{}", key);
+        }
+    }
+}
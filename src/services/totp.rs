@@ -0,0 +1,298 @@
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use uuid::Uuid;
+use crate::services::cache::Cache;
+
+/// RFC 6238 defaults, which every authenticator app supports
+const STEP_SECS: i64 = 30;
+const DIGITS: u32 = 6;
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+/// Recovery codes avoid characters that are easy to misread (0/o, 1/l/i)
+const RECOVERY_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+const CHALLENGE_TTL: Duration = Duration::from_secs(5 * 60);
+const CHALLENGE_MAX_ATTEMPTS: u32 = 5;
+
+pub fn generate_secret() -> String {
+    let bytes: [u8; 20] = rand::random();
+    base32_encode(&bytes)
+}
+
+pub fn otpauth_uri(secret: &str, account: &str, issuer: &str) -> String {
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        percent_encode(issuer), percent_encode(account), secret, percent_encode(issuer), DIGITS, STEP_SECS
+    )
+}
+
+/// Checks a 6-digit code against the secret, allowing one step of clock drift.
+/// Returns the matched time step so callers can reject replays.
+pub fn verify(secret: &str, code: &str, now: i64) -> Option<i64> {
+    let code = code.trim();
+    if code.len() != DIGITS as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let code: u32 = code.parse().ok()?;
+    let key = base32_decode(secret)?;
+    let step = now / STEP_SECS;
+    (step - 1..=step + 1).find(|s| hotp(&key, *s as u64) == code)
+}
+
+/// Marks a time step as used; returns false if the code was already used
+pub async fn consume_step(cache: &Cache, admin_id: i64, step: i64) -> anyhow::Result<bool> {
+    let key = format!("totp_used:{}:{}", admin_id, step);
+    if cache.get(&key).await?.is_some() {
+        return Ok(false);
+    }
+    cache.set(&key, "1", Duration::from_secs(STEP_SECS as u64 * 3)).await?;
+    Ok(true)
+}
+
+fn hotp(key: &[u8], counter: u64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(&counter.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([hash[offset] & 0x7f, hash[offset + 1], hash[offset + 2], hash[offset + 3]]);
+    binary % 10u32.pow(DIGITS)
+}
+
+fn base32_encode(data: &[u8]) -> String {
+    let mut out = String::new();
+    let (mut buffer, mut bits) = (0u32, 0u32);
+    for &byte in data {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            out.push(BASE32_ALPHABET[((buffer >> (bits - 5)) & 31) as usize] as char);
+            bits -= 5;
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    out
+}
+
+fn base32_decode(input: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    let (mut buffer, mut bits) = (0u32, 0u32);
+    for c in input.chars().filter(|c| *c != '=' && !c.is_whitespace()) {
+        let value = BASE32_ALPHABET.iter().position(|&a| a as char == c.to_ascii_uppercase())? as u32;
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            out.push((buffer >> (bits - 8)) as u8);
+            bits -= 8;
+        }
+    }
+    Some(out)
+}
+
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Generates one-time recovery codes in the form `xxxxx-xxxxx`
+pub fn generate_recovery_codes() -> Vec<String> {
+    use rand::Rng;
+    let mut rng = rand::thread_rng();
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let chars: String = (0..10)
+                .map(|_| RECOVERY_ALPHABET[rng.gen_range(0..RECOVERY_ALPHABET.len())] as char)
+                .collect();
+            format!("{}-{}", &chars[..5], &chars[5..])
+        })
+        .collect()
+}
+
+/// Recovery codes are compared case-insensitively and without the dash
+pub fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    hex::encode(Sha256::digest(normalized.as_bytes()))
+}
+
+/// Marks a matching unused recovery code as used; returns whether one matched
+pub async fn use_recovery_code(pool: &sqlx::MySqlPool, admin_id: i64, code: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("UPDATE admin_recovery_codes SET used_at = NOW() WHERE admin_id = ? AND code_hash = ? AND used_at IS NULL LIMIT 1")
+        .bind(admin_id)
+        .bind(hash_recovery_code(code))
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn role_requires_2fa(pool: &sqlx::MySqlPool, role: &str) -> Result<bool, sqlx::Error> {
+    let required: Option<bool> = sqlx::query_scalar("SELECT require_2fa FROM role_settings WHERE role = ?")
+        .bind(role)
+        .fetch_optional(pool)
+        .await?;
+    Ok(required.unwrap_or(false))
+}
+
+#[derive(Serialize, Deserialize)]
+struct Challenge {
+    admin_id: i64,
+    attempts: u32,
+    /// Unix time the challenge was due to expire when it was created
+    #[serde(default)]
+    expires_at: i64,
+}
+
+fn challenge_key(token: &str) -> String {
+    format!("mfa_challenge:{}", token)
+}
+
+/// Starts the second login step after the password was accepted.
+/// The returned token only identifies the pending login; it grants no access.
+pub async fn create_challenge(cache: &Cache, admin_id: i64) -> anyhow::Result<String> {
+    let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    let expires_at = chrono::Utc::now().timestamp() + CHALLENGE_TTL.as_secs() as i64;
+    let challenge = Challenge { admin_id, attempts: 0, expires_at };
+    cache.set(&challenge_key(&token), &serde_json::to_string(&challenge)?, CHALLENGE_TTL).await?;
+    Ok(token)
+}
+
+/// Admin id of a pending login, or None if it expired or ran out of attempts
+pub async fn challenge_admin(cache: &Cache, token: &str) -> anyhow::Result<Option<i64>> {
+    Ok(match cache.get(&challenge_key(token)).await? {
+        Some(raw) => serde_json::from_str::<Challenge>(&raw).ok().map(|c| c.admin_id),
+        None => None,
+    })
+}
+
+/// Counts a wrong code; the challenge is dropped after too many attempts.
+/// The challenge keeps its original expiry so retries cannot extend it.
+pub async fn fail_challenge(cache: &Cache, token: &str) -> anyhow::Result<()> {
+    let Some(raw) = cache.get(&challenge_key(token)).await? else {
+        return Ok(());
+    };
+    let mut challenge: Challenge = serde_json::from_str(&raw)?;
+    challenge.attempts += 1;
+    let remaining = challenge.expires_at - chrono::Utc::now().timestamp();
+    if challenge.attempts >= CHALLENGE_MAX_ATTEMPTS || remaining <= 0 {
+        cache.delete(&challenge_key(token)).await
+    } else {
+        let ttl = Duration::from_secs(remaining as u64).min(CHALLENGE_TTL);
+        cache.set(&challenge_key(token), &serde_json::to_string(&challenge)?, ttl).await
+    }
+}
+
+pub async fn finish_challenge(cache: &Cache, token: &str) -> anyhow::Result<()> {
+    cache.delete(&challenge_key(token)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// RFC 4226 appendix D test secret
+    const RFC_KEY: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn hotp_matches_rfc4226_vectors() {
+        let expected = [755224, 287082, 359152, 969429, 338314, 254676, 287922, 162583, 399871, 520489];
+        for (counter, code) in expected.into_iter().enumerate() {
+            assert_eq!(hotp(RFC_KEY, counter as u64), code, "counter {}", counter);
+        }
+    }
+
+    #[test]
+    fn verify_matches_rfc6238_vectors() {
+        // RFC 6238 appendix B (SHA1), truncated to the last 6 digits
+        let secret = base32_encode(RFC_KEY);
+        let cases = [
+            (59, "287082"),
+            (1111111109, "081804"),
+            (1111111111, "050471"),
+            (1234567890, "005924"),
+            (2000000000, "279037"),
+        ];
+        for (time, code) in cases {
+            assert_eq!(verify(&secret, code, time), Some(time / STEP_SECS), "T={}", time);
+        }
+    }
+
+    #[test]
+    fn verify_allows_one_step_of_drift() {
+        let secret = base32_encode(RFC_KEY);
+        assert_eq!(verify(&secret, "081804", 1111111109 + STEP_SECS), Some(1111111109 / STEP_SECS));
+        assert_eq!(verify(&secret, "081804", 1111111109 - STEP_SECS), Some(1111111109 / STEP_SECS));
+        assert_eq!(verify(&secret, "081804", 1111111109 + 2 * STEP_SECS), None);
+        assert_eq!(verify(&secret, " 081804 ", 1111111109), Some(1111111109 / STEP_SECS));
+        assert_eq!(verify(&secret, "81804", 1111111109), None);
+        assert_eq!(verify(&secret, "08180a", 1111111109), None);
+    }
+
+    #[test]
+    fn base32_matches_rfc4648_vectors() {
+        let cases = [
+            ("", ""),
+            ("f", "MY"),
+            ("fo", "MZXQ"),
+            ("foo", "MZXW6"),
+            ("foob", "MZXW6YQ"),
+            ("fooba", "MZXW6YTB"),
+            ("foobar", "MZXW6YTBOI"),
+        ];
+        for (plain, encoded) in cases {
+            assert_eq!(base32_encode(plain.as_bytes()), encoded);
+            assert_eq!(base32_decode(encoded).as_deref(), Some(plain.as_bytes()));
+        }
+        assert_eq!(base32_encode(RFC_KEY), "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+    }
+
+    #[test]
+    fn base32_decode_accepts_user_input() {
+        assert_eq!(base32_decode("mzxw6ytboi").as_deref(), Some(&b"foobar"[..]));
+        assert_eq!(base32_decode("MZXW 6YTB OI======").as_deref(), Some(&b"foobar"[..]));
+        assert_eq!(base32_decode("MZXW1"), None);
+    }
+
+    #[tokio::test]
+    async fn failed_attempts_keep_the_challenge_expiry() {
+        let cache = Cache::memory();
+        let token = create_challenge(&cache, 7).await.unwrap();
+        let raw = cache.get(&challenge_key(&token)).await.unwrap().unwrap();
+        let created: Challenge = serde_json::from_str(&raw).unwrap();
+
+        fail_challenge(&cache, &token).await.unwrap();
+        let raw = cache.get(&challenge_key(&token)).await.unwrap().unwrap();
+        let failed: Challenge = serde_json::from_str(&raw).unwrap();
+        assert_eq!(failed.attempts, 1);
+        assert_eq!(failed.expires_at, created.expires_at);
+        assert_eq!(challenge_admin(&cache, &token).await.unwrap(), Some(7));
+    }
+
+    #[tokio::test]
+    async fn challenge_is_dropped_after_too_many_attempts() {
+        let cache = Cache::memory();
+        let token = create_challenge(&cache, 7).await.unwrap();
+        for _ in 0..CHALLENGE_MAX_ATTEMPTS {
+            fail_challenge(&cache, &token).await.unwrap();
+        }
+        assert_eq!(challenge_admin(&cache, &token).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn expired_challenge_is_not_revived_by_a_failure() {
+        let cache = Cache::memory();
+        let stale = Challenge { admin_id: 7, attempts: 0, expires_at: chrono::Utc::now().timestamp() - 1 };
+        cache.set(&challenge_key("stale"), &serde_json::to_string(&stale).unwrap(), CHALLENGE_TTL).await.unwrap();
+        fail_challenge(&cache, "stale").await.unwrap();
+        assert_eq!(challenge_admin(&cache, "stale").await.unwrap(), None);
+    }
+}
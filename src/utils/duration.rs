@@ -0,0 +1,375 @@
+use chrono::{DateTime, Duration, FixedOffset, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use regex::Regex;
+use std::fmt;
+
+/// Why a ban/comm duration could not be turned into an expiry time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DurationError {
+    Empty,
+    /// Not a relative duration, ISO 8601 duration or `Until ...` timestamp
+    InvalidFormat(String),
+    UnknownUnit(String),
+    InvalidTimestamp(String),
+    InvalidTimezone(String),
+    /// Zero-length durations would expire immediately
+    Zero,
+    /// `Until ...` lies in the past
+    InPast(DateTime<Utc>),
+    OutOfRange,
+}
+
+impl fmt::Display for DurationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DurationError::Empty => write!(f, "Duration is empty"),
+            DurationError::InvalidFormat(s) => write!(
+                f,
+                "Invalid duration '{}': use 'permanent', e.g. '30m' / '1d12h' / '2mo', an ISO 8601 duration like 'P1DT12H', or 'Until YYYY-MM-DD HH:MM [+08:00]'",
+                s
+            ),
+            DurationError::UnknownUnit(u) => write!(f, "Unknown duration unit '{}' (use s, m, h, d, w, mo, y)", u),
+            DurationError::InvalidTimestamp(s) => write!(f, "Invalid timestamp '{}'", s),
+            DurationError::InvalidTimezone(s) => write!(f, "Invalid timezone '{}' (use e.g. +08:00, UTC+8 or Z)", s),
+            DurationError::Zero => write!(f, "Duration must be longer than zero"),
+            DurationError::InPast(t) => write!(f, "Expiry {} is in the past", t.to_rfc3339()),
+            DurationError::OutOfRange => write!(f, "Duration is out of range"),
+        }
+    }
+}
+
+impl std::error::Error for DurationError {}
+
+/// A relative duration. Months (and years) are kept apart so they are added on the
+/// calendar: 1mo from Jan 31 is Feb 28/29, not "30 days".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BanDuration {
+    pub months: u32,
+    pub fixed: Duration,
+}
+
+impl BanDuration {
+    pub fn add_to(&self, start: DateTime<Utc>) -> Result<DateTime<Utc>, DurationError> {
+        start
+            .checked_add_months(Months::new(self.months))
+            .and_then(|t| t.checked_add_signed(self.fixed))
+            .ok_or(DurationError::OutOfRange)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.months == 0 && self.fixed.is_zero()
+    }
+}
+
+/// Timezone for `Until ...` timestamps without an explicit offset.
+/// Configured with BAN_TIMEZONE (e.g. `+08:00`), defaults to UTC+8.
+pub fn default_offset() -> FixedOffset {
+    std::env::var("BAN_TIMEZONE")
+        .ok()
+        .and_then(|tz| parse_offset(&tz).ok())
+        .unwrap_or_else(|| FixedOffset::east_opt(8 * 3600).unwrap())
+}
+
+fn checked_units(value: i64, seconds_per_unit: i64) -> Result<Duration, DurationError> {
+    value
+        .checked_mul(seconds_per_unit)
+        .and_then(Duration::try_seconds)
+        .ok_or(DurationError::OutOfRange)
+}
+
+fn add_unit(total: &mut BanDuration, value: i64, unit: &str) -> Result<(), DurationError> {
+    let months = |n: i64| u32::try_from(n).map_err(|_| DurationError::OutOfRange);
+    let fixed = match unit {
+        "s" | "sec" => checked_units(value, 1)?,
+        "m" | "min" => checked_units(value, 60)?,
+        "h" => checked_units(value, 3600)?,
+        "d" => checked_units(value, 86400)?,
+        "w" => checked_units(value, 7 * 86400)?,
+        "mo" => {
+            total.months = total.months.checked_add(months(value)?).ok_or(DurationError::OutOfRange)?;
+            return Ok(());
+        }
+        "y" => {
+            let m = months(value)?.checked_mul(12).ok_or(DurationError::OutOfRange)?;
+            total.months = total.months.checked_add(m).ok_or(DurationError::OutOfRange)?;
+            return Ok(());
+        }
+        other => return Err(DurationError::UnknownUnit(other.to_string())),
+    };
+    total.fixed = total.fixed.checked_add(&fixed).ok_or(DurationError::OutOfRange)?;
+    Ok(())
+}
+
+/// `1d12h`, `30m`, `2mo`, `1y6mo`
+fn parse_compound(input: &str) -> Result<BanDuration, DurationError> {
+    let re = Regex::new(r"(\d+)([a-zA-Z]+)").unwrap();
+    let mut total = BanDuration { months: 0, fixed: Duration::zero() };
+    let mut consumed = 0;
+
+    for caps in re.captures_iter(input) {
+        let whole = caps.get(0).unwrap();
+        if whole.start() != consumed {
+            return Err(DurationError::InvalidFormat(input.to_string()));
+        }
+        consumed = whole.end();
+        let value: i64 = caps[1].parse().map_err(|_| DurationError::OutOfRange)?;
+        add_unit(&mut total, value, &caps[2])?;
+    }
+
+    if consumed == 0 || consumed != input.len() {
+        return Err(DurationError::InvalidFormat(input.to_string()));
+    }
+    Ok(total)
+}
+
+/// ISO 8601 durations: `P1Y2M10DT2H30M`, `PT45M`, `P2W`
+fn parse_iso8601(input: &str) -> Result<BanDuration, DurationError> {
+    let re = Regex::new(r"^P(?:(\d+)Y)?(?:(\d+)M)?(?:(\d+)W)?(?:(\d+)D)?(?:T(?:(\d+)H)?(?:(\d+)M)?(?:(\d+)S)?)?$").unwrap();
+    let caps = re.captures(input).ok_or_else(|| DurationError::InvalidFormat(input.to_string()))?;
+    if input.ends_with('T') || input == "P" {
+        return Err(DurationError::InvalidFormat(input.to_string()));
+    }
+
+    let mut total = BanDuration { months: 0, fixed: Duration::zero() };
+    for (group, unit) in [(1, "y"), (2, "mo"), (3, "w"), (4, "d"), (5, "h"), (6, "m"), (7, "s")] {
+        if let Some(m) = caps.get(group) {
+            let value: i64 = m.as_str().parse().map_err(|_| DurationError::OutOfRange)?;
+            add_unit(&mut total, value, unit)?;
+        }
+    }
+    Ok(total)
+}
+
+/// Parses a relative duration (compound or ISO 8601). `permanent` and `Until ...`
+/// are not durations; use [`calculate_expires_at`] for those.
+pub fn parse_duration(input: &str) -> Result<BanDuration, DurationError> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(DurationError::Empty);
+    }
+    let duration = if input.starts_with('P') {
+        parse_iso8601(input)?
+    } else {
+        parse_compound(input)?
+    };
+    if duration.is_zero() {
+        return Err(DurationError::Zero);
+    }
+    Ok(duration)
+}
+
+/// `+08:00`, `+0800`, `+8`, `UTC+8`, `GMT-05:30`, `Z`, `UTC`
+fn parse_offset(input: &str) -> Result<FixedOffset, DurationError> {
+    let input = input.trim();
+    if input.eq_ignore_ascii_case("z") || input.eq_ignore_ascii_case("utc") || input.eq_ignore_ascii_case("gmt") {
+        return Ok(FixedOffset::east_opt(0).unwrap());
+    }
+
+    let re = Regex::new(r"^(?i:UTC|GMT)?([+-])(\d{1,2})(?::?(\d{2}))?$").unwrap();
+    let caps = re.captures(input).ok_or_else(|| DurationError::InvalidTimezone(input.to_string()))?;
+    let hours: i32 = caps[2].parse().unwrap_or(99);
+    let minutes: i32 = caps.get(3).map_or(0, |m| m.as_str().parse().unwrap_or(99));
+    if hours > 14 || minutes > 59 {
+        return Err(DurationError::InvalidTimezone(input.to_string()));
+    }
+    let seconds = (hours * 3600 + minutes * 60) * if &caps[1] == "-" { -1 } else { 1 };
+    FixedOffset::east_opt(seconds).ok_or_else(|| DurationError::InvalidTimezone(input.to_string()))
+}
+
+/// `2026-01-01 12:00`, `2026-01-01T12:00:00+08:00`, `2026-01-01 12:00 UTC+8`, `2026-01-01`
+fn parse_timestamp(input: &str, default_tz: FixedOffset) -> Result<DateTime<Utc>, DurationError> {
+    let input = input.trim();
+    if let Ok(t) = DateTime::parse_from_rfc3339(input) {
+        return Ok(t.with_timezone(&Utc));
+    }
+
+    let re = Regex::new(r"^(\d{4}-\d{1,2}-\d{1,2})(?:[ T](\d{1,2}:\d{2}(?::\d{2})?))?\s*(.*)$").unwrap();
+    let caps = re.captures(input).ok_or_else(|| DurationError::InvalidTimestamp(input.to_string()))?;
+
+    let date = NaiveDate::parse_from_str(&caps[1], "%Y-%m-%d")
+        .map_err(|_| DurationError::InvalidTimestamp(input.to_string()))?;
+    let time = match caps.get(2) {
+        Some(t) => NaiveTime::parse_from_str(t.as_str(), "%H:%M:%S")
+            .or_else(|_| NaiveTime::parse_from_str(t.as_str(), "%H:%M"))
+            .map_err(|_| DurationError::InvalidTimestamp(input.to_string()))?,
+        None => NaiveTime::MIN,
+    };
+    let tz = match caps[3].trim() {
+        "" => default_tz,
+        tz => parse_offset(tz)?,
+    };
+
+    tz.from_local_datetime(&NaiveDateTime::new(date, time))
+        .single()
+        .map(|t| t.with_timezone(&Utc))
+        .ok_or_else(|| DurationError::InvalidTimestamp(input.to_string()))
+}
+
+/// A point in time as entered in search filters (`2026-01-01`, `2026-01-01 12:00 +08:00`, RFC 3339)
+pub fn parse_time(input: &str) -> Result<DateTime<Utc>, DurationError> {
+    parse_timestamp(input, default_offset())
+}
+
+/// Expiry for a duration string relative to `now`; `Ok(None)` means permanent
+pub fn expires_at_from(input: &str, now: DateTime<Utc>, default_tz: FixedOffset) -> Result<Option<DateTime<Utc>>, DurationError> {
+    let input = input.trim();
+    if input.eq_ignore_ascii_case("permanent") {
+        return Ok(None);
+    }
+
+    // 面板发送 "Until 2026-01-01 12:00"，未写时区时按 BAN_TIMEZONE（默认 UTC+8）解释
+    let until = input.get(..6).filter(|p| p.eq_ignore_ascii_case("until ")).map(|_| &input[6..]);
+    if let Some(timestamp) = until {
+        let t = parse_timestamp(timestamp, default_tz)?;
+        if t <= now {
+            return Err(DurationError::InPast(t));
+        }
+        return Ok(Some(t));
+    }
+
+    parse_duration(input)?.add_to(now).map(Some)
+}
+
+/// Expiry for a ban/comm duration starting now; `Ok(None)` means permanent
+pub fn calculate_expires_at(input: &str) -> Result<Option<DateTime<Utc>>, DurationError> {
+    expires_at_from(input, Utc::now(), default_offset())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn tz(hours: i32) -> FixedOffset {
+        FixedOffset::east_opt(hours * 3600).unwrap()
+    }
+
+    #[test]
+    fn parses_relative_durations() {
+        let cases = [
+            ("45s", 0, 45),
+            ("10sec", 0, 10),
+            ("30m", 0, 30 * 60),
+            ("5min", 0, 5 * 60),
+            ("1d12h", 0, 36 * 3600),
+            ("2w", 0, 14 * 86400),
+            ("2mo", 2, 0),
+            ("1y6mo", 18, 0),
+            ("1mo2d", 1, 2 * 86400),
+            (" 1h ", 0, 3600),
+            ("PT45M", 0, 45 * 60),
+            ("P2W", 0, 14 * 86400),
+            ("P1DT12H", 0, 36 * 3600),
+            ("P1Y2M10DT2H30M15S", 14, 10 * 86400 + 2 * 3600 + 30 * 60 + 15),
+        ];
+        for (input, months, seconds) in cases {
+            let d = parse_duration(input).unwrap_or_else(|e| panic!("{}: {}", input, e));
+            assert_eq!(d.months, months, "{}", input);
+            assert_eq!(d.fixed, Duration::seconds(seconds), "{}", input);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_durations() {
+        let cases = [
+            ("", DurationError::Empty),
+            ("   ", DurationError::Empty),
+            ("abc", DurationError::InvalidFormat("abc".into())),
+            ("d1", DurationError::InvalidFormat("d1".into())),
+            ("1d x", DurationError::InvalidFormat("1d x".into())),
+            ("10", DurationError::InvalidFormat("10".into())),
+            ("P", DurationError::InvalidFormat("P".into())),
+            ("PT", DurationError::InvalidFormat("PT".into())),
+            ("P1H", DurationError::InvalidFormat("P1H".into())),
+            ("5x", DurationError::UnknownUnit("x".into())),
+            ("1d5M", DurationError::UnknownUnit("M".into())),
+            ("0m", DurationError::Zero),
+            ("0d0h", DurationError::Zero),
+            ("PT0S", DurationError::Zero),
+            ("99999999999999999999d", DurationError::OutOfRange),
+            ("999999999999999d", DurationError::OutOfRange),
+            ("4294967296mo", DurationError::OutOfRange),
+            ("400000000y", DurationError::OutOfRange),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse_duration(input), Err(expected), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn adds_months_on_the_calendar() {
+        let cases = [
+            ("2026-01-31T10:00:00Z", "1mo", "2026-02-28T10:00:00Z"),
+            ("2028-01-31T10:00:00Z", "1mo", "2028-02-29T10:00:00Z"),
+            ("2026-03-31T10:00:00Z", "1mo", "2026-04-30T10:00:00Z"),
+            ("2028-02-29T10:00:00Z", "1y", "2029-02-28T10:00:00Z"),
+            ("2026-01-31T10:00:00Z", "1mo1d", "2026-03-01T10:00:00Z"),
+            ("2026-01-31T10:00:00Z", "P1M", "2026-02-28T10:00:00Z"),
+            ("2026-01-01T00:00:00Z", "30d", "2026-01-31T00:00:00Z"),
+        ];
+        for (start, input, expected) in cases {
+            let got = expires_at_from(input, utc(start), tz(8)).unwrap();
+            assert_eq!(got, Some(utc(expected)), "{} + {}", start, input);
+        }
+    }
+
+    #[test]
+    fn expiry_beyond_chrono_range_is_out_of_range() {
+        let now = utc("2026-01-01T00:00:00Z");
+        assert_eq!(expires_at_from("1000000y", now, tz(8)), Err(DurationError::OutOfRange));
+    }
+
+    #[test]
+    fn permanent_has_no_expiry() {
+        let now = utc("2026-01-01T00:00:00Z");
+        for input in ["permanent", "PERMANENT", " Permanent "] {
+            assert_eq!(expires_at_from(input, now, tz(8)), Ok(None), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn parses_until_timestamps_with_timezones() {
+        let now = utc("2026-01-01T00:00:00Z");
+        let cases = [
+            ("Until 2030-01-01 12:00", "2030-01-01T04:00:00Z"),
+            ("until 2030-01-01 12:00:30", "2030-01-01T04:00:30Z"),
+            ("Until 2030-01-01", "2029-12-31T16:00:00Z"),
+            ("Until 2030-01-01 12:00 UTC", "2030-01-01T12:00:00Z"),
+            ("Until 2030-01-01 12:00 Z", "2030-01-01T12:00:00Z"),
+            ("Until 2030-01-01 12:00 +0200", "2030-01-01T10:00:00Z"),
+            ("Until 2030-01-01 12:00 UTC+8", "2030-01-01T04:00:00Z"),
+            ("Until 2030-01-01 12:00 GMT-05:30", "2030-01-01T17:30:00Z"),
+            ("Until 2030-01-01T12:00:00+02:00", "2030-01-01T10:00:00Z"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(expires_at_from(input, now, tz(8)), Ok(Some(utc(expected))), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_until_timestamps() {
+        let now = utc("2026-01-01T00:00:00Z");
+        let cases = [
+            ("Until tomorrow", DurationError::InvalidTimestamp("tomorrow".into())),
+            ("Until 2030-02-30 12:00", DurationError::InvalidTimestamp("2030-02-30 12:00".into())),
+            ("Until 2030-01-01 25:00", DurationError::InvalidTimestamp("2030-01-01 25:00".into())),
+            ("Until 2030-01-01 12:00 +15", DurationError::InvalidTimezone("+15".into())),
+            ("Until 2030-01-01 12:00 +08:75", DurationError::InvalidTimezone("+08:75".into())),
+            ("Until 2030-01-01 12:00 Mars", DurationError::InvalidTimezone("Mars".into())),
+            ("Until 2025-12-31 12:00 UTC", DurationError::InPast(utc("2025-12-31T12:00:00Z"))),
+            ("Until 2026-01-01 08:00", DurationError::InPast(utc("2026-01-01T00:00:00Z"))),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(expires_at_from(input, now, tz(8)), Err(expected), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn parses_search_times() {
+        assert_eq!(parse_timestamp("2026-05-01 08:00 +08:00", tz(0)), Ok(utc("2026-05-01T00:00:00Z")));
+        assert_eq!(parse_timestamp("2026-05-01", tz(0)), Ok(utc("2026-05-01T00:00:00Z")));
+        assert!(parse_timestamp("05/01/2026", tz(0)).is_err());
+    }
+}
@@ -0,0 +1,215 @@
+use std::net::IpAddr;
+
+/// IPv4 ranges larger than a /16 need a super admin's confirmation
+const MAX_IPV4_HOST_BITS: u32 = 16;
+/// IPv6 ranges larger than a /32 need a super admin's confirmation
+const MAX_IPV6_HOST_BITS: u32 = 96;
+
+/// An inclusive address range. IPv4 addresses live in the IPv4-mapped IPv6 space
+/// (`::ffff:a.b.c.d`), so both families share one 16-byte key that sorts numerically
+/// and can be range-queried in MySQL (`ip_start <= ? AND ip_end >= ?`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpRange {
+    pub start: u128,
+    pub end: u128,
+    v4: bool,
+}
+
+fn to_u128(ip: IpAddr) -> (u128, bool) {
+    match ip {
+        IpAddr::V4(v4) => (u128::from(v4.to_ipv6_mapped()), true),
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => (u128::from(v4.to_ipv6_mapped()), true),
+            None => (u128::from(v6), false),
+        },
+    }
+}
+
+fn parse_addr(s: &str) -> Result<IpAddr, String> {
+    s.trim().parse().map_err(|_| format!("Invalid IP address '{}'", s.trim()))
+}
+
+/// 16-byte big-endian key for a single address
+pub fn key(ip: IpAddr) -> Vec<u8> {
+    to_u128(ip).0.to_be_bytes().to_vec()
+}
+
+/// Parses a player address, ignoring a trailing `:port` on IPv4
+pub fn parse_player_ip(s: &str) -> Option<IpAddr> {
+    let s = s.trim();
+    s.parse().ok().or_else(|| s.split(':').next()?.parse().ok())
+}
+
+impl IpRange {
+    /// Accepts a single address, CIDR notation (`10.0.0.0/24`, `2001:db8::/48`)
+    /// or an explicit range (`10.0.0.1-10.0.0.50`)
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let input = input.trim();
+
+        if let Some((addr, prefix)) = input.split_once('/') {
+            let (base, v4) = to_u128(parse_addr(addr)?);
+            let max = if v4 { 32 } else { 128 };
+            let prefix: u32 = prefix.trim().parse()
+                .ok()
+                .filter(|p| *p <= max)
+                .ok_or_else(|| format!("Invalid prefix length in '{}'", input))?;
+            let host_bits = max - prefix;
+            let mask = if host_bits == 128 { u128::MAX } else { (1u128 << host_bits) - 1 };
+            let start = base & !mask;
+            return Ok(IpRange { start, end: start | mask, v4 });
+        }
+
+        if let Some((from, to)) = input.split_once('-') {
+            let (start, v4) = to_u128(parse_addr(from)?);
+            let (end, end_v4) = to_u128(parse_addr(to)?);
+            if v4 != end_v4 {
+                return Err("Range start and end must be the same IP version".to_string());
+            }
+            if start > end {
+                return Err("Range start must not be after its end".to_string());
+            }
+            return Ok(IpRange { start, end, v4 });
+        }
+
+        let (ip, v4) = to_u128(parse_addr(input)?);
+        Ok(IpRange { start: ip, end: ip, v4 })
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let (ip, _) = to_u128(ip);
+        self.start <= ip && ip <= self.end
+    }
+
+    /// Number of bits needed to address every host in the range
+    fn host_bits(&self) -> u32 {
+        128 - (self.end - self.start).leading_zeros()
+    }
+
+    /// Whether the range is wide enough to need a super admin's confirmation
+    pub fn is_wide(&self) -> bool {
+        self.host_bits() > if self.v4 { MAX_IPV4_HOST_BITS } else { MAX_IPV6_HOST_BITS }
+    }
+
+    pub fn start_key(&self) -> Vec<u8> {
+        self.start.to_be_bytes().to_vec()
+    }
+
+    pub fn end_key(&self) -> Vec<u8> {
+        self.end.to_be_bytes().to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    fn v4(s: &str) -> u128 {
+        to_u128(ip(s)).0
+    }
+
+    fn v6(s: &str) -> u128 {
+        u128::from(s.parse::<std::net::Ipv6Addr>().unwrap())
+    }
+
+    #[test]
+    fn parses_addresses_cidrs_and_ranges() {
+        let cases = [
+            ("10.0.0.5", v4("10.0.0.5"), v4("10.0.0.5")),
+            (" 10.0.0.5 ", v4("10.0.0.5"), v4("10.0.0.5")),
+            ("::ffff:10.0.0.5", v4("10.0.0.5"), v4("10.0.0.5")),
+            ("10.0.0.77/24", v4("10.0.0.0"), v4("10.0.0.255")),
+            ("10.0.0.77/32", v4("10.0.0.77"), v4("10.0.0.77")),
+            ("10.0.0.0 / 8", v4("10.0.0.0"), v4("10.255.255.255")),
+            ("1.2.3.4/0", v4("0.0.0.0"), v4("255.255.255.255")),
+            ("10.0.0.1-10.0.0.50", v4("10.0.0.1"), v4("10.0.0.50")),
+            ("10.0.0.1 - 10.0.0.1", v4("10.0.0.1"), v4("10.0.0.1")),
+            ("2001:db8::1", v6("2001:db8::1"), v6("2001:db8::1")),
+            ("2001:db8:1:2::/48", v6("2001:db8:1::"), v6("2001:db8:1:ffff:ffff:ffff:ffff:ffff")),
+            ("2001:db8::1/128", v6("2001:db8::1"), v6("2001:db8::1")),
+            ("2001:db8::1/0", 0, u128::MAX),
+            ("::/0", 0, u128::MAX),
+        ];
+        for (input, start, end) in cases {
+            let range = IpRange::parse(input).unwrap_or_else(|e| panic!("{}: {}", input, e));
+            assert_eq!((range.start, range.end), (start, end), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_ranges() {
+        let cases = [
+            ("", "Invalid IP address ''"),
+            ("10.0.0", "Invalid IP address '10.0.0'"),
+            ("10.0.0.256", "Invalid IP address '10.0.0.256'"),
+            ("10.0.0.0/33", "Invalid prefix length in '10.0.0.0/33'"),
+            ("10.0.0.0/-1", "Invalid prefix length in '10.0.0.0/-1'"),
+            ("10.0.0.0/", "Invalid prefix length in '10.0.0.0/'"),
+            ("2001:db8::/129", "Invalid prefix length in '2001:db8::/129'"),
+            ("10.0.0.50-10.0.0.1", "Range start must not be after its end"),
+            ("2001:db8::ff-2001:db8::1", "Range start must not be after its end"),
+            ("10.0.0.1-2001:db8::1", "Range start and end must be the same IP version"),
+            ("10.0.0.1-", "Invalid IP address ''"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(IpRange::parse(input), Err(expected.to_string()), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn contains_checks_inclusive_bounds() {
+        let range = IpRange::parse("10.0.0.0/24").unwrap();
+        assert!(range.contains(ip("10.0.0.0")));
+        assert!(range.contains(ip("10.0.0.255")));
+        assert!(range.contains(ip("::ffff:10.0.0.9")));
+        assert!(!range.contains(ip("10.0.1.0")));
+        assert!(!range.contains(ip("9.255.255.255")));
+
+        // IPv4 ranges never match native IPv6 addresses and vice versa
+        let all_v4 = IpRange::parse("0.0.0.0/0").unwrap();
+        assert!(!all_v4.contains(ip("2001:db8::1")));
+        let v6 = IpRange::parse("2001:db8::/32").unwrap();
+        assert!(v6.contains(ip("2001:db8:ffff::1")));
+        assert!(!v6.contains(ip("10.0.0.1")));
+    }
+
+    #[test]
+    fn flags_wide_ranges() {
+        let cases = [
+            ("10.0.0.1", false),
+            ("10.0.0.0/16", false),
+            ("10.0.0.0/15", true),
+            ("10.0.0.0-10.0.255.255", false),
+            ("10.0.0.0-10.1.0.0", true),
+            ("0.0.0.0/0", true),
+            ("2001:db8::/32", false),
+            ("2001:db8::/31", true),
+            ("2001:db8::1/128", false),
+            ("::/0", true),
+        ];
+        for (input, wide) in cases {
+            assert_eq!(IpRange::parse(input).unwrap().is_wide(), wide, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn keys_sort_numerically() {
+        let a = key(ip("9.255.255.255"));
+        let b = key(ip("10.0.0.0"));
+        let c = key(ip("2001:db8::1"));
+        assert_eq!(a.len(), 16);
+        assert!(a < b && b < c);
+        assert_eq!(IpRange::parse("10.0.0.0/8").unwrap().start_key(), b);
+    }
+
+    #[test]
+    fn parses_player_addresses() {
+        assert_eq!(parse_player_ip("10.0.0.5:27005"), Some(ip("10.0.0.5")));
+        assert_eq!(parse_player_ip(" 10.0.0.5 "), Some(ip("10.0.0.5")));
+        assert_eq!(parse_player_ip("2001:db8::1"), Some(ip("2001:db8::1")));
+        assert_eq!(parse_player_ip("loopback"), None);
+    }
+}
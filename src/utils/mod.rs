@@ -1,56 +1,4 @@
-use chrono::{Duration, Utc, DateTime};
-use regex::Regex;
-
-pub fn parse_duration(duration_str: &str) -> Option<Duration> {
-    if duration_str == "permanent" || duration_str.starts_with("Until") {
-        return None; // Special handling elsewhere or infinite
-    }
-
-    let re = Regex::new(r"^(\d+)([a-zA-Z]+)$").unwrap();
-    if let Some(caps) = re.captures(duration_str) {
-        let value: i64 = caps[1].parse().ok()?;
-        let unit = &caps[2];
-
-        match unit {
-            "s" => Some(Duration::seconds(value)),
-            "m" => Some(Duration::minutes(value)),
-            "h" => Some(Duration::hours(value)),
-            "d" => Some(Duration::days(value)),
-            "mo" => Some(Duration::days(value * 30)), // Approx
-            "y" => Some(Duration::days(value * 365)), // Approx
-            _ => None
-        }
-    } else {
-        None
-    }
-}
-
-pub fn calculate_expires_at(duration_str: &str) -> Option<DateTime<Utc>> {
-    if duration_str == "permanent" {
-        return None;
-    }
-    // Handle "Until YYYY-MM-DD HH:MM" custom format if present
-    if duration_str.starts_with("Until ") {
-        // Simple parse attempt or frontend sends ISO? 
-        // Frontend sends "Until 2026-01-01 12:00"
-        let _date_str = &duration_str[6..];
-        // Naive parsing, assuming UTC or local? 
-        // Let's try to parse as naive and set to UTC.
-        // Actually better if frontend sends ISO8601, but we have text "Until ..."
-        // Let's implement robust parsing later if needed, for now try standard formats
-        // For this task, we assume standard durations mostly.
-        // If "Until", let's try strict format.
-        // For simplicity now, return None (manual handling or skip) if complex.
-        // But user wants "封禁时间+封禁时长".
-        return None; 
-    }
-
-    if let Some(duration) = parse_duration(duration_str) {
-        Some(Utc::now() + duration)
-    } else {
-        None
-    }
-}
+pub use duration::{calculate_expires_at, parse_duration};
 
 pub async fn log_admin_action(
     pool: &sqlx::MySqlPool,
@@ -69,4 +17,77 @@ pub async fn log_admin_action(
     Ok(())
 }
 
+/// 解析管理员的有效权限：角色默认权限 + 单独授予的权限。super_admin 拥有全部权限。
+pub async fn resolve_permissions(
+    pool: &sqlx::MySqlPool,
+    admin_id: i64,
+    role: &str,
+) -> Result<Vec<String>, sqlx::Error> {
+    if role == "super_admin" {
+        return Ok(crate::models::permission::ALL_PERMISSIONS
+            .iter()
+            .map(|(key, _)| key.to_string())
+            .collect());
+    }
+
+    let mut permissions: Vec<String> = sqlx::query_scalar(
+        "SELECT permission FROM role_permissions WHERE role = ? \
+         UNION SELECT permission FROM admin_permissions WHERE admin_id = ?"
+    )
+    .bind(role)
+    .bind(admin_id)
+    .fetch_all(pool)
+    .await?;
+
+    permissions.sort();
+    Ok(permissions)
+}
+
+/// 解析管理员可管理的服务器组。返回 `None` 表示全局（super_admin 或未标记为受限的管理员）。
+/// 受限管理员即使没有任何绑定行也返回 `Some(vec![])`，即无权访问任何组。
+pub async fn resolve_server_groups(
+    pool: &sqlx::MySqlPool,
+    admin_id: i64,
+    role: &str,
+) -> Result<Option<Vec<i64>>, sqlx::Error> {
+    if role == "super_admin" {
+        return Ok(None);
+    }
+
+    let scoped: bool = sqlx::query_scalar("SELECT server_scoped FROM admins WHERE id = ?")
+        .bind(admin_id)
+        .fetch_optional(pool)
+        .await?
+        .unwrap_or(true);
+    if !scoped {
+        return Ok(None);
+    }
+
+    let groups: Vec<i64> = sqlx::query_scalar("SELECT group_id FROM admin_server_groups WHERE admin_id = ? ORDER BY group_id")
+        .bind(admin_id)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(Some(groups))
+}
+
+/// 获取客户端 IP。仅当 TRUST_PROXY_HEADERS=true（部署在反向代理之后）时才信任 X-Forwarded-For / X-Real-IP。
+pub fn client_ip(headers: &axum::http::HeaderMap, addr: &std::net::SocketAddr) -> String {
+    let trust_proxy = std::env::var("TRUST_PROXY_HEADERS").map(|v| v == "true").unwrap_or(false);
+    if trust_proxy {
+        let forwarded = headers.get("x-forwarded-for")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.split(',').next())
+            .or_else(|| headers.get("x-real-ip").and_then(|v| v.to_str().ok()))
+            .map(|v| v.trim().to_string());
+        if let Some(ip) = forwarded.filter(|ip| !ip.is_empty()) {
+            return ip;
+        }
+    }
+    addr.ip().to_string()
+}
+
+pub mod duration;
+pub mod ip_range;
+pub mod password;
 pub mod rcon;
@@ -0,0 +1,115 @@
+use bcrypt::verify;
+use sqlx::MySqlPool;
+
+/// bcrypt only looks at the first 72 bytes
+const MAX_LENGTH: usize = 72;
+
+/// 密码策略，通过 PASSWORD_MIN_LENGTH / PASSWORD_HISTORY 配置
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    /// The new password may not match the current one or any of the last `history - 1` ones
+    pub history: usize,
+}
+
+impl PasswordPolicy {
+    pub fn from_env() -> Self {
+        let env = |key: &str, default: usize| {
+            std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+        };
+        PasswordPolicy {
+            min_length: env("PASSWORD_MIN_LENGTH", 8),
+            history: env("PASSWORD_HISTORY", 5),
+        }
+    }
+
+    pub fn validate(&self, password: &str) -> Result<(), String> {
+        if password.chars().count() < self.min_length {
+            return Err(format!("Password must be at least {} characters", self.min_length));
+        }
+        if password.len() > MAX_LENGTH {
+            return Err(format!("Password must be at most {} bytes", MAX_LENGTH));
+        }
+        Ok(())
+    }
+
+    /// Checks the new password against the admin's current and recent password hashes
+    pub async fn check_reuse(&self, pool: &MySqlPool, admin_id: i64, password: &str) -> Result<(), String> {
+        if self.history == 0 {
+            return Ok(());
+        }
+
+        let current: Option<String> = sqlx::query_scalar("SELECT password FROM admins WHERE id = ?")
+            .bind(admin_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let previous: Vec<String> = sqlx::query_scalar("SELECT password_hash FROM admin_password_history WHERE admin_id = ? ORDER BY id DESC LIMIT ?")
+            .bind(admin_id)
+            .bind(self.history.saturating_sub(1) as i64)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if current.iter().chain(previous.iter()).any(|h| verify(password, h).unwrap_or(false)) {
+            return Err(format!("Password must differ from the last {} passwords", self.history));
+        }
+        Ok(())
+    }
+}
+
+/// Moves the admin's current hash into the history and stores the new one.
+/// Older history entries beyond the policy window are dropped.
+pub async fn set_password(pool: &MySqlPool, admin_id: i64, new_hash: &str, must_change: bool) -> Result<(), sqlx::Error> {
+    let keep = PasswordPolicy::from_env().history.saturating_sub(1) as i64;
+    let mut tx = pool.begin().await?;
+    sqlx::query("INSERT INTO admin_password_history (admin_id, password_hash) SELECT id, password FROM admins WHERE id = ?")
+        .bind(admin_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("UPDATE admins SET password = ?, must_change_password = ? WHERE id = ?")
+        .bind(new_hash)
+        .bind(must_change)
+        .bind(admin_id)
+        .execute(&mut *tx)
+        .await?;
+    // MySQL 不支持在子查询中对同一张表使用 LIMIT，这里用派生表包一层
+    sqlx::query(
+        "DELETE FROM admin_password_history WHERE admin_id = ? AND id NOT IN (
+            SELECT id FROM (SELECT id FROM admin_password_history WHERE admin_id = ? ORDER BY id DESC LIMIT ?) AS recent
+        )"
+    )
+        .bind(admin_id)
+        .bind(admin_id)
+        .bind(keep)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(min_length: usize) -> PasswordPolicy {
+        PasswordPolicy { min_length, history: 5 }
+    }
+
+    #[test]
+    fn enforces_minimum_length_in_characters() {
+        assert!(policy(8).validate("1234567").is_err());
+        assert!(policy(8).validate("12345678").is_ok());
+        // 按字符而不是字节计数
+        assert!(policy(4).validate("密码测试").is_ok());
+        assert!(policy(5).validate("密码测试").is_err());
+        assert!(policy(0).validate("").is_ok());
+    }
+
+    #[test]
+    fn rejects_passwords_bcrypt_would_truncate() {
+        assert!(policy(8).validate(&"a".repeat(MAX_LENGTH)).is_ok());
+        assert!(policy(8).validate(&"a".repeat(MAX_LENGTH + 1)).is_err());
+        // 25 个三字节字符为 75 字节
+        assert!(policy(8).validate(&"密".repeat(25)).is_err());
+    }
+}